@@ -153,5 +153,8 @@ fn testnet_genesis(
 			// Assign network admin rights.
 			key: root_key,
 		}),
+		pallet_nft721: Some(Default::default()),
+		pallet_nft1155: Some(Default::default()),
+		pallet_nft2006: Some(Default::default()),
 	}
 }
@@ -13,7 +13,7 @@ use sp_runtime::DispatchResult;
 use sp_std::prelude::*;
 use utilities::{
     AIData, AIDataId, AIModel, AIModelHighlight, AIModelId, ClassId, CollectionId, DataIndustry,
-    DataResource, DataTechnology, ModelLanguage, NFT2006Manager, NFTId,
+    DataResource, DataTechnology, ModelLanguage, NFT2006Manager, NFTId, NFTStatus,
 };
 type MomentOf<T> = <<T as Config>::Time as Time>::Moment;
 type BalanceOf<T> =
@@ -303,3 +303,35 @@ impl<T: Config> Module<T> {
         nonce
     }
 }
+
+// Access
+impl<T: Config> Module<T> {
+    /// Creator, or the owner or current user of the bound NFT
+    pub fn can_use_ai_data(who: &T::AccountId, ai_data_id: AIDataId) -> bool {
+        match Self::ai_datas(ai_data_id) {
+            Some(ai_data) => match ai_data.nft_id {
+                Some(nft_id) => Self::_can_use_nft(who, nft_id),
+                None => ai_data.creator == *who,
+            },
+            None => false,
+        }
+    }
+
+    /// Creator, or the owner or current user of the bound NFT
+    pub fn can_use_ai_model(who: &T::AccountId, ai_model_id: AIModelId) -> bool {
+        match Self::ai_models(ai_model_id) {
+            Some(ai_model) => match ai_model.nft_id {
+                Some(nft_id) => Self::_can_use_nft(who, nft_id),
+                None => ai_model.creator == *who,
+            },
+            None => false,
+        }
+    }
+
+    fn _can_use_nft(who: &T::AccountId, nft_id: NFTId) -> bool {
+        let owned = T::NFT2006::get_nft(nft_id.clone())
+            .map(|nft| nft.owner == *who && nft.status != NFTStatus::Burned)
+            .unwrap_or(false);
+        owned || T::NFT2006::user_of(nft_id).as_ref() == Some(who)
+    }
+}
@@ -3,15 +3,31 @@
 use codec::Encode;
 use frame_support::{
     decl_error, decl_event, decl_module, decl_storage, ensure,
-    traits::{Currency, ExistenceRequirement, Get, Randomness},
-    StorageMap, StorageValue,
+    storage::{migration::remove_storage_prefix, StoragePrefixedMap},
+    traits::{BalanceStatus, Currency, ExistenceRequirement, Get, Randomness, ReservableCurrency},
+    transactional,
+    weights::Weight,
+    IterableStorageDoubleMap, IterableStorageMap, Parameter, StorageDoubleMap, StorageMap,
+    StorageValue,
 };
 use frame_system::ensure_signed;
-use randomness;
 use sp_io::hashing::blake2_256;
-use sp_runtime::DispatchResult;
-use sp_std::prelude::*;
-use utilities::{ClassId, ClassInfo, NFT1155Manager, NFTId, NFTInfo, NFTSource, NFTStatus};
+use sp_runtime::{
+    traits::{IdentifyAccount, SaturatedConversion, Saturating, Verify, Zero},
+    DispatchError, DispatchResult,
+};
+use sp_std::{
+    collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+    convert::TryInto,
+    prelude::*,
+};
+use utilities::{
+    helpers::{self, DropError, NestingError, PaymentSource, RentalError, RevealError},
+    Attribute, AttributeTarget, AttributeValue, ClassId, ClassInfo, ClassInfoV1, ClassRoles,
+    MintDrop, MintVoucher, MultiNonFungibleManager, MysteryBox, NFT1155Manager, NFTId, NFTInfo,
+    NFTStatus, NFTType, NFTUser, NonFungibleManager, Releases, RentOffer, RoyaltyInfo,
+    StorageDeposit, TransferPolicy,
+};
 
 #[cfg(test)]
 mod mock;
@@ -21,12 +37,37 @@ mod tests;
 
 pub trait Config: frame_system::Config {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
-    type Currency: Currency<Self::AccountId>;
+    type Currency: ReservableCurrency<Self::AccountId>;
+    type Signature: Verify<Signer = Self::PublicKey> + Parameter;
+    type PublicKey: IdentifyAccount<AccountId = Self::AccountId>;
+    /// Parents and children from every NFT standard, this pallet included
+    type NFT: MultiNonFungibleManager<Self::AccountId, BalanceOf<Self>>;
+    type MaxNestingDepth: Get<u32>;
+    /// Reserved for every attribute, plus `AttributeDepositPerByte` for its key and value
+    type AttributeDepositBase: Get<BalanceOf<Self>>;
+    type AttributeDepositPerByte: Get<BalanceOf<Self>>;
+    type AttributeKeyLimit: Get<u32>;
+    type AttributeValueLimit: Get<u32>;
+    /// Reserved per class and per NFT, plus `DepositPerByte` for the data they store
+    type ClassDepositBase: Get<BalanceOf<Self>>;
+    type NFTDepositBase: Get<BalanceOf<Self>>;
+    type DepositPerByte: Get<BalanceOf<Self>>;
+    /// Longest `name` or `symbol`
+    type NameLimit: Get<u32>;
+    /// Longest `info` or `metadata`
+    type InfoLimit: Get<u32>;
+    type MaxClassMinters: Get<u32>;
+    /// Most listing or user expiries scheduled for a single block
+    type MaxExpiriesPerBlock: Get<u32>;
+    /// Seeds ids and mystery box reveals
+    type Randomness: Randomness<Self::Hash>;
 }
 
 decl_event!(
     pub enum Event<T> where
         <T as frame_system::Config>::AccountId,
+        Balance = BalanceOf<T>,
+        BlockNumber = <T as frame_system::Config>::BlockNumber,
     {
         CreateClass(AccountId),
 
@@ -36,10 +77,30 @@ decl_event!(
 
         OfferNFT(AccountId),
 
+        CancelOfferNFT(AccountId),
+
+        OfferNFTExpired(NFTId),
+
         BuyNFT(AccountId),
 
         BurnNFT(AccountId),
 
+        RevokeNFT(AccountId),
+
+        UpdateUser(NFTId, AccountId, BlockNumber),
+
+        UserExpired(NFTId),
+
+        OfferRentNFT(AccountId),
+
+        CancelOfferRentNFT(AccountId),
+
+        RentNFT(AccountId),
+
+        RedeemVoucher(AccountId, NFTId),
+
+        RoyaltyPaid(NFTId, AccountId, Balance),
+
         TransferBatchNFT(AccountId),
         ApproveSingleNFT(AccountId),
 
@@ -58,6 +119,51 @@ decl_event!(
         DestroyCollection(AccountId),
 
         ApprovalForAll(AccountId, AccountId, bool),
+
+        MintBalance(AccountId, ClassId, u128),
+
+        BurnBalance(AccountId, ClassId, u128),
+
+        /// operator, from, to, class, amount
+        TransferSingle(AccountId, AccountId, AccountId, ClassId, u128),
+
+        /// operator, from, to, classes, amounts
+        TransferBatch(AccountId, AccountId, AccountId, Vec<ClassId>, Vec<u128>),
+
+        /// owner, child, parent type, parent
+        AttachNFT(AccountId, NFTId, NFTType, NFTId),
+
+        DetachNFT(AccountId, NFTId),
+
+        SetAttribute(AttributeTarget, Vec<u8>, AttributeValue),
+
+        ClearAttribute(AttributeTarget, Vec<u8>),
+
+        FreezeMetadata(AttributeTarget),
+
+        /// class, old owner, new owner
+        TransferClassOwnership(ClassId, AccountId, AccountId),
+
+        /// class, admin, freezer
+        SetClassRoles(ClassId, AccountId, AccountId),
+
+        SetClassMinters(ClassId, Vec<AccountId>),
+
+        UpdateClassMetadata(ClassId),
+
+        DestroyClass(AccountId, ClassId),
+
+        /// class, reveal block
+        SetMysteryBox(ClassId, BlockNumber),
+
+        RevealMysteryBox(ClassId),
+
+        SetMintDrop(ClassId),
+
+        RemoveMintDrop(ClassId),
+
+        /// buyer, class, amount
+        PublicMint(AccountId, ClassId, u128),
     }
 );
 
@@ -77,8 +183,93 @@ decl_error! {
         NotCollectionOwner,
         AlreadlyApproved,
         NFTNotForBuy,
+        InvalidExpiry,
+        NFTNotTransferable,
+        NFTNotRevocable,
+        NFTLocked,
+        NFTNotLocked,
+        NFTInUse,
+        TooManyExpiries,
+        NFTNotForRent,
+        InvalidDuration,
+        ClassNotExist,
+        VoucherExpired,
+        VoucherAlreadyUsed,
+        InvalidSignature,
         NFTAlreadyOwned,
         NFTNotOwned,
+        ClassNotFungible,
+        InsufficientBalance,
+        BatchLengthMismatch,
+        AmountOverflow,
+        NFTHasChildren,
+        NFTNotAttached,
+        NFTAlreadyAttached,
+        NestingCycle,
+        NestingTooDeep,
+        AttributeNotExist,
+        AttributeKeyTooLong,
+        AttributeValueTooLong,
+        MetadataFrozen,
+        NameTooLong,
+        InfoTooLong,
+        ClassNotEmpty,
+        TooManyMinters,
+        MysteryBoxExists,
+        MysteryBoxNotExist,
+        MysteryBoxRevealed,
+        InvalidRevealBlock,
+        RevealTooEarly,
+        MetadataHashMismatch,
+        MetadataLengthMismatch,
+        NotEnoughMetadata,
+        ClassNotUnique,
+        DropNotExist,
+        InvalidDropWindow,
+        DropNotOpen,
+        DropLimitReached,
+        NotInAllowlist,
+    }
+}
+
+impl<T: Config> From<DropError> for Error<T> {
+    fn from(error: DropError) -> Self {
+        match error {
+            DropError::NotOpen => Error::<T>::DropNotOpen,
+            DropError::NotInAllowlist => Error::<T>::NotInAllowlist,
+            DropError::LimitReached => Error::<T>::DropLimitReached,
+        }
+    }
+}
+
+impl<T: Config> From<RevealError> for Error<T> {
+    fn from(error: RevealError) -> Self {
+        match error {
+            RevealError::Revealed => Error::<T>::MysteryBoxRevealed,
+            RevealError::TooEarly => Error::<T>::RevealTooEarly,
+            RevealError::HashMismatch => Error::<T>::MetadataHashMismatch,
+            RevealError::LengthMismatch => Error::<T>::MetadataLengthMismatch,
+            RevealError::NotEnoughMetadata => Error::<T>::NotEnoughMetadata,
+        }
+    }
+}
+
+impl<T: Config> From<NestingError> for Error<T> {
+    fn from(error: NestingError) -> Self {
+        match error {
+            NestingError::NoPermission => Error::<T>::NoPermission,
+            NestingError::Cycle => Error::<T>::NestingCycle,
+            NestingError::TooDeep => Error::<T>::NestingTooDeep,
+        }
+    }
+}
+
+impl<T: Config> From<RentalError> for Error<T> {
+    fn from(error: RentalError) -> Self {
+        match error {
+            RentalError::InvalidDuration => Error::<T>::InvalidDuration,
+            RentalError::TooManyExpiries => Error::<T>::TooManyExpiries,
+        }
     }
 }
 type BalanceOf<T> =
@@ -91,12 +282,41 @@ decl_storage! {
         pub ClassInfos get(fn class_infos): map hasher(twox_64_concat) ClassId => Option<ClassInfo<T::AccountId>>;
         pub ClassCount get(fn class_count): u64;
         pub ClassIndex get(fn class_index): map hasher(blake2_128_concat) u64 => ClassId;
+        pub ClassNonces get(fn class_nonce): map hasher(twox_64_concat) ClassId => Option<u64>;
         pub ClassMintIndex get(fn class_mint_index): map hasher(blake2_128_concat) ClassId => u64;
+        pub ClassRoyalties get(fn class_royalties): map hasher(twox_64_concat) ClassId => Option<RoyaltyInfo<T::AccountId>>;
+        pub ClassDeposits get(fn class_deposits): map hasher(twox_64_concat) ClassId => Option<StorageDeposit<T::AccountId, BalanceOf<T>>>;
+        pub ClassRoleInfos get(fn class_role_infos): map hasher(twox_64_concat) ClassId => Option<ClassRoles<T::AccountId>>;
+        pub ClassMinters get(fn is_class_minter):
+        double_map hasher(twox_64_concat) ClassId, hasher(blake2_128_concat) T::AccountId => bool;
+        pub UsedVoucherNonces get(fn used_voucher_nonces):
+        double_map hasher(twox_64_concat) ClassId, hasher(twox_64_concat) u64 => bool;
+        pub UniqueClasses get(fn is_unique_class): map hasher(twox_64_concat) ClassId => bool;
+        pub ClassSupply get(fn class_supply): map hasher(twox_64_concat) ClassId => u128;
+
+        // Balance
+        pub Balances get(fn balances):
+        double_map hasher(twox_64_concat) ClassId, hasher(blake2_128_concat) T::AccountId => u128;
+
+        // Drop
+        pub MintDrops get(fn mint_drops): map hasher(twox_64_concat) ClassId => Option<MintDrop<BalanceOf<T>, T::BlockNumber>>;
+        pub DropMintCount get(fn drop_mint_count):
+        double_map hasher(twox_64_concat) ClassId, hasher(blake2_128_concat) T::AccountId => u32;
+
+        // Mystery box
+        pub MysteryBoxes get(fn mystery_boxes): map hasher(twox_64_concat) ClassId => Option<MysteryBox<T::BlockNumber>>;
 
         // NFT
         pub NFTInfos get(fn nft_infos): map hasher(twox_64_concat) NFTId => Option<NFTInfo<T::AccountId, BalanceOf<T>> >;
         pub NFTsCount get(fn nfts_count): u64;
         pub NFTsIndex get(fn nfts_index): map hasher(blake2_128_concat) u64 => NFTId;
+        pub NFTRoyalties get(fn nft_royalties): map hasher(twox_64_concat) NFTId => Option<RoyaltyInfo<T::AccountId>>;
+        pub NFTDeposits get(fn nft_deposits): map hasher(twox_64_concat) NFTId => Option<StorageDeposit<T::AccountId, BalanceOf<T>>>;
+        pub NFTListingExpiry get(fn nft_listing_expiry): map hasher(twox_64_concat) NFTId => Option<T::BlockNumber>;
+        pub ListingExpiries get(fn listing_expiries): map hasher(twox_64_concat) T::BlockNumber => Vec<NFTId>;
+        pub NFTUsers get(fn nft_users): map hasher(twox_64_concat) NFTId => Option<NFTUser<T::AccountId, T::BlockNumber>>;
+        pub UserExpiries get(fn user_expiries): map hasher(twox_64_concat) T::BlockNumber => Vec<NFTId>;
+        pub RentOffers get(fn rent_offers): map hasher(twox_64_concat) NFTId => Option<RentOffer<BalanceOf<T>, T::BlockNumber>>;
 
         pub NFTByClassIndex get(fn nft_by_class_index):
         double_map hasher(blake2_128_concat) ClassId, hasher(blake2_128_concat) u64 => Option<NFTId>;
@@ -106,9 +326,24 @@ decl_storage! {
         // TNonce
         pub TNonce get(fn tnonce): u64;
 
+        // Composition
+        pub NFTParents get(fn nft_parents): map hasher(twox_64_concat) NFTId => Option<(NFTType, NFTId)>;
+        pub NFTChildren get(fn nft_children):
+        double_map hasher(twox_64_concat) NFTId, hasher(twox_64_concat) (NFTType, NFTId) => ();
+
+        // Metadata
+        pub Attributes get(fn attributes):
+        double_map hasher(blake2_128_concat) AttributeTarget, hasher(blake2_128_concat) Vec<u8> => Option<Attribute<T::AccountId, BalanceOf<T>>>;
+        pub FrozenMetadata get(fn is_metadata_frozen): map hasher(blake2_128_concat) AttributeTarget => bool;
+
+        // Migration
+        pub PalletStorageVersion get(fn storage_version) build(|_| Releases::V3): Releases;
+
         // owned NFT
-        pub OwnedNFTIds get(fn owned_nft_ids): map hasher(blake2_128_concat) T::AccountId => Vec<NFTId>;
-        pub OwnedNFTSource get(fn owned_nft_source):  map hasher(twox_64_concat) T::AccountId => Vec<NFTSource<ClassId>>;
+        pub OwnedNFTIds: double_map hasher(blake2_128_concat) T::AccountId, hasher(twox_64_concat) NFTId => ();
+        pub OwnedNFTCount get(fn owned_nft_count): map hasher(blake2_128_concat) T::AccountId => u64;
+        pub OwnedClassNFTs: double_map hasher(blake2_128_concat) (T::AccountId, ClassId), hasher(twox_64_concat) u64 => ();
+        pub OwnedClassNFTCount get(fn owned_class_nft_count): map hasher(blake2_128_concat) (T::AccountId, ClassId) => u64;
 
         //Approvers
         pub NFTApprovers get(fn nft_approvers): map hasher(twox_64_concat) (T::AccountId, NFTId) => bool;
@@ -121,16 +356,55 @@ decl_module! {
         type Error = Error<T>;
         fn deposit_event() = default;
 
+        fn on_runtime_upgrade() -> Weight {
+            Self::migrate_to_v2().saturating_add(Self::migrate_to_v3())
+        }
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            let expired = <ListingExpiries<T>>::take(now);
+            let count = expired.len() as u64;
+
+            for nft_id in expired {
+                // the listing may have been cancelled or re-offered with another expiry
+                if Self::nft_listing_expiry(nft_id.clone()) == Some(now) {
+                    if let Some(mut nft) = Self::nft_infos(nft_id.clone()) {
+                        Self::_clear_listing(&nft_id, &mut nft);
+                        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+                        Self::deposit_event(RawEvent::OfferNFTExpired(nft_id));
+                    }
+                }
+            }
+
+            let expired_users = <UserExpiries<T>>::take(now);
+            let user_count = expired_users.len() as u64;
+
+            for nft_id in expired_users {
+                // the user may have been replaced after an earlier expiry
+                if Self::nft_users(nft_id.clone()).map(|info| info.expiry) == Some(now) {
+                    <NFTUsers<T>>::remove(nft_id.clone());
+                    Self::deposit_event(RawEvent::UserExpired(nft_id));
+                }
+            }
+
+            T::DbWeight::get().reads_writes(
+                2 + 2 * count + user_count,
+                2 + 2 * count + user_count,
+            )
+        }
+
         #[weight = 10_000 + T::DbWeight::get().reads_writes(1,4)]
         pub fn create_class(
             origin,
             name: Vec<u8>,
             info: Vec<u8>,
             total_supply: u64,
+            royalty: Option<RoyaltyInfo<T::AccountId>>,
+            transfer_policy: TransferPolicy,
+            unique: bool,
         ) {
             let who = ensure_signed(origin)?;
 
-            Self::_create_class(name, info, total_supply, who.clone())?;
+            Self::_create_class(name, info, total_supply, who.clone(), royalty, transfer_policy, unique)?;
 
             Self::deposit_event(RawEvent::CreateClass(who));
         }
@@ -142,10 +416,15 @@ decl_module! {
             info: Vec<u8>,
             metadata: Vec<u8>,
             price: BalanceOf<T>,
+            royalty: Option<RoyaltyInfo<T::AccountId>>,
         ) {
             let who = ensure_signed(origin)?;
+            Self::_ensure_class_minter(&who, &class_id)?;
+            Self::_ensure_nft_limits(&info, &metadata)?;
+            // fungible classes mint through `mint_balance`
+            ensure!(Self::is_unique_class(class_id.clone()), Error::<T>::ClassNotUnique);
 
-            Self::_mint_nft(class_id.clone(), info.clone(), metadata.clone(), price.clone(), who.clone());
+            Self::_mint_nft(class_id.clone(), info.clone(), metadata.clone(), price.clone(), who.clone(), royalty)?;
 
             Self::deposit_event(RawEvent::MintNFT(who));
 
@@ -159,6 +438,7 @@ decl_module! {
             nft_id: NFTId,
         ) {
             let who = ensure_signed(origin)?;
+            Self::_ensure_operator(&who, &from)?;
 
             Self::_transfer_single_nft( from.clone(), to.clone(), nft_id.clone())?;
 
@@ -171,15 +451,29 @@ decl_module! {
             origin,
             nft_id: NFTId,
             new_price: BalanceOf<T>,
+            expiry: Option<T::BlockNumber>,
         ) {
             let who = ensure_signed(origin)?;
 
-            Self::_offer_nft( who.clone(), nft_id.clone(), new_price.clone())?;
+            Self::_offer_nft( who.clone(), nft_id.clone(), new_price.clone(), expiry)?;
 
             Self::deposit_event(RawEvent::OfferNFT(who));
 
         }
 
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+        pub fn cancel_offer_nft(
+            origin,
+            nft_id: NFTId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_cancel_offer_nft(who.clone(), nft_id.clone())?;
+
+            Self::deposit_event(RawEvent::CancelOfferNFT(who));
+
+        }
+
         #[weight = 10_000 + T::DbWeight::get().reads_writes(1,4)]
          pub fn buy_nft(
             origin,
@@ -207,397 +501,1929 @@ decl_module! {
 
         }
 
-        #[weight = 10_000 + T::DbWeight::get().reads_writes(8,3)]
-        pub fn transfer_batch_nft(
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+        pub fn revoke_nft(
             origin,
-            to: T::AccountId,
-            class_id: ClassId,
-            amount: u64,
+            nft_id: NFTId,
         ) {
             let who = ensure_signed(origin)?;
 
-            Self::_transfer_batch_nft(who.clone(), to.clone(), class_id.clone(), amount.clone())?;
+            Self::_revoke_nft(who.clone(), nft_id.clone())?;
 
-            Self::deposit_event(RawEvent::TransferBatchNFT(who));
+            Self::deposit_event(RawEvent::RevokeNFT(who));
 
         }
 
-        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,1)]
-        pub fn approve_batch_nft(
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,6)]
+        pub fn redeem_voucher(
             origin,
-            to: T::AccountId,
-            class_id: ClassId,
-            amount: u64,
+            voucher: MintVoucher<BalanceOf<T>, T::BlockNumber>,
+            signature: T::Signature,
         ) {
             let who = ensure_signed(origin)?;
 
-            Self::_approve_batch_nft(who.clone(), to.clone(), class_id.clone(), amount.clone())?;
+            let nft_id = Self::_redeem_voucher(who.clone(), voucher, signature)?;
 
-            Self::deposit_event(RawEvent::ApproveBatchNFT(who));
+            Self::deposit_event(RawEvent::RedeemVoucher(who, nft_id));
 
         }
 
-        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,1)]
-        pub fn destroy_batch_nft(
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+        pub fn set_user(
             origin,
-            class_id: ClassId,
-            amount: u64,
+            nft_id: NFTId,
+            user: T::AccountId,
+            expiry: T::BlockNumber,
         ) {
             let who = ensure_signed(origin)?;
 
-            Self::_destroy_batch_nft(who.clone(), class_id.clone(), amount.clone())?;
+            Self::_set_user(who, nft_id.clone(), user.clone(), expiry)?;
 
-            Self::deposit_event(RawEvent::DestroyBatchNFT(who));
+            Self::deposit_event(RawEvent::UpdateUser(nft_id, user, expiry));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
+        pub fn offer_rent_nft(
+            origin,
+            nft_id: NFTId,
+            price_per_block: BalanceOf<T>,
+            max_duration: T::BlockNumber,
+        ) {
+            let who = ensure_signed(origin)?;
 
+            Self::_offer_rent_nft(who.clone(), nft_id, price_per_block, max_duration)?;
+
+            Self::deposit_event(RawEvent::OfferRentNFT(who));
         }
 
-    }
-}
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
+        pub fn cancel_offer_rent_nft(
+            origin,
+            nft_id: NFTId,
+        ) {
+            let who = ensure_signed(origin)?;
 
-// Class
-impl<T: Config> Module<T> {
-    fn _create_class(
-        name: Vec<u8>,
-        info: Vec<u8>,
-        total_supply: u64,
-        issuer: T::AccountId,
-    ) -> DispatchResult {
-        let nonce = Self::get_cnonce();
-        let random_seed = <randomness::Module<T>>::random_seed();
-        let encoded = (random_seed, issuer.clone(), nonce).encode();
-        let did = blake2_256(&encoded);
-        let new_class_id = ClassId { did };
-        let new_class = ClassInfo {
-            name: name.clone(),
-            info: info.clone(),
-            total_supply: total_supply.clone(),
-            issuer: issuer.clone(),
-        };
+            Self::_cancel_offer_rent_nft(who.clone(), nft_id)?;
 
-        <ClassInfos<T>>::insert(new_class_id.clone(), &new_class);
-        <ClassCount>::put(nonce.clone() + 1);
-        <ClassIndex>::insert(nonce.clone(), new_class_id.clone());
-        // Self::_add_class_to_class_list(new_class_id)?;
+            Self::deposit_event(RawEvent::CancelOfferRentNFT(who));
+        }
 
-        Ok(())
-    }
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,4)]
+        pub fn rent_nft(
+            origin,
+            nft_id: NFTId,
+            duration: T::BlockNumber,
+        ) {
+            let who = ensure_signed(origin)?;
 
-    // nonce
-    fn get_cnonce() -> u64 {
-        let nonce = <CNonce>::get();
-        <CNonce>::mutate(|n| *n += 1u64);
-        nonce
-    }
-}
+            let expiry = Self::_rent_nft(who.clone(), nft_id.clone(), duration)?;
 
-// NFT
-impl<T: Config> Module<T> {
-    fn _mint_nft(
-        class_id: ClassId,
-        info: Vec<u8>,
-        metadata: Vec<u8>,
-        price: BalanceOf<T>,
-        miner: T::AccountId,
-    ) -> Option<NFTId> {
-        if let Some(class_info) = Self::class_infos(class_id.clone()) {
-            let class_mint_index = Self::class_mint_index(class_id.clone()) + 1;
-            if class_info.total_supply >= class_mint_index {
-                let tnonce = Self::get_tnonce();
-                let random_seed = <randomness::Module<T>>::random_seed();
-                let encoded = (random_seed, miner.clone(), tnonce).encode();
-                let did = blake2_256(&encoded);
-                let new_nft_id = NFTId { did };
-
-                let new_nft = NFTInfo {
-                    class_id: class_id.clone(),
-                    index: class_mint_index.clone(),
-                    info: info.clone(),
-                    metadata: metadata.clone(),
-                    owner: miner.clone(),
-                    issuer: miner.clone(),
-                    price: price.clone(),
-                    status: NFTStatus::Normal,
-                };
-
-                <NFTInfos<T>>::insert(new_nft_id.clone(), &new_nft);
-                <NFTsCount>::put(tnonce.clone() + 1);
-                <NFTsIndex>::insert(tnonce.clone(), new_nft_id.clone());
-                <ClassMintIndex>::insert(class_id.clone(), class_mint_index.clone());
-                <NFTByClassIndex>::insert(
-                    class_id.clone(),
-                    class_mint_index.clone(),
-                    new_nft_id.clone(),
-                );
+            Self::deposit_event(RawEvent::UpdateUser(nft_id, who.clone(), expiry));
+            Self::deposit_event(RawEvent::RentNFT(who));
+        }
 
-                return Some(new_nft_id);
-            }
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,3)]
+        pub fn attach_nft(
+            origin,
+            nft_id: NFTId,
+            parent_type: NFTType,
+            parent_id: NFTId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_attach_nft(who.clone(), nft_id.clone(), (parent_type, parent_id.clone()))?;
+
+            Self::deposit_event(RawEvent::AttachNFT(who, nft_id, parent_type, parent_id));
         }
-        None
-    }
 
-    fn _transfer_single_nft(from: T::AccountId, to: T::AccountId, nft_id: NFTId) -> DispatchResult {
-        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
-        ensure!(nft.owner == from.clone(), Error::<T>::NotNFTOwner);
-        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,3)]
+        pub fn detach_nft(
+            origin,
+            nft_id: NFTId,
+        ) {
+            let who = ensure_signed(origin)?;
 
-        nft.owner = to.clone();
-        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
-        Self::_remove_nft_from_owned_nftids(from.clone(), nft_id.clone())?;
-        Self::_add_nft_to_owned_nftids(to.clone(), nft_id.clone())?;
+            Self::_detach_nft(who.clone(), nft_id.clone())?;
 
-        Ok(())
-    }
+            Self::deposit_event(RawEvent::DetachNFT(who, nft_id));
+        }
 
-    fn _offer_nft(from: T::AccountId, nft_id: NFTId, new_price: BalanceOf<T>) -> DispatchResult {
-        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
-        ensure!(nft.owner == from.clone(), Error::<T>::NotNFTOwner);
-        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,2)]
+        pub fn set_attribute(
+            origin,
+            target: AttributeTarget,
+            key: Vec<u8>,
+            value: AttributeValue,
+        ) {
+            let who = ensure_signed(origin)?;
 
-        nft.price = new_price.clone();
-        nft.status = NFTStatus::Offered;
-        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+            Self::_set_attribute(who, target.clone(), key.clone(), value.clone())?;
 
-        Ok(())
-    }
+            Self::deposit_event(RawEvent::SetAttribute(target, key, value));
+        }
 
-    fn _buy_nft(who: T::AccountId, nft_id: NFTId) -> DispatchResult {
-        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
-        let from = nft.owner.clone();
-        ensure!(nft.owner != who.clone(), Error::<T>::NoPermission);
-        ensure!(nft.status == NFTStatus::Offered, Error::<T>::NFTNotForBuy);
-        T::Currency::transfer(&who, &nft.owner, nft.price, ExistenceRequirement::KeepAlive)?;
-        nft.status = NFTStatus::Normal;
-        nft.owner = who.clone();
-        Self::_remove_nft_from_owned_nftids(from.clone(), nft_id.clone())?;
-        Self::_add_nft_to_owned_nftids(who.clone(), nft_id.clone())?;
-        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,2)]
+        pub fn clear_attribute(
+            origin,
+            target: AttributeTarget,
+            key: Vec<u8>,
+        ) {
+            let who = ensure_signed(origin)?;
 
-        Ok(())
-    }
+            Self::_clear_attribute(who, target.clone(), key.clone())?;
 
-    fn _burn_nft(who: T::AccountId, nft_id: NFTId) -> DispatchResult {
-        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
-        ensure!(nft.owner == who.clone(), Error::<T>::NoPermission);
-        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
-        nft.status = NFTStatus::Burned;
+            Self::deposit_event(RawEvent::ClearAttribute(target, key));
+        }
 
-        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2,1)]
+        pub fn freeze_metadata(
+            origin,
+            target: AttributeTarget,
+        ) {
+            let who = ensure_signed(origin)?;
 
-        Ok(())
-    }
+            Self::_freeze_metadata(who, target.clone())?;
 
-    // nonce
-    fn get_tnonce() -> u64 {
-        let nonce = <TNonce>::get();
-        <TNonce>::mutate(|n| *n += 1u64);
-        nonce
-    }
+            Self::deposit_event(RawEvent::FreezeMetadata(target));
+        }
 
-    fn _approve_single_nft(from: T::AccountId, to: T::AccountId, nft_id: NFTId) -> DispatchResult {
-        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
-        ensure!(nft.owner == from.clone(), Error::<T>::NoPermission);
-        ensure!(
-            Self::nft_approvers((to.clone(), nft_id.clone())) == false,
-            Error::<T>::AlreadlyApproved
-        );
-        <NFTApprovers<T>>::insert((to.clone(), nft_id.clone()), true);
-        Ok(())
-    }
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+        pub fn transfer_class_ownership(
+            origin,
+            class_id: ClassId,
+            new_owner: T::AccountId,
+        ) {
+            let who = ensure_signed(origin)?;
 
-    fn _transfer_batch_nft(
-        from: T::AccountId,
-        to: T::AccountId,
-        class_id: ClassId,
-        amount: u64,
-    ) -> DispatchResult {
-        let owned_nfts = Self::owned_nft_source(from.clone());
-        for i in 0..owned_nfts.len() {
-            if owned_nfts[i].class_id == class_id.clone() {
-                ensure!(
-                    owned_nfts[i].amount >= amount.clone(),
-                    Error::<T>::NotEnoughtNFT
-                );
-                for j in 0..owned_nfts[i].nfts_indexs.len() {
-                    let nft_id = Self::nft_by_class_index(
-                        class_id.clone(),
-                        owned_nfts[i].nfts_indexs[j].clone(),
-                    )
-                    .ok_or(Error::<T>::NFTNotExist)?;
-
-                    Self::_transfer_single_nft(from.clone(), to.clone(), nft_id)?;
-                }
-            }
+            Self::_transfer_class_ownership(who.clone(), class_id.clone(), new_owner.clone())?;
+
+            Self::deposit_event(RawEvent::TransferClassOwnership(class_id, who, new_owner));
         }
-        Ok(())
-    }
 
-    fn _approve_batch_nft(
-        who: T::AccountId,
-        to: T::AccountId,
-        class_id: ClassId,
-        amount: u64,
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
+        pub fn set_class_roles(
+            origin,
+            class_id: ClassId,
+            admin: T::AccountId,
+            freezer: T::AccountId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_set_class_roles(who, class_id.clone(), admin.clone(), freezer.clone())?;
+
+            Self::deposit_event(RawEvent::SetClassRoles(class_id, admin, freezer));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2, 1 + minters.len() as u64)]
+        pub fn set_class_minters(
+            origin,
+            class_id: ClassId,
+            minters: Vec<T::AccountId>,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_set_class_minters(who, class_id.clone(), minters.clone())?;
+
+            Self::deposit_event(RawEvent::SetClassMinters(class_id, minters));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,2)]
+        pub fn update_class_metadata(
+            origin,
+            class_id: ClassId,
+            name: Vec<u8>,
+            info: Vec<u8>,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_update_class_metadata(who, class_id.clone(), name, info)?;
+
+            Self::deposit_event(RawEvent::UpdateClassMetadata(class_id));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,6)]
+        pub fn destroy_class(
+            origin,
+            class_id: ClassId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_destroy_class(who.clone(), class_id.clone())?;
+
+            Self::deposit_event(RawEvent::DestroyClass(who, class_id));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,1)]
+        pub fn set_mystery_box(
+            origin,
+            class_id: ClassId,
+            metadata_hash: [u8; 32],
+            metadata_len: u64,
+            reveal_block: T::BlockNumber,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_set_mystery_box(who, class_id.clone(), metadata_hash, metadata_len, reveal_block)?;
+
+            Self::deposit_event(RawEvent::SetMysteryBox(class_id, reveal_block));
+        }
+
+        /// Anyone holding the committed metadata set may reveal once `reveal_block` is reached
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4 + metadata.len() as u64, 2 + metadata.len() as u64)]
+        pub fn reveal_mystery_box(
+            origin,
+            class_id: ClassId,
+            metadata: Vec<Vec<u8>>,
+        ) {
+            ensure_signed(origin)?;
+
+            Self::_commit_reveal_seed(&class_id)?;
+            Self::_reveal_mystery_box(class_id.clone(), metadata)?;
+
+            Self::deposit_event(RawEvent::RevealMysteryBox(class_id));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2,1)]
+        pub fn set_mint_drop(
+            origin,
+            class_id: ClassId,
+            drop: MintDrop<BalanceOf<T>, T::BlockNumber>,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_set_mint_drop(who, class_id.clone(), drop)?;
+
+            Self::deposit_event(RawEvent::SetMintDrop(class_id));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2,1)]
+        pub fn remove_mint_drop(
+            origin,
+            class_id: ClassId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_remove_mint_drop(who, class_id.clone())?;
+
+            Self::deposit_event(RawEvent::RemoveMintDrop(class_id));
+        }
+
+        /// Unique classes mint `amount` NFTs, fungible classes add `amount` to the balance
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(5 + proof.len() as u64, Self::_public_mint_writes(class_id, *amount))]
+        pub fn public_mint(
+            origin,
+            class_id: ClassId,
+            amount: u128,
+            proof: Vec<[u8; 32]>,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_public_mint(who.clone(), class_id.clone(), amount, proof)?;
+
+            Self::deposit_event(RawEvent::PublicMint(who, class_id, amount));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(8,3)]
+        pub fn transfer_batch_nft(
+            origin,
+            to: T::AccountId,
+            class_id: ClassId,
+            amount: u64,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_transfer_batch_nft(who.clone(), to.clone(), class_id.clone(), amount.clone())?;
+
+            Self::deposit_event(RawEvent::TransferBatchNFT(who));
+
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,1)]
+        pub fn approve_batch_nft(
+            origin,
+            to: T::AccountId,
+            class_id: ClassId,
+            amount: u64,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_approve_batch_nft(who.clone(), to.clone(), class_id.clone(), amount.clone())?;
+
+            Self::deposit_event(RawEvent::ApproveBatchNFT(who));
+
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,1)]
+        pub fn destroy_batch_nft(
+            origin,
+            class_id: ClassId,
+            amount: u64,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_destroy_batch_nft(who.clone(), class_id.clone(), amount.clone())?;
+
+            Self::deposit_event(RawEvent::DestroyBatchNFT(who));
+
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().writes(1)]
+        pub fn set_approval_for_all(
+            origin,
+            operator: T::AccountId,
+            approved: bool,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_set_approval_for_all(who.clone(), operator.clone(), approved)?;
+
+            Self::deposit_event(RawEvent::ApprovalForAll(who, operator, approved));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,2)]
+        pub fn mint_balance(
+            origin,
+            class_id: ClassId,
+            to: T::AccountId,
+            amount: u128,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_mint_balance(who, class_id.clone(), to.clone(), amount)?;
+
+            Self::deposit_event(RawEvent::MintBalance(to, class_id, amount));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,2)]
+        pub fn burn_balance(
+            origin,
+            class_id: ClassId,
+            amount: u128,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_burn_balance(who.clone(), class_id.clone(), amount)?;
+
+            Self::deposit_event(RawEvent::BurnBalance(who, class_id, amount));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,2)]
+        pub fn safe_transfer_from(
+            origin,
+            from: T::AccountId,
+            to: T::AccountId,
+            class_id: ClassId,
+            amount: u128,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_safe_transfer_from(who.clone(), from.clone(), to.clone(), class_id.clone(), amount)?;
+
+            Self::deposit_event(RawEvent::TransferSingle(who, from, to, class_id, amount));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,2).saturating_mul(class_ids.len() as u64)]
+        pub fn safe_batch_transfer_from(
+            origin,
+            from: T::AccountId,
+            to: T::AccountId,
+            class_ids: Vec<ClassId>,
+            amounts: Vec<u128>,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_safe_batch_transfer_from(who.clone(), from.clone(), to.clone(), class_ids.clone(), amounts.clone())?;
+
+            Self::deposit_event(RawEvent::TransferBatch(who, from, to, class_ids, amounts));
+        }
+
+    }
+}
+
+// Class
+impl<T: Config> Module<T> {
+    fn _create_class(
+        name: Vec<u8>,
+        info: Vec<u8>,
+        total_supply: u64,
+        issuer: T::AccountId,
+        royalty: Option<RoyaltyInfo<T::AccountId>>,
+        transfer_policy: TransferPolicy,
+        unique: bool,
+    ) -> DispatchResult {
+        Self::_ensure_class_limits(&name, &info)?;
+        let deposit =
+            Self::_reserve_deposit(&issuer, T::ClassDepositBase::get(), name.len() + info.len())?;
+
+        let nonce = Self::get_cnonce();
+        let random_seed = T::Randomness::random_seed();
+        let encoded = (random_seed, issuer.clone(), nonce).encode();
+        let did = blake2_256(&encoded);
+        let new_class_id = ClassId { did };
+        let new_class = ClassInfo {
+            name: name.clone(),
+            info: info.clone(),
+            total_supply: total_supply.clone(),
+            issuer: issuer.clone(),
+            transfer_policy,
+        };
+
+        <ClassInfos<T>>::insert(new_class_id.clone(), &new_class);
+        <ClassCount>::put(nonce.clone() + 1);
+        <ClassIndex>::insert(nonce.clone(), new_class_id.clone());
+        <ClassNonces>::insert(new_class_id.clone(), nonce);
+        <ClassDeposits<T>>::insert(new_class_id.clone(), deposit);
+        if let Some(royalty) = royalty {
+            <ClassRoyalties<T>>::insert(new_class_id.clone(), royalty);
+        }
+        if unique {
+            <UniqueClasses>::insert(new_class_id.clone(), true);
+        }
+        // Self::_add_class_to_class_list(new_class_id)?;
+
+        Ok(())
+    }
+
+    // nonce
+    fn get_cnonce() -> u64 {
+        let nonce = <CNonce>::get();
+        <CNonce>::mutate(|n| *n += 1u64);
+        nonce
+    }
+}
+
+// Class roles
+impl<T: Config> Module<T> {
+    /// Admin and freezer default to the class owner
+    fn _class_roles(
+        class_id: &ClassId,
+        class_info: &ClassInfo<T::AccountId>,
+    ) -> ClassRoles<T::AccountId> {
+        Self::class_role_infos(class_id).unwrap_or_else(|| ClassRoles {
+            admin: class_info.issuer.clone(),
+            freezer: class_info.issuer.clone(),
+        })
+    }
+
+    fn _ensure_class_owner(
+        who: &T::AccountId,
+        class_id: &ClassId,
+    ) -> Result<ClassInfo<T::AccountId>, DispatchError> {
+        let class_info = Self::class_infos(class_id).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(class_info.issuer == *who, Error::<T>::NoPermission);
+        Ok(class_info)
+    }
+
+    fn _ensure_class_admin(
+        who: &T::AccountId,
+        class_id: &ClassId,
+    ) -> Result<ClassInfo<T::AccountId>, DispatchError> {
+        let class_info = Self::class_infos(class_id).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(
+            class_info.issuer == *who || Self::_class_roles(class_id, &class_info).admin == *who,
+            Error::<T>::NoPermission
+        );
+        Ok(class_info)
+    }
+
+    fn _ensure_class_minter(who: &T::AccountId, class_id: &ClassId) -> DispatchResult {
+        let class_info = Self::class_infos(class_id).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(
+            class_info.issuer == *who || Self::is_class_minter(class_id, who),
+            Error::<T>::NoPermission
+        );
+        Ok(())
+    }
+
+    fn _ensure_class_limits(name: &[u8], info: &[u8]) -> DispatchResult {
+        ensure!(
+            name.len() <= T::NameLimit::get() as usize,
+            Error::<T>::NameTooLong
+        );
+        ensure!(
+            info.len() <= T::InfoLimit::get() as usize,
+            Error::<T>::InfoTooLong
+        );
+        Ok(())
+    }
+
+    /// The class deposit moves to the new owner
+    #[transactional]
+    fn _transfer_class_ownership(
+        who: T::AccountId,
+        class_id: ClassId,
+        new_owner: T::AccountId,
+    ) -> DispatchResult {
+        let mut class_info = Self::_ensure_class_owner(&who, &class_id)?;
+
+        if let Some(mut deposit) = Self::class_deposits(&class_id) {
+            T::Currency::repatriate_reserved(
+                &deposit.depositor,
+                &new_owner,
+                deposit.amount,
+                BalanceStatus::Reserved,
+            )?;
+            deposit.depositor = new_owner.clone();
+            <ClassDeposits<T>>::insert(&class_id, deposit);
+        }
+        class_info.issuer = new_owner;
+        <ClassInfos<T>>::insert(class_id, class_info);
+
+        Ok(())
+    }
+
+    fn _set_class_roles(
+        who: T::AccountId,
+        class_id: ClassId,
+        admin: T::AccountId,
+        freezer: T::AccountId,
+    ) -> DispatchResult {
+        Self::_ensure_class_owner(&who, &class_id)?;
+
+        <ClassRoleInfos<T>>::insert(class_id, ClassRoles { admin, freezer });
+
+        Ok(())
+    }
+
+    /// Replaces every minter of the class; the owner can always mint
+    fn _set_class_minters(
+        who: T::AccountId,
+        class_id: ClassId,
+        minters: Vec<T::AccountId>,
+    ) -> DispatchResult {
+        Self::_ensure_class_admin(&who, &class_id)?;
+        ensure!(
+            minters.len() <= T::MaxClassMinters::get() as usize,
+            Error::<T>::TooManyMinters
+        );
+
+        <ClassMinters<T>>::remove_prefix(&class_id);
+        for minter in minters {
+            <ClassMinters<T>>::insert(&class_id, minter, true);
+        }
+
+        Ok(())
+    }
+
+    /// The class owner pays the deposit for the new bytes
+    #[transactional]
+    fn _update_class_metadata(
+        who: T::AccountId,
+        class_id: ClassId,
+        name: Vec<u8>,
+        info: Vec<u8>,
+    ) -> DispatchResult {
+        let mut class_info = Self::_ensure_class_admin(&who, &class_id)?;
+        ensure!(
+            !Self::is_metadata_frozen(AttributeTarget::Class(class_id.clone())),
+            Error::<T>::MetadataFrozen
+        );
+        Self::_ensure_class_limits(&name, &info)?;
+
+        Self::_release_deposit(<ClassDeposits<T>>::take(&class_id));
+        let deposit = Self::_reserve_deposit(
+            &class_info.issuer,
+            T::ClassDepositBase::get(),
+            name.len() + info.len(),
+        )?;
+        class_info.name = name;
+        class_info.info = info;
+        <ClassInfos<T>>::insert(&class_id, class_info);
+        <ClassDeposits<T>>::insert(class_id, deposit);
+
+        Ok(())
+    }
+
+    /// Releases the class deposit and those of its attributes
+    #[transactional]
+    fn _destroy_class(who: T::AccountId, class_id: ClassId) -> DispatchResult {
+        Self::_ensure_class_owner(&who, &class_id)?;
+        ensure!(
+            Self::class_supply(&class_id) == 0,
+            Error::<T>::ClassNotEmpty
+        );
+
+        Self::_remove_metadata(&AttributeTarget::Class(class_id.clone()));
+        Self::_release_deposit(<ClassDeposits<T>>::take(&class_id));
+        <ClassInfos<T>>::remove(&class_id);
+        <ClassRoyalties<T>>::remove(&class_id);
+        <ClassRoleInfos<T>>::remove(&class_id);
+        <ClassMinters<T>>::remove_prefix(&class_id);
+        <MysteryBoxes<T>>::remove(&class_id);
+        <MintDrops<T>>::remove(&class_id);
+        <DropMintCount<T>>::remove_prefix(&class_id);
+        <UniqueClasses>::remove(&class_id);
+        <ClassSupply>::remove(&class_id);
+        if let Some(nonce) = <ClassNonces>::take(&class_id) {
+            <ClassIndex>::remove(nonce);
+        }
+        <ClassMintIndex>::remove(&class_id);
+        <NFTByClassIndex>::remove_prefix(&class_id);
+        <UsedVoucherNonces>::remove_prefix(&class_id);
+
+        Ok(())
+    }
+}
+
+// Drop
+impl<T: Config> Module<T> {
+    fn _set_mint_drop(
+        who: T::AccountId,
+        class_id: ClassId,
+        drop: MintDrop<BalanceOf<T>, T::BlockNumber>,
+    ) -> DispatchResult {
+        Self::_ensure_class_admin(&who, &class_id)?;
+        ensure!(drop.start < drop.end, Error::<T>::InvalidDropWindow);
+
+        <MintDrops<T>>::insert(class_id, drop);
+
+        Ok(())
+    }
+
+    /// Mint counts are kept, so a drop set again later doesn't reset the per-account cap
+    fn _remove_mint_drop(who: T::AccountId, class_id: ClassId) -> DispatchResult {
+        Self::_ensure_class_admin(&who, &class_id)?;
+        ensure!(
+            <MintDrops<T>>::contains_key(&class_id),
+            Error::<T>::DropNotExist
+        );
+
+        <MintDrops<T>>::remove(class_id);
+
+        Ok(())
+    }
+
+    /// Checks the window, the allowlist proof and the per-account cap, then pays the issuer
+    fn _charge_drop(
+        who: &T::AccountId,
+        class_id: &ClassId,
+        amount: u32,
+        proof: &[[u8; 32]],
+    ) -> DispatchResult {
+        let class_info = Self::class_infos(class_id).ok_or(Error::<T>::ClassNotExist)?;
+        let drop = Self::mint_drops(class_id).ok_or(Error::<T>::DropNotExist)?;
+        let minted = helpers::check_drop(
+            &drop,
+            &<frame_system::Module<T>>::block_number(),
+            who,
+            proof,
+            Self::drop_mint_count(class_id, who),
+            amount,
+        )
+        .map_err(Error::<T>::from)?;
+
+        <DropMintCount<T>>::insert(class_id, who, minted);
+        T::Currency::transfer(
+            who,
+            &class_info.issuer,
+            drop.price.saturating_mul(amount.into()),
+            ExistenceRequirement::KeepAlive,
+        )?;
+
+        Ok(())
+    }
+
+    #[transactional]
+    fn _public_mint(
+        who: T::AccountId,
+        class_id: ClassId,
+        amount: u128,
+        proof: Vec<[u8; 32]>,
+    ) -> DispatchResult {
+        let count: u32 = amount
+            .try_into()
+            .map_err(|_| Error::<T>::DropLimitReached)?;
+        Self::_charge_drop(&who, &class_id, count, &proof)?;
+
+        // the drop stands in for the minter role
+        if Self::is_unique_class(class_id.clone()) {
+            let class_info = Self::class_infos(&class_id).ok_or(Error::<T>::ClassNotExist)?;
+            for _ in 0..count {
+                let nft_id = Self::_do_mint_nft(
+                    class_id.clone(),
+                    Vec::new(),
+                    Vec::new(),
+                    Zero::zero(),
+                    who.clone(),
+                    None,
+                )?;
+                <NFTInfos<T>>::mutate(nft_id, |nft| {
+                    if let Some(nft) = nft {
+                        nft.issuer = class_info.issuer.clone();
+                    }
+                });
+            }
+            Ok(())
+        } else {
+            Self::_do_mint_balance(class_id, who, amount)
+        }
+    }
+
+    /// Only unique classes write per minted unit; `amount` beyond `u32` fails in `_public_mint`
+    fn _public_mint_writes(class_id: &ClassId, amount: u128) -> Weight {
+        if Self::is_unique_class(class_id) {
+            let count = amount.min(u32::MAX.into()) as Weight;
+            count.saturating_mul(5).saturating_add(3)
+        } else {
+            5
+        }
+    }
+}
+
+// Mystery box
+impl<T: Config> Module<T> {
+    fn _set_mystery_box(
+        who: T::AccountId,
+        class_id: ClassId,
+        metadata_hash: [u8; 32],
+        metadata_len: u64,
+        reveal_block: T::BlockNumber,
+    ) -> DispatchResult {
+        Self::_ensure_class_admin(&who, &class_id)?;
+        ensure!(
+            Self::is_unique_class(class_id.clone()),
+            Error::<T>::ClassNotUnique
+        );
+        ensure!(
+            !<MysteryBoxes<T>>::contains_key(&class_id),
+            Error::<T>::MysteryBoxExists
+        );
+        ensure!(
+            Self::class_mint_index(class_id.clone()) == 0,
+            Error::<T>::ClassNotEmpty
+        );
+        ensure!(
+            reveal_block > <frame_system::Module<T>>::block_number(),
+            Error::<T>::InvalidRevealBlock
+        );
+
+        <MysteryBoxes<T>>::insert(
+            class_id,
+            MysteryBox {
+                metadata_hash,
+                metadata_len,
+                reveal_block,
+                seed: None,
+                revealed: false,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// The first attempt at or after `reveal_block` fixes the seed, and keeps it even when the
+    /// reveal itself fails, so the seed can't be picked by retrying in a later block
+    fn _commit_reveal_seed(class_id: &ClassId) -> DispatchResult {
+        let mut mystery_box =
+            Self::mystery_boxes(class_id).ok_or(Error::<T>::MysteryBoxNotExist)?;
+        if mystery_box.seed.is_none()
+            && <frame_system::Module<T>>::block_number() >= mystery_box.reveal_block
+        {
+            let random = T::Randomness::random(&(b"NFT1155/reveal", class_id).encode());
+            mystery_box.seed = Some(blake2_256(&random.encode()));
+            <MysteryBoxes<T>>::insert(class_id, mystery_box);
+        }
+
+        Ok(())
+    }
+
+    /// Shuffles the committed set and gives every minted NFT one entry.
+    /// The class owner pays the deposit for the revealed bytes.
+    #[transactional]
+    fn _reveal_mystery_box(class_id: ClassId, mut metadata: Vec<Vec<u8>>) -> DispatchResult {
+        let class_info = Self::class_infos(&class_id).ok_or(Error::<T>::ClassNotExist)?;
+        let mut mystery_box =
+            Self::mystery_boxes(&class_id).ok_or(Error::<T>::MysteryBoxNotExist)?;
+        let minted = Self::class_mint_index(class_id.clone());
+        helpers::check_reveal(
+            &mystery_box,
+            &<frame_system::Module<T>>::block_number(),
+            &metadata,
+            minted,
+        )
+        .map_err(Error::<T>::from)?;
+        let seed = mystery_box.seed.ok_or(Error::<T>::RevealTooEarly)?;
+
+        helpers::shuffle_metadata(&seed, &mut metadata, minted);
+        let mut bytes = 0;
+        for (i, entry) in metadata.into_iter().take(minted as usize).enumerate() {
+            Self::_ensure_nft_limits(&[], &entry)?;
+            bytes += entry.len();
+            if let Some(nft_id) = Self::nft_by_class_index(&class_id, i as u64 + 1) {
+                <NFTInfos<T>>::mutate(nft_id, |nft| {
+                    if let Some(nft) = nft {
+                        nft.metadata = entry;
+                    }
+                });
+            }
+        }
+
+        let deposit = Self::_reserve_deposit(&class_info.issuer, Zero::zero(), bytes)?;
+        <ClassDeposits<T>>::mutate(&class_id, |stored| match stored {
+            Some(stored) => stored.amount = stored.amount.saturating_add(deposit.amount),
+            None => *stored = Some(deposit),
+        });
+        mystery_box.revealed = true;
+        <MysteryBoxes<T>>::insert(class_id, mystery_box);
+
+        Ok(())
+    }
+}
+
+// NFT
+impl<T: Config> Module<T> {
+    /// Only the class owner and its minters may mint
+    fn _mint_nft(
+        class_id: ClassId,
+        info: Vec<u8>,
+        metadata: Vec<u8>,
+        price: BalanceOf<T>,
+        miner: T::AccountId,
+        royalty: Option<RoyaltyInfo<T::AccountId>>,
+    ) -> Result<NFTId, DispatchError> {
+        Self::_ensure_class_minter(&miner, &class_id)?;
+
+        Self::_do_mint_nft(class_id, info, metadata, price, miner, royalty)
+    }
+
+    fn _do_mint_nft(
+        class_id: ClassId,
+        info: Vec<u8>,
+        metadata: Vec<u8>,
+        price: BalanceOf<T>,
+        miner: T::AccountId,
+        royalty: Option<RoyaltyInfo<T::AccountId>>,
+    ) -> Result<NFTId, DispatchError> {
+        let class_info = Self::class_infos(class_id.clone()).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(
+            Self::is_unique_class(class_id.clone()),
+            Error::<T>::ClassNotUnique
+        );
+        let class_mint_index = Self::class_mint_index(class_id.clone()) + 1;
+        ensure!(
+            class_info.total_supply >= class_mint_index,
+            Error::<T>::IndexExceedTotalSupply
+        );
+        if let Some(mystery_box) = Self::mystery_boxes(&class_id) {
+            // a revealed box has handed out its metadata set
+            ensure!(!mystery_box.revealed, Error::<T>::MysteryBoxRevealed);
+            ensure!(
+                class_mint_index <= mystery_box.metadata_len,
+                Error::<T>::NotEnoughMetadata
+            );
+        }
+        Self::_ensure_nft_limits(&info, &metadata)?;
+        let deposit = Self::_reserve_deposit(
+            &miner,
+            T::NFTDepositBase::get(),
+            info.len() + metadata.len(),
+        )?;
+
+        let tnonce = Self::get_tnonce();
+        let random_seed = T::Randomness::random_seed();
+        let encoded = (random_seed, miner.clone(), tnonce).encode();
+        let did = blake2_256(&encoded);
+        let new_nft_id = NFTId { did };
+
+        let new_nft = NFTInfo {
+            class_id: class_id.clone(),
+            index: class_mint_index.clone(),
+            info: info.clone(),
+            metadata: metadata.clone(),
+            owner: miner.clone(),
+            issuer: miner.clone(),
+            price: price.clone(),
+            status: NFTStatus::Normal,
+        };
+
+        <NFTInfos<T>>::insert(new_nft_id.clone(), &new_nft);
+        <NFTsCount>::put(tnonce.clone() + 1);
+        <NFTsIndex>::insert(tnonce.clone(), new_nft_id.clone());
+        <ClassMintIndex>::insert(class_id.clone(), class_mint_index.clone());
+        <ClassSupply>::mutate(&class_id, |supply| *supply += 1);
+        <NFTByClassIndex>::insert(
+            class_id.clone(),
+            class_mint_index.clone(),
+            new_nft_id.clone(),
+        );
+        if let Some(royalty) = royalty {
+            <NFTRoyalties<T>>::insert(new_nft_id.clone(), royalty);
+        }
+        <NFTDeposits<T>>::insert(new_nft_id.clone(), deposit);
+        let _ = Self::_add_nft_to_owned_nftids(miner.clone(), new_nft_id.clone());
+        let _ =
+            Self::_add_nft_to_owned_nft_sources(miner.clone(), class_id.clone(), class_mint_index);
+
+        Ok(new_nft_id)
+    }
+
+    #[transactional]
+    fn _transfer_single_nft(from: T::AccountId, to: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == from.clone(), Error::<T>::NotNFTOwner);
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        ensure!(nft.status != NFTStatus::Locked, Error::<T>::NFTLocked);
+        Self::_ensure_transferable(&nft.class_id, &from, &to)?;
+
+        Self::_clear_listing(&nft_id, &mut nft);
+        <RentOffers<T>>::remove(nft_id.clone());
+        nft.owner = to.clone();
+        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+        Self::_remove_nft_from_owned_nftids(from.clone(), nft_id.clone())?;
+        Self::_add_nft_to_owned_nftids(to.clone(), nft_id.clone())?;
+        Self::_remove_nft_from_owned_nft_sources(from.clone(), nft.class_id.clone(), nft.index)?;
+        Self::_add_nft_to_owned_nft_sources(to.clone(), nft.class_id.clone(), nft.index)?;
+
+        Self::_move_children(&nft_id, &from, &to)?;
+
+        Ok(())
+    }
+
+    #[transactional]
+    fn _offer_nft(
+        from: T::AccountId,
+        nft_id: NFTId,
+        new_price: BalanceOf<T>,
+        expiry: Option<T::BlockNumber>,
+    ) -> DispatchResult {
+        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == from.clone(), Error::<T>::NotNFTOwner);
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        let class_info =
+            Self::class_infos(nft.class_id.clone()).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(class_info.can_offer(&from), Error::<T>::NFTNotTransferable);
+        ensure!(nft.status != NFTStatus::Locked, Error::<T>::NFTLocked);
+        if let Some(expiry) = expiry {
+            ensure!(
+                expiry > <frame_system::Module<T>>::block_number(),
+                Error::<T>::InvalidExpiry
+            );
+        }
+
+        nft.price = new_price.clone();
+        nft.status = NFTStatus::Offered;
+        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+
+        // a re-offer replaces the pending expiry instead of adding a second one
+        Self::_remove_listing_expiry(&nft_id);
+        if let Some(expiry) = expiry {
+            <ListingExpiries<T>>::try_mutate(expiry, |nft_ids| -> DispatchResult {
+                ensure!(
+                    (nft_ids.len() as u32) < T::MaxExpiriesPerBlock::get(),
+                    Error::<T>::TooManyExpiries
+                );
+                nft_ids.push(nft_id.clone());
+                Ok(())
+            })?;
+            <NFTListingExpiry<T>>::insert(nft_id.clone(), expiry);
+        }
+
+        Ok(())
+    }
+
+    fn _cancel_offer_nft(from: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == from.clone(), Error::<T>::NotNFTOwner);
+        ensure!(nft.status == NFTStatus::Offered, Error::<T>::NFTNotForBuy);
+
+        Self::_clear_listing(&nft_id, &mut nft);
+        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+
+        Ok(())
+    }
+
+    fn _clear_listing(nft_id: &NFTId, nft: &mut NFTInfo<T::AccountId, BalanceOf<T>>) {
+        if nft.status == NFTStatus::Offered {
+            nft.status = NFTStatus::Normal;
+        }
+        Self::_remove_listing_expiry(nft_id);
+    }
+
+    fn _remove_listing_expiry(nft_id: &NFTId) {
+        if let Some(expiry) = <NFTListingExpiry<T>>::take(nft_id) {
+            <ListingExpiries<T>>::mutate_exists(expiry, |nft_ids| {
+                if let Some(ids) = nft_ids {
+                    ids.retain(|id| id != nft_id);
+                    if ids.is_empty() {
+                        *nft_ids = None;
+                    }
+                }
+            });
+        }
+    }
+
+    #[transactional]
+    fn _buy_nft(who: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        let from = nft.owner.clone();
+        ensure!(nft.owner != who.clone(), Error::<T>::NoPermission);
+        ensure!(nft.status == NFTStatus::Offered, Error::<T>::NFTNotForBuy);
+        Self::_ensure_transferable(&nft.class_id, &from, &who)?;
+        Self::_pay_with_royalty(who.clone(), from.clone(), nft_id.clone(), nft.price)?;
+        Self::_clear_listing(&nft_id, &mut nft);
+        <RentOffers<T>>::remove(nft_id.clone());
+        nft.owner = who.clone();
+        Self::_remove_nft_from_owned_nftids(from.clone(), nft_id.clone())?;
+        Self::_add_nft_to_owned_nftids(who.clone(), nft_id.clone())?;
+        Self::_remove_nft_from_owned_nft_sources(from.clone(), nft.class_id.clone(), nft.index)?;
+        Self::_add_nft_to_owned_nft_sources(who.clone(), nft.class_id.clone(), nft.index)?;
+        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+        Self::_move_children(&nft_id, &from, &who)?;
+
+        Ok(())
+    }
+
+    /// The issuer signs `("NFT1155", voucher)` so a voucher can't be replayed on another pallet.
+    #[transactional]
+    fn _redeem_voucher(
+        who: T::AccountId,
+        voucher: MintVoucher<BalanceOf<T>, T::BlockNumber>,
+        signature: T::Signature,
+    ) -> Result<NFTId, DispatchError> {
+        let class_info =
+            Self::class_infos(voucher.class_id.clone()).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(
+            voucher.expiry > <frame_system::Module<T>>::block_number(),
+            Error::<T>::VoucherExpired
+        );
+        ensure!(
+            !Self::used_voucher_nonces(voucher.class_id.clone(), voucher.nonce),
+            Error::<T>::VoucherAlreadyUsed
+        );
+        let payload = (b"NFT1155", &voucher).encode();
+        ensure!(
+            signature.verify(&payload[..], &class_info.issuer),
+            Error::<T>::InvalidSignature
+        );
+
+        Self::_ensure_nft_limits(&[], &voucher.metadata)?;
+
+        // the issuer's signature stands in for the minter role
+        let nft_id = Self::_do_mint_nft(
+            voucher.class_id.clone(),
+            Vec::new(),
+            voucher.metadata.clone(),
+            voucher.price,
+            who.clone(),
+            None,
+        )?;
+        <NFTInfos<T>>::mutate(nft_id.clone(), |nft| {
+            if let Some(nft) = nft {
+                nft.issuer = class_info.issuer.clone();
+            }
+        });
+        <UsedVoucherNonces>::insert(voucher.class_id, voucher.nonce, true);
+
+        T::Currency::transfer(
+            &who,
+            &class_info.issuer,
+            voucher.price,
+            ExistenceRequirement::KeepAlive,
+        )?;
+
+        Ok(nft_id)
+    }
+
+    fn _ensure_transferable(
+        class_id: &ClassId,
+        from: &T::AccountId,
+        to: &T::AccountId,
+    ) -> DispatchResult {
+        let class_info = Self::class_infos(class_id).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(
+            class_info.can_transfer(from, to),
+            Error::<T>::NFTNotTransferable
+        );
+        Ok(())
+    }
+
+    fn _revoke_nft(who: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        let class_info =
+            Self::class_infos(nft.class_id.clone()).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(class_info.issuer == who, Error::<T>::NoPermission);
+        ensure!(
+            class_info.transfer_policy != TransferPolicy::Transferable,
+            Error::<T>::NFTNotRevocable
+        );
+
+        Self::_burn_nft(nft.owner, nft_id)
+    }
+
+    fn _royalty_of(nft_id: NFTId) -> Option<RoyaltyInfo<T::AccountId>> {
+        Self::nft_royalties(nft_id.clone())
+            .or_else(|| Self::nft_infos(nft_id).and_then(|nft| Self::class_royalties(nft.class_id)))
+    }
+
+    #[transactional]
+    fn _pay_with_royalty(
+        buyer: T::AccountId,
+        seller: T::AccountId,
+        nft_id: NFTId,
+        price: BalanceOf<T>,
+    ) -> DispatchResult {
+        let royalty = Self::_royalty_of(nft_id.clone());
+        if let Some((beneficiary, amount)) = helpers::pay_with_royalty::<_, T::Currency>(
+            PaymentSource::Free,
+            &buyer,
+            &seller,
+            price,
+            royalty,
+        )? {
+            Self::deposit_event(RawEvent::RoyaltyPaid(nft_id, beneficiary, amount));
+        }
+
+        Ok(())
+    }
+
+    fn _burn_nft(who: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == who.clone(), Error::<T>::NoPermission);
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        ensure!(nft.status != NFTStatus::Locked, Error::<T>::NFTLocked);
+        ensure!(!Self::_has_children(&nft_id), Error::<T>::NFTHasChildren);
+        Self::_clear_listing(&nft_id, &mut nft);
+        <RentOffers<T>>::remove(nft_id.clone());
+        if let Some(info) = <NFTUsers<T>>::take(&nft_id) {
+            <UserExpiries<T>>::mutate_exists(info.expiry, |nft_ids| {
+                if let Some(ids) = nft_ids {
+                    ids.retain(|id| id != &nft_id);
+                    if ids.is_empty() {
+                        *nft_ids = None;
+                    }
+                }
+            });
+        }
+        Self::_remove_nft_from_owned_nftids(who.clone(), nft_id.clone())?;
+        Self::_remove_nft_from_owned_nft_sources(who.clone(), nft.class_id.clone(), nft.index)?;
+        Self::_release_deposit(<NFTDeposits<T>>::take(&nft_id));
+        Self::_remove_metadata(&AttributeTarget::NFT(nft_id.clone()));
+        nft.status = NFTStatus::Burned;
+        <ClassSupply>::mutate(&nft.class_id, |supply| *supply = supply.saturating_sub(1));
+
+        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+
+        Ok(())
+    }
+
+    // nonce
+    fn get_tnonce() -> u64 {
+        let nonce = <TNonce>::get();
+        <TNonce>::mutate(|n| *n += 1u64);
+        nonce
+    }
+
+    fn _ensure_nft_limits(info: &[u8], metadata: &[u8]) -> DispatchResult {
+        ensure!(
+            info.len() <= T::InfoLimit::get() as usize,
+            Error::<T>::InfoTooLong
+        );
+        ensure!(
+            metadata.len() <= T::InfoLimit::get() as usize,
+            Error::<T>::InfoTooLong
+        );
+        Ok(())
+    }
+
+    fn _approve_single_nft(from: T::AccountId, to: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == from.clone(), Error::<T>::NoPermission);
+        ensure!(
+            Self::nft_approvers((to.clone(), nft_id.clone())) == false,
+            Error::<T>::AlreadlyApproved
+        );
+        <NFTApprovers<T>>::insert((to.clone(), nft_id.clone()), true);
+        Ok(())
+    }
+
+    fn _transfer_batch_nft(
+        from: T::AccountId,
+        to: T::AccountId,
+        class_id: ClassId,
+        amount: u64,
+    ) -> DispatchResult {
+        let class_info = Self::class_infos(class_id.clone()).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(
+            class_info.can_transfer(&from, &to),
+            Error::<T>::NFTNotTransferable
+        );
+        for nft_id in Self::_owned_class_nfts(from.clone(), class_id, amount)? {
+            Self::_transfer_single_nft(from.clone(), to.clone(), nft_id)?;
+        }
+        Ok(())
+    }
+
+    fn _approve_batch_nft(
+        who: T::AccountId,
+        to: T::AccountId,
+        class_id: ClassId,
+        amount: u64,
+    ) -> DispatchResult {
+        for nft_id in Self::_owned_class_nfts(who.clone(), class_id, amount)? {
+            Self::_approve_single_nft(who.clone(), to.clone(), nft_id)?;
+        }
+        Ok(())
+    }
+
+    fn _destroy_batch_nft(who: T::AccountId, class_id: ClassId, amount: u64) -> DispatchResult {
+        for nft_id in Self::_owned_class_nfts(who.clone(), class_id, amount)? {
+            Self::_burn_nft(who.clone(), nft_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Config> Module<T> {
+    pub fn _add_nft_to_owned_nftids(owner: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        ensure!(
+            !<OwnedNFTIds<T>>::contains_key(owner.clone(), nft_id.clone()),
+            Error::<T>::NFTAlreadyOwned
+        );
+
+        <OwnedNFTIds<T>>::insert(owner.clone(), nft_id, ());
+        <OwnedNFTCount<T>>::mutate(owner, |count| *count += 1);
+
+        Ok(())
+    }
+    pub fn _remove_nft_from_owned_nftids(owner: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        ensure!(
+            <OwnedNFTIds<T>>::contains_key(owner.clone(), nft_id.clone()),
+            Error::<T>::NFTNotOwned
+        );
+
+        <OwnedNFTIds<T>>::remove(owner.clone(), nft_id);
+        <OwnedNFTCount<T>>::mutate(owner, |count| *count = count.saturating_sub(1));
+
+        Ok(())
+    }
+
+    fn _add_nft_to_owned_nft_sources(
+        owner: T::AccountId,
+        class_id: ClassId,
+        nfts_index: u64,
+    ) -> DispatchResult {
+        let key = (owner, class_id);
+        ensure!(
+            !<OwnedClassNFTs<T>>::contains_key(key.clone(), nfts_index),
+            Error::<T>::NFTAlreadyOwned
+        );
+
+        <OwnedClassNFTs<T>>::insert(key.clone(), nfts_index, ());
+        <OwnedClassNFTCount<T>>::mutate(key, |count| *count += 1);
+
+        Ok(())
+    }
+
+    fn _remove_nft_from_owned_nft_sources(
+        owner: T::AccountId,
+        class_id: ClassId,
+        nfts_index: u64,
+    ) -> DispatchResult {
+        let key = (owner, class_id);
+        ensure!(
+            <OwnedClassNFTs<T>>::contains_key(key.clone(), nfts_index),
+            Error::<T>::NFTNotOwned
+        );
+
+        <OwnedClassNFTs<T>>::remove(key.clone(), nfts_index);
+        <OwnedClassNFTCount<T>>::mutate(key, |count| *count = count.saturating_sub(1));
+
+        Ok(())
+    }
+
+    /// The first `amount` NFTs of `class_id` held by `owner`
+    fn _owned_class_nfts(
+        owner: T::AccountId,
+        class_id: ClassId,
+        amount: u64,
+    ) -> Result<Vec<NFTId>, DispatchError> {
+        ensure!(
+            Self::owned_class_nft_count((owner.clone(), class_id.clone())) >= amount,
+            Error::<T>::NotEnoughtNFT
+        );
+
+        <OwnedClassNFTs<T>>::iter_prefix((owner, class_id.clone()))
+            .take(amount as usize)
+            .map(|(nfts_index, _)| {
+                Self::nft_by_class_index(class_id.clone(), nfts_index)
+                    .ok_or_else(|| Error::<T>::NFTNotExist.into())
+            })
+            .collect()
+    }
+}
+
+// Balance
+impl<T: Config> Module<T> {
+    fn _set_approval_for_all(
+        owner: T::AccountId,
+        operator: T::AccountId,
+        approved: bool,
+    ) -> DispatchResult {
+        ensure!(owner != operator, Error::<T>::CanNotApproveToSelf);
+        <OwnerToApprove<T>>::insert((&owner, &operator), approved);
+        Ok(())
+    }
+
+    fn _mint_balance(
+        who: T::AccountId,
+        class_id: ClassId,
+        to: T::AccountId,
+        amount: u128,
+    ) -> DispatchResult {
+        Self::_ensure_class_minter(&who, &class_id)?;
+
+        Self::_do_mint_balance(class_id, to, amount)
+    }
+
+    fn _do_mint_balance(class_id: ClassId, to: T::AccountId, amount: u128) -> DispatchResult {
+        let class_info = Self::class_infos(class_id.clone()).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(
+            !Self::is_unique_class(class_id.clone()),
+            Error::<T>::ClassNotFungible
+        );
+
+        let supply = Self::class_supply(class_id.clone())
+            .checked_add(amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+        ensure!(
+            supply <= class_info.total_supply as u128,
+            Error::<T>::IndexExceedTotalSupply
+        );
+
+        <Balances<T>>::mutate(class_id.clone(), to, |balance| *balance += amount);
+        <ClassSupply>::insert(class_id, supply);
+
+        Ok(())
+    }
+
+    /// Unique classes burn `amount` whole NFTs of the class.
+    fn _burn_balance(who: T::AccountId, class_id: ClassId, amount: u128) -> DispatchResult {
+        if Self::is_unique_class(class_id.clone()) {
+            return Self::_destroy_batch_nft(who, class_id, amount.saturated_into());
+        }
+
+        let balance = Self::balances(class_id.clone(), who.clone())
+            .checked_sub(amount)
+            .ok_or(Error::<T>::InsufficientBalance)?;
+
+        Self::_set_balance(class_id.clone(), who, balance);
+        <ClassSupply>::mutate(class_id, |supply| *supply = supply.saturating_sub(amount));
+
+        Ok(())
+    }
+
+    /// ERC-1155 `safeTransferFrom`: the operator must be `from` or approved for all by it.
+    #[transactional]
+    fn _safe_transfer_from(
+        operator: T::AccountId,
+        from: T::AccountId,
+        to: T::AccountId,
+        class_id: ClassId,
+        amount: u128,
+    ) -> DispatchResult {
+        Self::_ensure_operator(&operator, &from)?;
+        Self::_transfer_balance(from, to, class_id, amount)
+    }
+
+    /// ERC-1155 `safeBatchTransferFrom`: either every pair moves or none does.
+    #[transactional]
+    fn _safe_batch_transfer_from(
+        operator: T::AccountId,
+        from: T::AccountId,
+        to: T::AccountId,
+        class_ids: Vec<ClassId>,
+        amounts: Vec<u128>,
     ) -> DispatchResult {
-        let owned_nfts = Self::owned_nft_source(who.clone());
-        for i in 0..owned_nfts.len() {
-            if owned_nfts[i].class_id == class_id.clone() {
-                ensure!(
-                    owned_nfts[i].amount >= amount.clone(),
-                    Error::<T>::NotEnoughtNFT
-                );
-                for j in 0..owned_nfts[i].nfts_indexs.len() {
-                    let nft_id = Self::nft_by_class_index(
-                        class_id.clone(),
-                        owned_nfts[i].nfts_indexs[j].clone(),
-                    )
-                    .ok_or(Error::<T>::NFTNotExist)?;
-
-                    Self::_approve_single_nft(who.clone(), to.clone(), nft_id)?;
-                }
-            }
+        ensure!(
+            class_ids.len() == amounts.len(),
+            Error::<T>::BatchLengthMismatch
+        );
+        Self::_ensure_operator(&operator, &from)?;
+
+        for (class_id, amount) in class_ids.into_iter().zip(amounts) {
+            Self::_transfer_balance(from.clone(), to.clone(), class_id, amount)?;
         }
+
         Ok(())
     }
 
-    fn _destroy_batch_nft(who: T::AccountId, class_id: ClassId, amount: u64) -> DispatchResult {
-        let owned_nfts = Self::owned_nft_source(who.clone());
-        for i in 0..owned_nfts.len() {
-            if owned_nfts[i].class_id == class_id.clone() {
-                ensure!(
-                    owned_nfts[i].amount >= amount.clone(),
-                    Error::<T>::NotEnoughtNFT
-                );
-                for j in 0..owned_nfts[i].nfts_indexs.len() {
-                    let nft_id = Self::nft_by_class_index(
-                        class_id.clone(),
-                        owned_nfts[i].nfts_indexs[j].clone(),
-                    )
-                    .ok_or(Error::<T>::NFTNotExist)?;
-
-                    Self::_burn_nft(who.clone(), nft_id)?;
-                }
-            }
+    fn _ensure_operator(operator: &T::AccountId, from: &T::AccountId) -> DispatchResult {
+        ensure!(
+            operator == from || Self::is_approved_for_all((from.clone(), operator.clone())),
+            Error::<T>::NoPermission
+        );
+        Ok(())
+    }
+
+    /// Unique classes move `amount` whole NFTs of the class.
+    fn _transfer_balance(
+        from: T::AccountId,
+        to: T::AccountId,
+        class_id: ClassId,
+        amount: u128,
+    ) -> DispatchResult {
+        Self::_ensure_transferable(&class_id, &from, &to)?;
+        if Self::is_unique_class(class_id.clone()) {
+            return Self::_transfer_batch_nft(from, to, class_id, amount.saturated_into());
         }
+
+        let from_balance = Self::balances(class_id.clone(), from.clone())
+            .checked_sub(amount)
+            .ok_or(Error::<T>::InsufficientBalance)?;
+        if from == to {
+            return Ok(());
+        }
+        let to_balance = Self::balances(class_id.clone(), to.clone())
+            .checked_add(amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+
+        Self::_set_balance(class_id.clone(), from, from_balance);
+        Self::_set_balance(class_id, to, to_balance);
+
         Ok(())
     }
+
+    fn _set_balance(class_id: ClassId, who: T::AccountId, balance: u128) {
+        if balance.is_zero() {
+            <Balances<T>>::remove(class_id, who);
+        } else {
+            <Balances<T>>::insert(class_id, who, balance);
+        }
+    }
 }
 
+// RPC
 impl<T: Config> Module<T> {
-    pub fn _add_nft_to_owned_nftids(owner: T::AccountId, nft_id: NFTId) -> DispatchResult {
-        ensure!(
-            !Self::owned_nft_ids(owner.clone()).contains(&nft_id),
-            Error::<T>::NFTAlreadyOwned
-        );
+    /// ERC-1155 `balanceOf`; unique classes count the NFTs held
+    pub fn balance_of(owner: T::AccountId, class_id: ClassId) -> u128 {
+        if Self::is_unique_class(class_id.clone()) {
+            Self::owned_class_nft_count((owner, class_id)) as u128
+        } else {
+            Self::balances(class_id, owner)
+        }
+    }
+
+    /// ERC-1155 `balanceOfBatch`, pairing `owners` and `class_ids` by position
+    pub fn balance_of_batch(owners: Vec<T::AccountId>, class_ids: Vec<ClassId>) -> Vec<u128> {
+        owners
+            .into_iter()
+            .zip(class_ids)
+            .map(|(owner, class_id)| Self::balance_of(owner, class_id))
+            .collect()
+    }
 
-        let mut owned_nfts = Self::owned_nft_ids(owner.clone());
+    /// At most `page_size` NFTs owned by `owner`, skipping the first `page * page_size`
+    pub fn owned_nfts_paged(owner: T::AccountId, page: u32, page_size: u32) -> Vec<NFTId> {
+        <OwnedNFTIds<T>>::iter_prefix(owner)
+            .skip((page as usize).saturating_mul(page_size as usize))
+            .take(page_size as usize)
+            .map(|(nft_id, _)| nft_id)
+            .collect()
+    }
 
-        owned_nfts.push(nft_id.clone());
+    /// Same as `owned_nfts_paged`, restricted to one class
+    pub fn owned_class_nfts_paged(
+        owner: T::AccountId,
+        class_id: ClassId,
+        page: u32,
+        page_size: u32,
+    ) -> Vec<NFTId> {
+        <OwnedClassNFTs<T>>::iter_prefix((owner, class_id.clone()))
+            .skip((page as usize).saturating_mul(page_size as usize))
+            .take(page_size as usize)
+            .filter_map(|(nfts_index, _)| Self::nft_by_class_index(class_id.clone(), nfts_index))
+            .collect()
+    }
+}
 
-        <OwnedNFTIds<T>>::insert(owner, owned_nfts);
+// Migration
+impl<T: Config> Module<T> {
+    /// V1 kept a `Vec` per account; V2 moves to double maps with counters.
+    /// The old vectors missed some mints, so the indexes are rebuilt from `NFTInfos`.
+    /// V1 classes had no transfer policy and become `Transferable`.
+    fn migrate_to_v2() -> Weight {
+        if Self::storage_version() != Releases::V1 {
+            return 0;
+        }
 
-        Ok(())
+        let mut class_count = 0u64;
+        <ClassInfos<T>>::translate(|_, old: ClassInfoV1<T::AccountId>| {
+            class_count += 1;
+            Some(old.into())
+        });
+
+        <OwnedNFTIds<T>>::remove_all();
+        <OwnedNFTCount<T>>::remove_all();
+        remove_storage_prefix(b"NFT1155", b"OwnedNFTSource", &[]);
+        <OwnedClassNFTs<T>>::remove_all();
+        <OwnedClassNFTCount<T>>::remove_all();
+
+        let mut count = 0u64;
+        for (nft_id, nft) in <NFTInfos<T>>::iter() {
+            count += 1;
+            if nft.status != NFTStatus::Burned {
+                let _ = Self::_add_nft_to_owned_nftids(nft.owner.clone(), nft_id);
+                let _ = Self::_add_nft_to_owned_nft_sources(nft.owner, nft.class_id, nft.index);
+            }
+        }
+
+        <PalletStorageVersion>::put(Releases::V2);
+
+        T::DbWeight::get().reads_writes(1 + class_count + 3 * count, 1 + class_count + 4 * count)
     }
-    pub fn _remove_nft_from_owned_nftids(owner: T::AccountId, nft_id: NFTId) -> DispatchResult {
-        ensure!(
-            Self::owned_nft_ids(owner.clone()).contains(&nft_id),
-            Error::<T>::NFTNotOwned
-        );
 
-        let mut owned_nfts = Self::owned_nft_ids(owner.clone());
+    /// V2 stored one `NFTInfo` per unit; V3 keeps fungible classes as balances.
+    /// Only classes whose live units are plain copies of each other become fungible,
+    /// every other class, empty ones included, keeps its unique units.
+    /// Class nonces and live supplies are backfilled for `destroy_class`.
+    fn migrate_to_v3() -> Weight {
+        if Self::storage_version() != Releases::V2 {
+            return 0;
+        }
+
+        let nfts: Vec<_> = <NFTInfos<T>>::iter().collect();
+        let count = nfts.len() as u64;
+
+        // the first live unit of each class, `None` once any unit differs from it
+        let mut templates = BTreeMap::new();
+        let mut held = BTreeSet::new();
+        for (nft_id, nft) in nfts.iter() {
+            held.insert(nft.class_id.clone());
+            if nft.status == NFTStatus::Burned {
+                continue;
+            }
+            let unit = (nft.info.clone(), nft.metadata.clone(), nft.issuer.clone());
+            let plain = Self::_is_plain_unit(nft_id, nft);
+            let template = templates
+                .entry(nft.class_id.clone())
+                .or_insert_with(|| Some(unit.clone()));
+            if !plain || template.as_ref() != Some(&unit) {
+                *template = None;
+            }
+        }
 
-        let mut j = 0;
+        // only classes holding units are touched; unique or balance holding classes already use V3
+        let class_count = held.len() as u64;
+        let mut fungible = BTreeSet::new();
+        for class_id in held {
+            if Self::is_unique_class(class_id.clone())
+                || <Balances<T>>::iter_prefix(&class_id).next().is_some()
+            {
+                continue;
+            }
+            if matches!(templates.get(&class_id), Some(Some(_))) {
+                fungible.insert(class_id);
+            } else {
+                <UniqueClasses>::insert(class_id, true);
+            }
+        }
 
-        for i in &owned_nfts {
-            if *i == nft_id.clone() {
-                owned_nfts.remove(j);
+        for (nonce, class_id) in <ClassIndex>::iter() {
+            <ClassNonces>::insert(class_id, nonce);
+        }
 
-                break;
+        let mut supplies = BTreeMap::new();
+        for (nft_id, nft) in nfts {
+            if nft.status != NFTStatus::Burned {
+                let supply = supplies.entry(nft.class_id.clone()).or_insert(0u128);
+                *supply = supply.saturating_add(1);
+            }
+            if !fungible.contains(&nft.class_id) {
+                continue;
+            }
+            if nft.status != NFTStatus::Burned {
+                <Balances<T>>::mutate(nft.class_id.clone(), nft.owner.clone(), |balance| {
+                    *balance = balance.saturating_add(1)
+                });
+                let _ = Self::_remove_nft_from_owned_nftids(nft.owner.clone(), nft_id.clone());
+                let _ = Self::_remove_nft_from_owned_nft_sources(
+                    nft.owner.clone(),
+                    nft.class_id.clone(),
+                    nft.index,
+                );
             }
+            Self::_release_deposit(<NFTDeposits<T>>::take(&nft_id));
+            <NFTInfos<T>>::remove(nft_id);
+            <NFTByClassIndex>::remove(nft.class_id, nft.index);
+        }
 
-            j += 1;
+        // classes already tracking their supply keep it
+        for (class_id, supply) in supplies {
+            if !<ClassSupply>::contains_key(&class_id) {
+                <ClassSupply>::insert(class_id, supply);
+            }
         }
 
-        <OwnedNFTIds<T>>::insert(owner, owned_nfts);
+        <PalletStorageVersion>::put(Releases::V3);
+
+        T::DbWeight::get().reads_writes(
+            1 + 2 * class_count + 10 * count,
+            1 + 2 * class_count + 9 * count,
+        )
+    }
+
+    /// A unit with no state of its own besides its owner
+    fn _is_plain_unit(nft_id: &NFTId, nft: &NFTInfo<T::AccountId, BalanceOf<T>>) -> bool {
+        let target = AttributeTarget::NFT(nft_id.clone());
+        nft.status == NFTStatus::Normal
+            && !<NFTUsers<T>>::contains_key(nft_id)
+            && !<RentOffers<T>>::contains_key(nft_id)
+            && !<NFTRoyalties<T>>::contains_key(nft_id)
+            && !<NFTParents>::contains_key(nft_id)
+            && !Self::_has_children(nft_id)
+            && !Self::is_metadata_frozen(&target)
+            && <Attributes<T>>::iter_prefix(&target).next().is_none()
+    }
+}
+
+// Composition
+impl<T: Config> Module<T> {
+    /// The child stays locked in place; its ownership follows the root of the tree
+    #[transactional]
+    fn _attach_nft(who: T::AccountId, nft_id: NFTId, parent: (NFTType, NFTId)) -> DispatchResult {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == who, Error::<T>::NotNFTOwner);
+        ensure!(
+            Self::nft_parents(nft_id.clone()).is_none(),
+            Error::<T>::NFTAlreadyAttached
+        );
+        let child = (NFTType::NFT1155, nft_id.clone());
+        helpers::check_attach::<_, BalanceOf<T>, T::NFT>(
+            &who,
+            &child,
+            &parent,
+            T::MaxNestingDepth::get(),
+        )
+        .map_err(Error::<T>::from)?;
+
+        Self::_lock_nft(nft_id.clone())?;
+        T::NFT::add_child(parent.0, parent.1.clone(), child)?;
+        <NFTParents>::insert(nft_id, parent);
 
         Ok(())
     }
 
-    fn _add_nft_to_owned_nft_sources(owner: T::AccountId, class_id: ClassId, nfts_index: u64) {
-        if let Some(_nft_id) = Self::nft_by_class_index(class_id.clone(), nfts_index.clone()) {
-            let mut owned_nfts = Self::owned_nft_source(owner.clone());
-            let mut nfts_exist_flag = false;
-            let mut nft_exist_flag = false;
-            for i in owned_nfts.clone() {
-                if i.class_id == class_id.clone() {
-                    nfts_exist_flag = true;
-                    for j in i.nfts_indexs.clone() {
-                        if j == nfts_index.clone() {
-                            nft_exist_flag = true;
-                        }
-                    }
-                }
+    #[transactional]
+    fn _detach_nft(who: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        let (parent_type, parent_id) =
+            Self::nft_parents(nft_id.clone()).ok_or(Error::<T>::NFTNotAttached)?;
+        ensure!(
+            T::NFT::owner_of(parent_type, parent_id.clone()) == Some(who),
+            Error::<T>::NoPermission
+        );
+
+        T::NFT::remove_child(parent_type, parent_id, (NFTType::NFT1155, nft_id.clone()))?;
+        <NFTParents>::remove(nft_id.clone());
+        Self::_unlock_nft(nft_id)
+    }
+
+    fn _move_children(nft_id: &NFTId, from: &T::AccountId, to: &T::AccountId) -> DispatchResult {
+        let children = <NFTChildren>::iter_prefix(nft_id)
+            .map(|(child, _)| child)
+            .collect();
+        helpers::move_children::<_, BalanceOf<T>, T::NFT>(children, from, to)
+    }
+
+    fn _has_children(nft_id: &NFTId) -> bool {
+        <NFTChildren>::iter_prefix(nft_id).next().is_some()
+    }
+}
+
+// Deposit
+impl<T: Config> Module<T> {
+    /// `base` plus `DepositPerByte` for every stored byte
+    fn _reserve_deposit(
+        who: &T::AccountId,
+        base: BalanceOf<T>,
+        bytes: usize,
+    ) -> Result<StorageDeposit<T::AccountId, BalanceOf<T>>, DispatchError> {
+        helpers::reserve_deposit::<_, T::Currency>(who, base, T::DepositPerByte::get(), bytes)
+    }
+
+    fn _release_deposit(deposit: Option<StorageDeposit<T::AccountId, BalanceOf<T>>>) {
+        helpers::release_deposit::<_, T::Currency>(deposit)
+    }
+}
+
+// Metadata
+impl<T: Config> Module<T> {
+    /// Class attributes belong to the class admin, NFT attributes to the NFT issuer
+    fn _ensure_metadata_issuer(who: &T::AccountId, target: &AttributeTarget) -> DispatchResult {
+        match target {
+            AttributeTarget::Class(class_id) => {
+                Self::_ensure_class_admin(who, class_id)?;
             }
-            if !nft_exist_flag {
-                if nfts_exist_flag {
-                    for i in 0..owned_nfts.len() {
-                        if owned_nfts[i].class_id == class_id.clone() {
-                            owned_nfts[i].amount += 1;
-                            owned_nfts[i].nfts_indexs.push(nfts_index.clone());
-                        }
-                    }
-                    <OwnedNFTSource<T>>::insert(owner, owned_nfts);
-                } else {
-                    let new_owned_nfts = NFTSource {
-                        class_id: class_id.clone(),
-                        amount: 1,
-                        nfts_indexs: vec![nfts_index.clone()],
-                    };
-                    owned_nfts.push(new_owned_nfts);
-                    <OwnedNFTSource<T>>::insert(owner, owned_nfts);
-                }
+            AttributeTarget::NFT(nft_id) => {
+                let nft = Self::nft_infos(nft_id).ok_or(Error::<T>::NFTNotExist)?;
+                ensure!(nft.issuer == *who, Error::<T>::NoPermission);
+                ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
             }
         }
+        ensure!(
+            !Self::is_metadata_frozen(target),
+            Error::<T>::MetadataFrozen
+        );
+        Ok(())
     }
 
-    fn _remove_nft_from_owned_nft_sources(
-        owner: T::AccountId,
-        class_id: ClassId,
-        nfts_index: u64,
+    /// Overwriting an attribute releases the old deposit and reserves one for the new bytes
+    #[transactional]
+    fn _set_attribute(
+        who: T::AccountId,
+        target: AttributeTarget,
+        key: Vec<u8>,
+        value: AttributeValue,
     ) -> DispatchResult {
-        let _nft = Self::nft_by_class_index(class_id.clone(), nfts_index.clone())
-            .ok_or(Error::<T>::NFTNotExist)?;
-        let mut owned_nfts = Self::owned_nft_source(owner.clone());
-        let mut nfts_exist_flag = false;
-        let mut nft_exist_flag = false;
-        for i in owned_nfts.clone() {
-            if i.class_id == class_id.clone() {
-                nfts_exist_flag = true;
-                for j in i.nfts_indexs.clone() {
-                    if j == nfts_index.clone() {
-                        nft_exist_flag = true;
-                    }
-                }
+        Self::_ensure_metadata_issuer(&who, &target)?;
+        ensure!(
+            key.len() <= T::AttributeKeyLimit::get() as usize,
+            Error::<T>::AttributeKeyTooLong
+        );
+        ensure!(
+            value.encoded_size() <= T::AttributeValueLimit::get() as usize,
+            Error::<T>::AttributeValueTooLong
+        );
+
+        let attribute = helpers::reserve_attribute::<_, T::Currency>(
+            who,
+            value,
+            key.len(),
+            T::AttributeDepositBase::get(),
+            T::AttributeDepositPerByte::get(),
+            Self::attributes(&target, &key),
+        )?;
+        <Attributes<T>>::insert(target, key, attribute);
+
+        Ok(())
+    }
+
+    fn _clear_attribute(
+        who: T::AccountId,
+        target: AttributeTarget,
+        key: Vec<u8>,
+    ) -> DispatchResult {
+        Self::_ensure_metadata_issuer(&who, &target)?;
+        let old = Self::attributes(&target, &key).ok_or(Error::<T>::AttributeNotExist)?;
+
+        T::Currency::unreserve(&old.depositor, old.deposit);
+        <Attributes<T>>::remove(target, key);
+
+        Ok(())
+    }
+
+    /// There is no unfreeze; deposits of frozen attributes stay reserved
+    fn _freeze_metadata(who: T::AccountId, target: AttributeTarget) -> DispatchResult {
+        match &target {
+            AttributeTarget::Class(class_id) => {
+                let class_info = Self::class_infos(class_id).ok_or(Error::<T>::ClassNotExist)?;
+                ensure!(
+                    class_info.issuer == who
+                        || Self::_class_roles(class_id, &class_info).freezer == who,
+                    Error::<T>::NoPermission
+                );
             }
+            AttributeTarget::NFT(_) => Self::_ensure_metadata_issuer(&who, &target)?,
         }
-        ensure!(nfts_exist_flag == true, Error::<T>::NFTNotExist);
-        ensure!(nft_exist_flag == true, Error::<T>::NFTNotExist);
-
-        for i in 0..owned_nfts.len() {
-            if owned_nfts[i].class_id == class_id.clone() {
-                let mut k = 0;
-                for j in owned_nfts[i].nfts_indexs.clone() {
-                    if j == nfts_index.clone() {
-                        owned_nfts[i].nfts_indexs.remove(k);
-                        owned_nfts[i].amount -= 1;
-                        break;
-                    }
-                    k += 1;
-                }
-            }
+
+        <FrozenMetadata>::insert(target, true);
+
+        Ok(())
+    }
+
+    /// Drops every attribute of `target`, frozen or not, and returns their deposits
+    fn _remove_metadata(target: &AttributeTarget) {
+        for (_, attribute) in <Attributes<T>>::iter_prefix(target) {
+            T::Currency::unreserve(&attribute.depositor, attribute.deposit);
         }
-        <OwnedNFTSource<T>>::insert(owner, owned_nfts);
+        <Attributes<T>>::remove_prefix(target);
+        <FrozenMetadata>::remove(target);
+    }
+}
+
+// Lock
+impl<T: Config> Module<T> {
+    fn _lock_nft(nft_id: NFTId) -> DispatchResult {
+        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        ensure!(
+            nft.status == NFTStatus::Normal || nft.status == NFTStatus::Offered,
+            Error::<T>::NFTLocked
+        );
+
+        Self::_clear_listing(&nft_id, &mut nft);
+        nft.status = NFTStatus::Locked;
+        <NFTInfos<T>>::insert(nft_id, &nft);
+
+        Ok(())
+    }
+
+    fn _unlock_nft(nft_id: NFTId) -> DispatchResult {
+        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.status == NFTStatus::Locked, Error::<T>::NFTNotLocked);
+
+        nft.status = NFTStatus::Normal;
+        <NFTInfos<T>>::insert(nft_id, &nft);
+
+        Ok(())
+    }
+}
+
+// Rental
+impl<T: Config> Module<T> {
+    fn _user_of(nft_id: NFTId) -> Option<T::AccountId> {
+        helpers::active_user(
+            Self::nft_users(nft_id),
+            &<frame_system::Module<T>>::block_number(),
+        )
+    }
+
+    fn _set_user(
+        who: T::AccountId,
+        nft_id: NFTId,
+        user: T::AccountId,
+        expiry: T::BlockNumber,
+    ) -> DispatchResult {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == who, Error::<T>::NotNFTOwner);
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+
+        Self::_update_user(nft_id, user, expiry)
+    }
+
+    fn _update_user(nft_id: NFTId, user: T::AccountId, expiry: T::BlockNumber) -> DispatchResult {
+        ensure!(
+            expiry > <frame_system::Module<T>>::block_number(),
+            Error::<T>::InvalidExpiry
+        );
+        // an active rental can not be cut short by the owner
+        ensure!(
+            Self::_user_of(nft_id.clone()).is_none(),
+            Error::<T>::NFTInUse
+        );
+
+        <UserExpiries<T>>::try_mutate(expiry, |nft_ids| {
+            helpers::schedule_expiry(nft_ids, &nft_id, T::MaxExpiriesPerBlock::get())
+        })
+        .map_err(Error::<T>::from)?;
+        <NFTUsers<T>>::insert(nft_id, NFTUser { user, expiry });
+
+        Ok(())
+    }
+
+    fn _offer_rent_nft(
+        from: T::AccountId,
+        nft_id: NFTId,
+        price_per_block: BalanceOf<T>,
+        max_duration: T::BlockNumber,
+    ) -> DispatchResult {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == from, Error::<T>::NotNFTOwner);
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        ensure!(!max_duration.is_zero(), Error::<T>::InvalidDuration);
+
+        <RentOffers<T>>::insert(
+            nft_id,
+            RentOffer {
+                price_per_block,
+                max_duration,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn _cancel_offer_rent_nft(from: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == from, Error::<T>::NotNFTOwner);
+        ensure!(
+            <RentOffers<T>>::contains_key(nft_id.clone()),
+            Error::<T>::NFTNotForRent
+        );
+
+        <RentOffers<T>>::remove(nft_id);
 
         Ok(())
     }
+
+    #[transactional]
+    fn _rent_nft(
+        who: T::AccountId,
+        nft_id: NFTId,
+        duration: T::BlockNumber,
+    ) -> Result<T::BlockNumber, DispatchError> {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner != who, Error::<T>::NoPermission);
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        let offer = Self::rent_offers(nft_id.clone()).ok_or(Error::<T>::NFTNotForRent)?;
+        let cost = helpers::rent_cost(&offer, duration).map_err(Error::<T>::from)?;
+
+        let expiry = <frame_system::Module<T>>::block_number().saturating_add(duration);
+        Self::_update_user(nft_id, who.clone(), expiry)?;
+
+        T::Currency::transfer(&who, &nft.owner, cost, ExistenceRequirement::KeepAlive)?;
+
+        Ok(expiry)
+    }
 }
 
 impl<T: Config> NFT1155Manager<T::AccountId, BalanceOf<T>> for Module<T> {
@@ -608,7 +2434,15 @@ impl<T: Config> NFT1155Manager<T::AccountId, BalanceOf<T>> for Module<T> {
         total_supply: u64,
         issuer: T::AccountId,
     ) -> DispatchResult {
-        Self::_create_class(name, info, total_supply, issuer)
+        Self::_create_class(
+            name,
+            info,
+            total_supply,
+            issuer,
+            None,
+            TransferPolicy::Transferable,
+            true,
+        )
     }
 
     fn get_class(class_id: ClassId) -> Option<ClassInfo<T::AccountId>> {
@@ -623,13 +2457,21 @@ impl<T: Config> NFT1155Manager<T::AccountId, BalanceOf<T>> for Module<T> {
         price: BalanceOf<T>,
         miner: T::AccountId,
     ) -> Option<NFTId> {
-        Self::_mint_nft(class_id, info, metadata, price, miner)
+        Self::_mint_nft(class_id, info, metadata, price, miner, None).ok()
     }
 
     fn get_nft(nft_id: NFTId) -> Option<NFTInfo<T::AccountId, BalanceOf<T>>> {
         Self::nft_infos(nft_id)
     }
 
+    fn royalty_of(nft_id: NFTId) -> Option<RoyaltyInfo<T::AccountId>> {
+        Self::_royalty_of(nft_id)
+    }
+
+    fn user_of(nft_id: NFTId) -> Option<T::AccountId> {
+        Self::_user_of(nft_id)
+    }
+
     fn get_nft_by_index(class_id: ClassId, index: u64) -> Option<NFTId> {
         Self::nft_by_class_index(class_id, index)
     }
@@ -669,3 +2511,86 @@ impl<T: Config> NFT1155Manager<T::AccountId, BalanceOf<T>> for Module<T> {
         Self::_destroy_batch_nft(who, class_id, amount)
     }
 }
+
+impl<T: Config> NonFungibleManager<T::AccountId, BalanceOf<T>> for Module<T> {
+    fn get_class(class_id: ClassId) -> Option<ClassInfo<T::AccountId>> {
+        Self::class_infos(class_id)
+    }
+
+    fn get_nft(nft_id: NFTId) -> Option<NFTInfo<T::AccountId, BalanceOf<T>>> {
+        Self::nft_infos(nft_id)
+    }
+
+    fn owner_of(nft_id: NFTId) -> Option<T::AccountId> {
+        match Self::nft_parents(nft_id.clone()) {
+            Some((parent_type, parent_id)) => T::NFT::owner_of(parent_type, parent_id),
+            None => Self::nft_infos(nft_id)
+                .filter(|nft| nft.status != NFTStatus::Burned)
+                .map(|nft| nft.owner),
+        }
+    }
+
+    fn royalty_of(nft_id: NFTId) -> Option<RoyaltyInfo<T::AccountId>> {
+        Self::_royalty_of(nft_id)
+    }
+
+    fn user_of(nft_id: NFTId) -> Option<T::AccountId> {
+        Self::_user_of(nft_id)
+    }
+
+    fn mint_nft(
+        class_id: ClassId,
+        info: Vec<u8>,
+        metadata: Vec<u8>,
+        price: BalanceOf<T>,
+        miner: T::AccountId,
+    ) -> Option<NFTId> {
+        Self::_mint_nft(class_id, info, metadata, price, miner, None).ok()
+    }
+
+    fn transfer_single_nft(from: T::AccountId, to: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        Self::_transfer_single_nft(from, to, nft_id)
+    }
+
+    fn lock_nft(nft_id: NFTId) -> DispatchResult {
+        Self::_lock_nft(nft_id)
+    }
+
+    fn unlock_nft(nft_id: NFTId) -> DispatchResult {
+        Self::_unlock_nft(nft_id)
+    }
+
+    fn burn_nft(who: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        Self::_burn_nft(who, nft_id)
+    }
+
+    fn approve_single_nft(who: T::AccountId, to: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        Self::_approve_single_nft(who, to, nft_id)
+    }
+
+    fn parent_of(nft_id: NFTId) -> Option<(NFTType, NFTId)> {
+        Self::nft_parents(nft_id)
+    }
+
+    fn children_of(nft_id: NFTId) -> Vec<(NFTType, NFTId)> {
+        <NFTChildren>::iter_prefix(nft_id)
+            .map(|(child, _)| child)
+            .collect()
+    }
+
+    fn add_child(nft_id: NFTId, child: (NFTType, NFTId)) -> DispatchResult {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        <NFTChildren>::insert(nft_id, child, ());
+        Ok(())
+    }
+
+    fn remove_child(nft_id: NFTId, child: (NFTType, NFTId)) -> DispatchResult {
+        ensure!(
+            <NFTChildren>::contains_key(&nft_id, &child),
+            Error::<T>::NFTNotAttached
+        );
+        <NFTChildren>::remove(nft_id, child);
+        Ok(())
+    }
+}
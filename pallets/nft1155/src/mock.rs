@@ -1,8 +1,9 @@
 use crate as pallet_nft1155;
 use sp_core::H256;
-use frame_support::parameter_types;
+use frame_support::{parameter_types, traits::Get};
+use std::cell::RefCell;
 use sp_runtime::{
-	traits::{BlakeTwo256, IdentityLookup}, testing::Header,
+	traits::{BlakeTwo256, IdentityLookup}, testing::{Header, TestSignature, UintAuthorityId},
 };
 use frame_system as system;
 
@@ -26,6 +27,17 @@ parameter_types! {
 	pub const BlockHashCount: u64 = 250;
 	pub const SS58Prefix: u8 = 42;
 	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxNestingDepth: u32 = 2;
+	pub const AttributeDepositBase: u64 = 10;
+	pub const AttributeDepositPerByte: u64 = 1;
+	pub const AttributeKeyLimit: u32 = 32;
+	pub const AttributeValueLimit: u32 = 64;
+	pub const ClassDepositBase: u64 = 0;
+	pub const NFTDepositBase: u64 = 0;
+	pub const NameLimit: u32 = 16;
+	pub const InfoLimit: u32 = 64;
+	pub const MaxClassMinters: u32 = 4;
+	pub const MaxExpiriesPerBlock: u32 = 2;
 }
 
 impl system::Config for Test {
@@ -65,6 +77,38 @@ impl pallet_balances::Config for Test {
 impl pallet_nft1155::Config for Test {
 	type Event = Event;
 	type Currency = Balances;
+	type Signature = TestSignature;
+	type PublicKey = UintAuthorityId;
+	type NFT = utilities::NonFungibleAdapter<NFT1155Module, NFT1155Module, NFT1155Module>;
+	type MaxNestingDepth = MaxNestingDepth;
+	type AttributeDepositBase = AttributeDepositBase;
+	type AttributeDepositPerByte = AttributeDepositPerByte;
+	type AttributeKeyLimit = AttributeKeyLimit;
+	type AttributeValueLimit = AttributeValueLimit;
+	type ClassDepositBase = ClassDepositBase;
+	type NFTDepositBase = NFTDepositBase;
+	type DepositPerByte = DepositPerByte;
+	type NameLimit = NameLimit;
+	type InfoLimit = InfoLimit;
+	type MaxClassMinters = MaxClassMinters;
+	type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+	type Randomness = randomness::Module<Test>;
+}
+
+thread_local! {
+	static DEPOSIT_PER_BYTE: RefCell<u64> = RefCell::new(0);
+}
+
+/// Free by default so accounts without funds can still mint
+pub struct DepositPerByte;
+impl Get<u64> for DepositPerByte {
+	fn get() -> u64 {
+		DEPOSIT_PER_BYTE.with(|v| *v.borrow())
+	}
+}
+
+pub fn set_deposit_per_byte(deposit: u64) {
+	DEPOSIT_PER_BYTE.with(|v| *v.borrow_mut() = deposit);
 }
 
 // Build genesis storage according to the mock runtime.
@@ -74,6 +118,8 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
     pallet_balances::GenesisConfig::<Test>{
 		balances: vec![(200, 500)],
     }.assimilate_storage(&mut t).unwrap();
+
+	pallet_nft1155::GenesisConfig::default().assimilate_storage::<Test>(&mut t).unwrap();
 	
 	let mut t: sp_io::TestExternalities = t.into();
 
@@ -1,7 +1,7 @@
-use crate::mock::*;
-use frame_support::assert_ok;
+use crate::{mock::*, Error, PalletStorageVersion, UniqueClasses};
+use frame_support::{assert_noop, assert_ok, traits::OnRuntimeUpgrade, StorageMap, StorageValue};
 use sp_io::hashing::blake2_256;
-use utilities::ClassId;
+use utilities::{ClassId, MintDrop, NFTStatus, Releases, TransferPolicy};
 
 #[test]
 fn create_class_should_work() {
@@ -10,7 +10,10 @@ fn create_class_should_work() {
             Origin::signed(1),
             vec![1],
             vec![2],
-            1000
+            1000,
+            None,
+            TransferPolicy::Transferable,
+            false
         ));
     })
 }
@@ -18,24 +21,430 @@ fn create_class_should_work() {
 #[test]
 fn mint_nft_should_work() {
     new_test_ext().execute_with(|| {
-        let did = blake2_256(b"test");
-        let new_class_id = ClassId { did };
+        assert_ok!(NFT1155Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable,
+            true
+        ));
         assert_ok!(NFT1155Module::mint_nft(
             Origin::signed(1),
-            new_class_id,
+            NFT1155Module::class_index(0),
             vec![1],
             vec![2],
-            100
+            100,
+            None
         ));
     })
 }
 
+#[test]
+fn mint_nft_should_fail_for_fungible_class() {
+    new_test_ext().execute_with(|| {
+        let class_id = create_fungible_class(1, 1000);
+        assert_noop!(
+            NFT1155Module::mint_nft(
+                Origin::signed(1),
+                class_id.clone(),
+                vec![1],
+                vec![2],
+                100,
+                None
+            ),
+            Error::<Test>::ClassNotUnique
+        );
+        assert_eq!(NFT1155Module::class_supply(class_id), 0);
+    })
+}
+
+#[test]
+fn transfer_nft_should_require_operator() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT1155Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable,
+            true
+        ));
+        let class_id = NFT1155Module::class_index(0);
+        let nft_id = NFT1155Module::_mint_nft(class_id, vec![1], vec![2], 100, 1, None).unwrap();
+        assert_noop!(
+            NFT1155Module::transfer_nft(Origin::signed(2), 1, 2, nft_id.clone()),
+            Error::<Test>::NoPermission
+        );
+
+        assert_ok!(NFT1155Module::set_approval_for_all(
+            Origin::signed(1),
+            2,
+            true
+        ));
+        assert_ok!(NFT1155Module::transfer_nft(
+            Origin::signed(2),
+            1,
+            3,
+            nft_id.clone()
+        ));
+        assert_eq!(NFT1155Module::nft_infos(nft_id).unwrap().owner, 3);
+    })
+}
+
 #[test]
 fn transfer_nft_should_work() {
     new_test_ext().execute_with(|| {
         let did = blake2_256(b"test");
         let new_class_id = ClassId { did };
-        let nft_id = NFT1155Module::_mint_nft(new_class_id, vec![1], vec![2], 100, 1);
-        assert_eq!(nft_id, None);
+        assert_noop!(
+            NFT1155Module::_mint_nft(new_class_id, vec![1], vec![2], 100, 1, None),
+            Error::<Test>::ClassNotExist
+        );
+    })
+}
+
+#[test]
+fn transfer_batch_nft_should_move_owned_index() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT1155Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable,
+            true
+        ));
+        let class_id = NFT1155Module::class_index(0);
+        for _ in 0..3 {
+            NFT1155Module::_mint_nft(class_id.clone(), vec![1], vec![2], 100, 1, None).unwrap();
+        }
+        assert_eq!(NFT1155Module::owned_nft_count(1), 3);
+
+        assert_ok!(NFT1155Module::transfer_batch_nft(
+            Origin::signed(1),
+            2,
+            class_id.clone(),
+            2
+        ));
+        assert_eq!(NFT1155Module::owned_nft_count(1), 1);
+        assert_eq!(NFT1155Module::owned_nft_count(2), 2);
+        assert_eq!(
+            NFT1155Module::owned_class_nft_count((2, class_id.clone())),
+            2
+        );
+        assert_eq!(
+            NFT1155Module::owned_class_nfts_paged(2, class_id, 0, 10).len(),
+            2
+        );
+    })
+}
+
+fn create_fungible_class(issuer: u64, total_supply: u64) -> ClassId {
+    assert_ok!(NFT1155Module::create_class(
+        Origin::signed(issuer),
+        vec![1],
+        vec![2],
+        total_supply,
+        None,
+        TransferPolicy::Transferable,
+        false
+    ));
+    NFT1155Module::class_index(NFT1155Module::class_count() - 1)
+}
+
+#[test]
+fn safe_transfer_from_should_move_balance() {
+    new_test_ext().execute_with(|| {
+        let class_id = create_fungible_class(1, 1000);
+        assert_noop!(
+            NFT1155Module::mint_balance(Origin::signed(2), class_id.clone(), 1, 100),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(NFT1155Module::mint_balance(
+            Origin::signed(1),
+            class_id.clone(),
+            1,
+            100
+        ));
+        assert_noop!(
+            NFT1155Module::mint_balance(Origin::signed(1), class_id.clone(), 1, 901),
+            Error::<Test>::IndexExceedTotalSupply
+        );
+
+        assert_ok!(NFT1155Module::safe_transfer_from(
+            Origin::signed(1),
+            1,
+            2,
+            class_id.clone(),
+            40
+        ));
+        assert_eq!(NFT1155Module::balance_of(1, class_id.clone()), 60);
+        assert_eq!(NFT1155Module::balance_of(2, class_id.clone()), 40);
+
+        assert_noop!(
+            NFT1155Module::safe_transfer_from(Origin::signed(3), 1, 3, class_id.clone(), 10),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(NFT1155Module::set_approval_for_all(
+            Origin::signed(1),
+            3,
+            true
+        ));
+        assert_ok!(NFT1155Module::safe_transfer_from(
+            Origin::signed(3),
+            1,
+            3,
+            class_id.clone(),
+            10
+        ));
+        assert_noop!(
+            NFT1155Module::safe_transfer_from(Origin::signed(1), 1, 2, class_id.clone(), 51),
+            Error::<Test>::InsufficientBalance
+        );
+
+        assert_ok!(NFT1155Module::burn_balance(
+            Origin::signed(2),
+            class_id.clone(),
+            40
+        ));
+        assert_eq!(NFT1155Module::balance_of(2, class_id.clone()), 0);
+        assert_eq!(NFT1155Module::class_supply(class_id), 60);
+    })
+}
+
+#[test]
+fn safe_batch_transfer_from_should_be_atomic() {
+    new_test_ext().execute_with(|| {
+        let gold = create_fungible_class(1, 1000);
+        let silver = create_fungible_class(1, 1000);
+        assert_ok!(NFT1155Module::mint_balance(
+            Origin::signed(1),
+            gold.clone(),
+            1,
+            100
+        ));
+        assert_ok!(NFT1155Module::mint_balance(
+            Origin::signed(1),
+            silver.clone(),
+            1,
+            10
+        ));
+
+        assert_noop!(
+            NFT1155Module::safe_batch_transfer_from(
+                Origin::signed(1),
+                1,
+                2,
+                vec![gold.clone(), silver.clone()],
+                vec![50, 20]
+            ),
+            Error::<Test>::InsufficientBalance
+        );
+        assert_noop!(
+            NFT1155Module::safe_batch_transfer_from(
+                Origin::signed(1),
+                1,
+                2,
+                vec![gold.clone(), silver.clone()],
+                vec![50]
+            ),
+            Error::<Test>::BatchLengthMismatch
+        );
+
+        assert_ok!(NFT1155Module::safe_batch_transfer_from(
+            Origin::signed(1),
+            1,
+            2,
+            vec![gold.clone(), silver.clone()],
+            vec![50, 10]
+        ));
+        assert_eq!(
+            NFT1155Module::balance_of_batch(vec![1, 2, 2], vec![gold.clone(), gold, silver]),
+            vec![50, 50, 10]
+        );
+    })
+}
+
+#[test]
+fn genesis_should_start_at_latest_version() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(NFT1155Module::storage_version(), Releases::V3);
+        assert_ok!(NFT1155Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable,
+            false
+        ));
+        let coins = NFT1155Module::class_index(0);
+        assert_ok!(NFT1155Module::mint_balance(
+            Origin::signed(1),
+            coins.clone(),
+            1,
+            5
+        ));
+
+        NFT1155Module::on_runtime_upgrade();
+
+        assert_eq!(NFT1155Module::storage_version(), Releases::V3);
+        assert!(!NFT1155Module::is_unique_class(coins.clone()));
+        assert_eq!(NFT1155Module::balance_of(1, coins.clone()), 5);
+        assert_eq!(NFT1155Module::class_supply(coins), 5);
+    })
+}
+
+#[test]
+fn migrate_to_v3_should_convert_units_to_balances() {
+    new_test_ext().execute_with(|| {
+        for _ in 0..4 {
+            assert_ok!(NFT1155Module::create_class(
+                Origin::signed(1),
+                vec![1],
+                vec![2],
+                1000,
+                None,
+                TransferPolicy::Transferable,
+                true
+            ));
+        }
+        let coins = NFT1155Module::class_index(0);
+        let items = NFT1155Module::class_index(1);
+        let art = NFT1155Module::class_index(2);
+        let empty = NFT1155Module::class_index(3);
+        let mut coin_ids = vec![];
+        for _ in 0..3 {
+            coin_ids.push(
+                NFT1155Module::_mint_nft(coins.clone(), vec![1], vec![2], 100, 1, None).unwrap(),
+            );
+        }
+        let item_id =
+            NFT1155Module::_mint_nft(items.clone(), vec![1], vec![2], 100, 1, None).unwrap();
+        assert_ok!(NFT1155Module::offer_nft(
+            Origin::signed(1),
+            item_id.clone(),
+            100,
+            None
+        ));
+        let art_ids: Vec<_> = (0..2u8)
+            .map(|i| NFT1155Module::_mint_nft(art.clone(), vec![1], vec![i], 100, 1, None).unwrap())
+            .collect();
+        // V2 layout: every class is per-unit
+        for class_id in vec![coins.clone(), items.clone(), art.clone(), empty.clone()] {
+            UniqueClasses::remove(class_id);
+        }
+        PalletStorageVersion::put(Releases::V2);
+
+        NFT1155Module::on_runtime_upgrade();
+
+        assert_eq!(NFT1155Module::storage_version(), Releases::V3);
+        assert!(!NFT1155Module::is_unique_class(coins.clone()));
+        assert_eq!(NFT1155Module::balance_of(1, coins.clone()), 3);
+        assert_eq!(NFT1155Module::class_supply(coins), 3);
+        assert_eq!(NFT1155Module::nft_infos(coin_ids[0].clone()), None);
+
+        assert!(NFT1155Module::is_unique_class(items.clone()));
+        assert_eq!(NFT1155Module::owned_nft_count(1), 1);
+        assert_eq!(
+            NFT1155Module::nft_infos(item_id).unwrap().status,
+            NFTStatus::Offered
+        );
+
+        // units with their own metadata keep it
+        assert!(NFT1155Module::is_unique_class(art.clone()));
+        assert_eq!(NFT1155Module::balance_of(1, art), 2);
+        let second = NFT1155Module::nft_infos(art_ids[1].clone()).unwrap();
+        assert_eq!(second.metadata, vec![1]);
+        assert!(!NFT1155Module::is_unique_class(empty));
+    })
+}
+
+#[test]
+fn migrate_to_v3_should_keep_v3_layout() {
+    new_test_ext().execute_with(|| {
+        for unique in vec![false, true, false] {
+            assert_ok!(NFT1155Module::create_class(
+                Origin::signed(1),
+                vec![1],
+                vec![2],
+                1000,
+                None,
+                TransferPolicy::Transferable,
+                unique
+            ));
+        }
+        let coins = NFT1155Module::class_index(0);
+        let items = NFT1155Module::class_index(1);
+        let empty = NFT1155Module::class_index(2);
+        assert_ok!(NFT1155Module::mint_balance(
+            Origin::signed(1),
+            coins.clone(),
+            2,
+            30
+        ));
+        let item_ids: Vec<_> = (0..2)
+            .map(|_| {
+                NFT1155Module::_mint_nft(items.clone(), vec![1], vec![2], 100, 1, None).unwrap()
+            })
+            .collect();
+        // a chain already on the V3 layout that still reports V2
+        PalletStorageVersion::put(Releases::V2);
+
+        NFT1155Module::on_runtime_upgrade();
+
+        assert_eq!(NFT1155Module::storage_version(), Releases::V3);
+        assert!(!NFT1155Module::is_unique_class(coins.clone()));
+        assert_eq!(NFT1155Module::balance_of(2, coins.clone()), 30);
+        assert_eq!(NFT1155Module::class_supply(coins), 30);
+
+        assert!(NFT1155Module::is_unique_class(items.clone()));
+        assert_eq!(NFT1155Module::balance_of(1, items.clone()), 2);
+        assert_eq!(NFT1155Module::class_supply(items), 2);
+        assert!(NFT1155Module::nft_infos(item_ids[1].clone()).is_some());
+        assert!(!NFT1155Module::is_unique_class(empty));
+    })
+}
+
+#[test]
+fn public_mint_should_keep_class_issuer() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT1155Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable,
+            true
+        ));
+        let class_id = NFT1155Module::class_index(0);
+        assert_ok!(NFT1155Module::set_mint_drop(
+            Origin::signed(1),
+            class_id.clone(),
+            MintDrop {
+                start: 1,
+                end: 10,
+                price: 0,
+                per_account_limit: 3,
+                allowlist_root: None,
+            }
+        ));
+
+        assert_ok!(NFT1155Module::public_mint(
+            Origin::signed(200),
+            class_id.clone(),
+            2,
+            vec![]
+        ));
+
+        assert_eq!(NFT1155Module::balance_of(200, class_id.clone()), 2);
+        let nft_id = NFT1155Module::nft_by_class_index(class_id, 1).unwrap();
+        let nft = NFT1155Module::nft_infos(nft_id).unwrap();
+        assert_eq!((nft.owner, nft.issuer), (200, 1));
     })
 }
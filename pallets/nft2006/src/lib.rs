@@ -1,31 +1,80 @@
 #![cfg_attr(not(feature = "std"), no_std)]
+#![recursion_limit = "256"]
 
-use codec::Encode;
+use codec::{Decode, Encode};
 use frame_support::{
     decl_error, decl_event, decl_module, decl_storage, ensure,
-    traits::{Currency, ExistenceRequirement, Get, Randomness},
-    StorageMap, StorageValue,
+    storage::{migration::remove_storage_prefix, StoragePrefixedMap},
+    traits::{BalanceStatus, Currency, ExistenceRequirement, Get, Randomness, ReservableCurrency},
+    transactional,
+    weights::Weight,
+    IterableStorageDoubleMap, IterableStorageMap, Parameter, StorageDoubleMap, StorageMap,
+    StorageValue,
 };
 use frame_system::ensure_signed;
-use randomness;
 use sp_io::hashing::blake2_256;
-use sp_runtime::DispatchResult;
+use sp_runtime::{
+    helpers_128bit::multiply_by_rational,
+    traits::{
+        AccountIdConversion, AtLeast32BitUnsigned, Member, SaturatedConversion, Saturating, Zero,
+    },
+    DispatchError, DispatchResult, ModuleId,
+};
 use sp_std::prelude::*;
 
 use utilities::{
-    ClassId, ClassInfo, Collection, CollectionId, CollectionStatus, Did, NFT2006Manager, NFTId,
-    NFTInfo, NFTSource, NFTStatus, TokenManager,
+    helpers::{self, NestingError, PaymentSource, RentalError, RevealError},
+    Attribute, AttributeTarget, AttributeValue, ClassId, ClassInfo, ClassInfoV1, ClassRoles,
+    Collection, CollectionId, CollectionStatus, Did, MultiNonFungibleManager, MysteryBox,
+    NFT2006Manager, NFTId, NFTInfo, NFTSource, NFTStatus, NFTType, NFTUser, NonFungibleManager,
+    Releases, RentOffer, RoyaltyInfo, StorageDeposit, TokenManager, TransferPolicy, Vault,
+    VaultStatus,
 };
 
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
 pub trait Config: frame_system::Config {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
-    type Currency: Currency<Self::AccountId>;
-    type Token: TokenManager<Self::AccountId>;
+    type Currency: ReservableCurrency<Self::AccountId>;
+    /// Fragment amounts, matching the `Token` balance type
+    type Balance: Parameter + Member + AtLeast32BitUnsigned + Default + Copy;
+    type Token: TokenManager<Self::AccountId, Self::Balance>;
+    /// Collection members, parents and children from every NFT standard, this pallet included
+    type NFT: MultiNonFungibleManager<Self::AccountId, BalanceOf<Self>>;
+    /// Holds fractionalized NFTs and buyout proceeds
+    type ModuleId: Get<ModuleId>;
+    type BuyoutPeriod: Get<Self::BlockNumber>;
+    type MaxNestingDepth: Get<u32>;
+    /// Reserved for every attribute, plus `AttributeDepositPerByte` for its key and value
+    type AttributeDepositBase: Get<BalanceOf<Self>>;
+    type AttributeDepositPerByte: Get<BalanceOf<Self>>;
+    type AttributeKeyLimit: Get<u32>;
+    type AttributeValueLimit: Get<u32>;
+    /// Reserved per class and per NFT, plus `DepositPerByte` for the data they store
+    type ClassDepositBase: Get<BalanceOf<Self>>;
+    type NFTDepositBase: Get<BalanceOf<Self>>;
+    type CollectionDepositBase: Get<BalanceOf<Self>>;
+    type DepositPerByte: Get<BalanceOf<Self>>;
+    /// Longest `name` or `symbol`
+    type NameLimit: Get<u32>;
+    /// Longest `info` or `metadata`
+    type InfoLimit: Get<u32>;
+    type MaxClassMinters: Get<u32>;
+    /// Most listing or user expiries scheduled for a single block
+    type MaxExpiriesPerBlock: Get<u32>;
+    /// Seeds ids and mystery box reveals
+    type Randomness: Randomness<Self::Hash>;
 }
 
 decl_event!(
     pub enum Event<T> where
         <T as frame_system::Config>::AccountId,
+        Balance = BalanceOf<T>,
+        BlockNumber = <T as frame_system::Config>::BlockNumber,
     {
         CreateClass(AccountId),
 
@@ -35,10 +84,28 @@ decl_event!(
 
         OfferNFT(AccountId),
 
+        CancelOfferNFT(AccountId),
+
+        OfferNFTExpired(NFTId),
+
         BuyNFT(AccountId),
 
         BurnNFT(AccountId),
 
+        RevokeNFT(AccountId),
+
+        UpdateUser(NFTId, AccountId, BlockNumber),
+
+        UserExpired(NFTId),
+
+        OfferRentNFT(AccountId),
+
+        CancelOfferRentNFT(AccountId),
+
+        RentNFT(AccountId),
+
+        RoyaltyPaid(NFTId, AccountId, Balance),
+
         TransferBatchNFT(AccountId),
 
         ApproveSingleNFT(AccountId),
@@ -57,9 +124,55 @@ decl_event!(
 
         DestroyCollection(AccountId),
 
+        DecoupledCollection(AccountId),
+
+        OfferCollection(AccountId),
+
+        CancelOfferCollection(AccountId),
+
+        BuyCollection(AccountId),
+
         ApprovalForAll(AccountId, AccountId, bool),
 
         NFTFragmentation(AccountId),
+
+        RedeemNFT(AccountId, NFTId),
+
+        BuyoutStarted(NFTId, AccountId, Balance),
+
+        BuyoutBid(NFTId, AccountId, Balance),
+
+        BuyoutSettled(NFTId, AccountId, Balance),
+
+        ProceedsClaimed(AccountId, Did, Balance),
+
+        /// owner, child, parent type, parent
+        AttachNFT(AccountId, NFTId, NFTType, NFTId),
+
+        DetachNFT(AccountId, NFTId),
+
+        SetAttribute(AttributeTarget, Vec<u8>, AttributeValue),
+
+        ClearAttribute(AttributeTarget, Vec<u8>),
+
+        FreezeMetadata(AttributeTarget),
+
+        /// class, old owner, new owner
+        TransferClassOwnership(ClassId, AccountId, AccountId),
+
+        /// class, admin, freezer
+        SetClassRoles(ClassId, AccountId, AccountId),
+
+        SetClassMinters(ClassId, Vec<AccountId>),
+
+        UpdateClassMetadata(ClassId),
+
+        DestroyClass(AccountId, ClassId),
+
+        /// class, reveal block
+        SetMysteryBox(ClassId, BlockNumber),
+
+        RevealMysteryBox(ClassId),
     }
 );
 
@@ -77,11 +190,82 @@ decl_error! {
         NotEnoughtNFT,
         CollectionNotExist,
         NotCollectionOwner,
+        CollectionNotActive,
+        CollectionNotForBuy,
         AlreadlyApproved,
         NFTNotForBuy,
+        InvalidExpiry,
+        NFTNotTransferable,
+        ClassNotExist,
+        NFTNotRevocable,
+        NFTLocked,
+        NFTNotLocked,
+        NFTInUse,
+        TooManyExpiries,
+        NFTNotForRent,
+        InvalidDuration,
         NFTAlreadyOwned,
         NFTNotOwned,
         NFTAlreadyShiftINFragmentation,
+        NFTFractionalized,
+        NFTInCollection,
+        VaultNotExist,
+        VaultNotActive,
+        NotEnoughFragments,
+        BidTooLow,
+        NoBuyoutProceeds,
+        NFTHasChildren,
+        NFTNotAttached,
+        NFTAlreadyAttached,
+        NestingCycle,
+        NestingTooDeep,
+        AttributeNotExist,
+        AttributeKeyTooLong,
+        AttributeValueTooLong,
+        MetadataFrozen,
+        NameTooLong,
+        InfoTooLong,
+        ClassNotEmpty,
+        TooManyMinters,
+        MysteryBoxExists,
+        MysteryBoxNotExist,
+        MysteryBoxRevealed,
+        InvalidRevealBlock,
+        RevealTooEarly,
+        MetadataHashMismatch,
+        MetadataLengthMismatch,
+        NotEnoughMetadata,
+    }
+}
+
+impl<T: Config> From<RevealError> for Error<T> {
+    fn from(error: RevealError) -> Self {
+        match error {
+            RevealError::Revealed => Error::<T>::MysteryBoxRevealed,
+            RevealError::TooEarly => Error::<T>::RevealTooEarly,
+            RevealError::HashMismatch => Error::<T>::MetadataHashMismatch,
+            RevealError::LengthMismatch => Error::<T>::MetadataLengthMismatch,
+            RevealError::NotEnoughMetadata => Error::<T>::NotEnoughMetadata,
+        }
+    }
+}
+
+impl<T: Config> From<NestingError> for Error<T> {
+    fn from(error: NestingError) -> Self {
+        match error {
+            NestingError::NoPermission => Error::<T>::NoPermission,
+            NestingError::Cycle => Error::<T>::NestingCycle,
+            NestingError::TooDeep => Error::<T>::NestingTooDeep,
+        }
+    }
+}
+
+impl<T: Config> From<RentalError> for Error<T> {
+    fn from(error: RentalError) -> Self {
+        match error {
+            RentalError::InvalidDuration => Error::<T>::InvalidDuration,
+            RentalError::TooManyExpiries => Error::<T>::TooManyExpiries,
+        }
     }
 }
 
@@ -95,12 +279,29 @@ decl_storage! {
         pub ClassInfos get(fn class_infos): map hasher(twox_64_concat) ClassId => Option<ClassInfo<T::AccountId>>;
         pub ClassCount get(fn class_count): u64;
         pub ClassIndex get(fn class_index): map hasher(blake2_128_concat) u64 => ClassId;
+        pub ClassNonces get(fn class_nonce): map hasher(twox_64_concat) ClassId => Option<u64>;
         pub ClassMintIndex get(fn class_mint_index): map hasher(blake2_128_concat) ClassId => u64;
+        pub ClassSupply get(fn class_supply): map hasher(twox_64_concat) ClassId => u64;
+        pub ClassRoyalties get(fn class_royalties): map hasher(twox_64_concat) ClassId => Option<RoyaltyInfo<T::AccountId>>;
+        pub ClassDeposits get(fn class_deposits): map hasher(twox_64_concat) ClassId => Option<StorageDeposit<T::AccountId, BalanceOf<T>>>;
+        pub ClassRoleInfos get(fn class_role_infos): map hasher(twox_64_concat) ClassId => Option<ClassRoles<T::AccountId>>;
+        pub ClassMinters get(fn is_class_minter):
+        double_map hasher(twox_64_concat) ClassId, hasher(blake2_128_concat) T::AccountId => bool;
+
+        // Mystery box
+        pub MysteryBoxes get(fn mystery_boxes): map hasher(twox_64_concat) ClassId => Option<MysteryBox<T::BlockNumber>>;
 
         // NFT
         pub NFTInfos get(fn nft_infos): map hasher(twox_64_concat) NFTId => Option<NFTInfo<T::AccountId, BalanceOf<T>> >;
         pub NFTsCount get(fn nfts_count): u64;
         pub NFTsIndex get(fn nfts_index): map hasher(blake2_128_concat) u64 => NFTId;
+        pub NFTRoyalties get(fn nft_royalties): map hasher(twox_64_concat) NFTId => Option<RoyaltyInfo<T::AccountId>>;
+        pub NFTDeposits get(fn nft_deposits): map hasher(twox_64_concat) NFTId => Option<StorageDeposit<T::AccountId, BalanceOf<T>>>;
+        pub NFTListingExpiry get(fn nft_listing_expiry): map hasher(twox_64_concat) NFTId => Option<T::BlockNumber>;
+        pub ListingExpiries get(fn listing_expiries): map hasher(twox_64_concat) T::BlockNumber => Vec<NFTId>;
+        pub NFTUsers get(fn nft_users): map hasher(twox_64_concat) NFTId => Option<NFTUser<T::AccountId, T::BlockNumber>>;
+        pub UserExpiries get(fn user_expiries): map hasher(twox_64_concat) T::BlockNumber => Vec<NFTId>;
+        pub RentOffers get(fn rent_offers): map hasher(twox_64_concat) NFTId => Option<RentOffer<BalanceOf<T>, T::BlockNumber>>;
 
         pub NFTByClassIndex get(fn nft_by_class_index):
         double_map hasher(blake2_128_concat) ClassId, hasher(blake2_128_concat) u64 => Option<NFTId>;
@@ -110,6 +311,19 @@ decl_storage! {
         // TNonce
         pub TNonce get(fn tnonce): u64;
 
+        // Composition
+        pub NFTParents get(fn nft_parents): map hasher(twox_64_concat) NFTId => Option<(NFTType, NFTId)>;
+        pub NFTChildren get(fn nft_children):
+        double_map hasher(twox_64_concat) NFTId, hasher(twox_64_concat) (NFTType, NFTId) => ();
+
+        // Metadata
+        pub Attributes get(fn attributes):
+        double_map hasher(blake2_128_concat) AttributeTarget, hasher(blake2_128_concat) Vec<u8> => Option<Attribute<T::AccountId, BalanceOf<T>>>;
+        pub FrozenMetadata get(fn is_metadata_frozen): map hasher(blake2_128_concat) AttributeTarget => bool;
+
+        // Migration
+        pub PalletStorageVersion get(fn storage_version) build(|_| Releases::V3): Releases;
+
 
         //Approvers
         pub NFTApprovers get(fn nft_approvers): map hasher(twox_64_concat) (T::AccountId, NFTId) => bool;
@@ -117,22 +331,29 @@ decl_storage! {
         pub OwnerToApprove get(fn is_approved_for_all): map hasher(twox_64_concat) (T::AccountId, T::AccountId) => bool;
 
         // Collection
-        pub Collections get(fn collections): map hasher(twox_64_concat) CollectionId => Option<Collection<T::AccountId, BalanceOf<T>, CollectionStatus, ClassId>>;
+        pub Collections get(fn collections): map hasher(twox_64_concat) CollectionId => Option<Collection<T::AccountId, BalanceOf<T>, CollectionStatus>>;
         pub CollectionsCount get(fn collections_count): u64;
         pub CollectionsIndex get(fn collections_index): map hasher(blake2_128_concat) u64 => CollectionId;
+        pub CollectionDeposits get(fn collection_deposits): map hasher(twox_64_concat) CollectionId => Option<StorageDeposit<T::AccountId, BalanceOf<T>>>;
 
         // CoNonce
         pub CoNonce get(fn cononce): u64;
 
         // owned NFT
-        pub OwnedNFTIds get(fn owned_nft_ids): map hasher(blake2_128_concat) T::AccountId => Vec<NFTId>;
-        pub OwnedNFTSource get(fn owned_nft_source):  map hasher(twox_64_concat) T::AccountId => Vec<NFTSource<ClassId>>;
+        pub OwnedNFTIds: double_map hasher(blake2_128_concat) T::AccountId, hasher(twox_64_concat) NFTId => ();
+        pub OwnedNFTCount get(fn owned_nft_count): map hasher(blake2_128_concat) T::AccountId => u64;
+        pub OwnedClassNFTs: double_map hasher(blake2_128_concat) (T::AccountId, ClassId), hasher(twox_64_concat) u64 => ();
+        pub OwnedClassNFTCount get(fn owned_class_nft_count): map hasher(blake2_128_concat) (T::AccountId, ClassId) => u64;
         pub OwnedCollections get(fn owned_collections):  map hasher(twox_64_concat) T::AccountId => Vec<CollectionId>;
 
 
 
         /// NFT2006 fragmentation NFTId --> TokenId
         pub NFTShiftToken get(fn nft_shift_token): map hasher(blake2_128_concat) NFTId => Option<Did>;
+        pub Vaults get(fn vaults): map hasher(twox_64_concat) NFTId => Option<Vault<T::AccountId, BalanceOf<T>, T::BlockNumber, T::Balance>>;
+        pub BuyoutEnds get(fn buyout_ends): map hasher(twox_64_concat) T::BlockNumber => Vec<NFTId>;
+        /// Fragment TokenId --> (unclaimed proceeds, unclaimed fragments)
+        pub BuyoutProceeds get(fn buyout_proceeds): map hasher(blake2_128_concat) Did => Option<(BalanceOf<T>, T::Balance)>;
 
 
 
@@ -144,16 +365,62 @@ decl_module! {
         type Error = Error<T>;
         fn deposit_event() = default;
 
+        fn on_runtime_upgrade() -> Weight {
+            Self::migrate_to_v2().saturating_add(Self::migrate_to_v3())
+        }
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            let expired = <ListingExpiries<T>>::take(now);
+            let count = expired.len() as u64;
+
+            for nft_id in expired {
+                // the listing may have been cancelled or re-offered with another expiry
+                if Self::nft_listing_expiry(nft_id.clone()) == Some(now) {
+                    if let Some(mut nft) = Self::nft_infos(nft_id.clone()) {
+                        Self::_clear_listing(&nft_id, &mut nft);
+                        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+                        Self::deposit_event(RawEvent::OfferNFTExpired(nft_id));
+                    }
+                }
+            }
+
+            let expired_users = <UserExpiries<T>>::take(now);
+            let user_count = expired_users.len() as u64;
+
+            for nft_id in expired_users {
+                // the user may have been replaced after an earlier expiry
+                if Self::nft_users(nft_id.clone()).map(|info| info.expiry) == Some(now) {
+                    <NFTUsers<T>>::remove(nft_id.clone());
+                    Self::deposit_event(RawEvent::UserExpired(nft_id));
+                }
+            }
+
+            let ended = <BuyoutEnds<T>>::take(now);
+            let buyout_count = ended.len() as u64;
+
+            for nft_id in ended {
+                // the vault may have been redeemed or settled already
+                let _ = Self::_settle_buyout(nft_id, now);
+            }
+
+            T::DbWeight::get().reads_writes(
+                3 + 2 * count + user_count + 4 * buyout_count,
+                3 + 2 * count + user_count + 6 * buyout_count,
+            )
+        }
+
         #[weight = 10_000 + T::DbWeight::get().reads_writes(1,4)]
         pub fn create_class(
             origin,
             name: Vec<u8>,
             info: Vec<u8>,
             total_supply: u64,
+            royalty: Option<RoyaltyInfo<T::AccountId>>,
+            transfer_policy: TransferPolicy,
         ) {
             let who = ensure_signed(origin)?;
 
-            Self::_create_class(name, info, total_supply, who.clone())?;
+            Self::_create_class(name, info, total_supply, who.clone(), royalty, transfer_policy)?;
 
             Self::deposit_event(RawEvent::CreateClass(who));
         }
@@ -165,10 +432,13 @@ decl_module! {
             info: Vec<u8>,
             metadata: Vec<u8>,
             price: BalanceOf<T>,
+            royalty: Option<RoyaltyInfo<T::AccountId>>,
         ) {
             let who = ensure_signed(origin)?;
+            Self::_ensure_class_minter(&who, &class_id)?;
+            Self::_ensure_nft_limits(&info, &metadata)?;
 
-            Self::_mint_nft(class_id.clone(), info.clone(), metadata.clone(), price.clone(), who.clone());
+            Self::_mint_nft(class_id.clone(), info.clone(), metadata.clone(), price.clone(), who.clone(), royalty)?;
 
             Self::deposit_event(RawEvent::MintNFT(who));
 
@@ -194,15 +464,29 @@ decl_module! {
             origin,
             nft_id: NFTId,
             new_price: BalanceOf<T>,
+            expiry: Option<T::BlockNumber>,
         ) {
             let who = ensure_signed(origin)?;
 
-            Self::_offer_nft( who.clone(), nft_id.clone(), new_price.clone())?;
+            Self::_offer_nft( who.clone(), nft_id.clone(), new_price.clone(), expiry)?;
 
             Self::deposit_event(RawEvent::OfferNFT(who));
 
         }
 
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+        pub fn cancel_offer_nft(
+            origin,
+            nft_id: NFTId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_cancel_offer_nft(who.clone(), nft_id.clone())?;
+
+            Self::deposit_event(RawEvent::CancelOfferNFT(who));
+
+        }
+
         #[weight = 10_000 + T::DbWeight::get().reads_writes(1,4)]
          pub fn buy_nft(
             origin,
@@ -230,6 +514,234 @@ decl_module! {
 
         }
 
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+        pub fn revoke_nft(
+            origin,
+            nft_id: NFTId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_revoke_nft(who.clone(), nft_id.clone())?;
+
+            Self::deposit_event(RawEvent::RevokeNFT(who));
+
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+        pub fn set_user(
+            origin,
+            nft_id: NFTId,
+            user: T::AccountId,
+            expiry: T::BlockNumber,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_set_user(who, nft_id.clone(), user.clone(), expiry)?;
+
+            Self::deposit_event(RawEvent::UpdateUser(nft_id, user, expiry));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
+        pub fn offer_rent_nft(
+            origin,
+            nft_id: NFTId,
+            price_per_block: BalanceOf<T>,
+            max_duration: T::BlockNumber,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_offer_rent_nft(who.clone(), nft_id, price_per_block, max_duration)?;
+
+            Self::deposit_event(RawEvent::OfferRentNFT(who));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
+        pub fn cancel_offer_rent_nft(
+            origin,
+            nft_id: NFTId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_cancel_offer_rent_nft(who.clone(), nft_id)?;
+
+            Self::deposit_event(RawEvent::CancelOfferRentNFT(who));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,4)]
+        pub fn rent_nft(
+            origin,
+            nft_id: NFTId,
+            duration: T::BlockNumber,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            let expiry = Self::_rent_nft(who.clone(), nft_id.clone(), duration)?;
+
+            Self::deposit_event(RawEvent::UpdateUser(nft_id, who.clone(), expiry));
+            Self::deposit_event(RawEvent::RentNFT(who));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,3)]
+        pub fn attach_nft(
+            origin,
+            nft_id: NFTId,
+            parent_type: NFTType,
+            parent_id: NFTId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_attach_nft(who.clone(), nft_id.clone(), (parent_type, parent_id.clone()))?;
+
+            Self::deposit_event(RawEvent::AttachNFT(who, nft_id, parent_type, parent_id));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,3)]
+        pub fn detach_nft(
+            origin,
+            nft_id: NFTId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_detach_nft(who.clone(), nft_id.clone())?;
+
+            Self::deposit_event(RawEvent::DetachNFT(who, nft_id));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,2)]
+        pub fn set_attribute(
+            origin,
+            target: AttributeTarget,
+            key: Vec<u8>,
+            value: AttributeValue,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_set_attribute(who, target.clone(), key.clone(), value.clone())?;
+
+            Self::deposit_event(RawEvent::SetAttribute(target, key, value));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,2)]
+        pub fn clear_attribute(
+            origin,
+            target: AttributeTarget,
+            key: Vec<u8>,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_clear_attribute(who, target.clone(), key.clone())?;
+
+            Self::deposit_event(RawEvent::ClearAttribute(target, key));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2,1)]
+        pub fn freeze_metadata(
+            origin,
+            target: AttributeTarget,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_freeze_metadata(who, target.clone())?;
+
+            Self::deposit_event(RawEvent::FreezeMetadata(target));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+        pub fn transfer_class_ownership(
+            origin,
+            class_id: ClassId,
+            new_owner: T::AccountId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_transfer_class_ownership(who.clone(), class_id.clone(), new_owner.clone())?;
+
+            Self::deposit_event(RawEvent::TransferClassOwnership(class_id, who, new_owner));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
+        pub fn set_class_roles(
+            origin,
+            class_id: ClassId,
+            admin: T::AccountId,
+            freezer: T::AccountId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_set_class_roles(who, class_id.clone(), admin.clone(), freezer.clone())?;
+
+            Self::deposit_event(RawEvent::SetClassRoles(class_id, admin, freezer));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2, 1 + minters.len() as u64)]
+        pub fn set_class_minters(
+            origin,
+            class_id: ClassId,
+            minters: Vec<T::AccountId>,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_set_class_minters(who, class_id.clone(), minters.clone())?;
+
+            Self::deposit_event(RawEvent::SetClassMinters(class_id, minters));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,2)]
+        pub fn update_class_metadata(
+            origin,
+            class_id: ClassId,
+            name: Vec<u8>,
+            info: Vec<u8>,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_update_class_metadata(who, class_id.clone(), name, info)?;
+
+            Self::deposit_event(RawEvent::UpdateClassMetadata(class_id));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,6)]
+        pub fn destroy_class(
+            origin,
+            class_id: ClassId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_destroy_class(who.clone(), class_id.clone())?;
+
+            Self::deposit_event(RawEvent::DestroyClass(who, class_id));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,1)]
+        pub fn set_mystery_box(
+            origin,
+            class_id: ClassId,
+            metadata_hash: [u8; 32],
+            metadata_len: u64,
+            reveal_block: T::BlockNumber,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_set_mystery_box(who, class_id.clone(), metadata_hash, metadata_len, reveal_block)?;
+
+            Self::deposit_event(RawEvent::SetMysteryBox(class_id, reveal_block));
+        }
+
+        /// Anyone holding the committed metadata set may reveal once `reveal_block` is reached
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4 + metadata.len() as u64, 2 + metadata.len() as u64)]
+        pub fn reveal_mystery_box(
+            origin,
+            class_id: ClassId,
+            metadata: Vec<Vec<u8>>,
+        ) {
+            ensure_signed(origin)?;
+
+            Self::_commit_reveal_seed(&class_id)?;
+            Self::_reveal_mystery_box(class_id.clone(), metadata)?;
+
+            Self::deposit_event(RawEvent::RevealMysteryBox(class_id));
+        }
+
         #[weight = 10_000 + T::DbWeight::get().reads_writes(8,3)]
         pub fn transfer_batch_nft(
             origin,
@@ -281,11 +793,11 @@ decl_module! {
             symbol: Vec<u8>,
             info: Vec<u8>,
             price: BalanceOf<T>,
-            source: Vec<NFTSource<ClassId>>,
+            members: Vec<(NFTType, NFTId)>,
         ) {
             let who = ensure_signed(origin)?;
 
-            Self::_coupled_collection(name.clone(), symbol.clone(), who.clone(), info.clone(), price.clone(), source.clone())?;
+            Self::_coupled_collection(name.clone(), symbol.clone(), who.clone(), info.clone(), price, members)?;
 
             Self::deposit_event(RawEvent::CoupledCollection(who));
 
@@ -324,15 +836,65 @@ decl_module! {
         pub fn destroy_collection(
             origin,
             collection_id: CollectionId,
+            burn_nfts: bool,
         ) {
             let who = ensure_signed(origin)?;
 
-            Self::_destroy_collection(who.clone(), collection_id.clone())?;
+            Self::_destroy_collection(who.clone(), collection_id.clone(), burn_nfts)?;
 
             Self::deposit_event(RawEvent::DestroyCollection(who));
 
         }
 
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+        pub fn decoupled_collection(
+            origin,
+            collection_id: CollectionId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_decoupled_collection(who.clone(), collection_id)?;
+
+            Self::deposit_event(RawEvent::DecoupledCollection(who));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
+        pub fn offer_collection(
+            origin,
+            collection_id: CollectionId,
+            new_price: BalanceOf<T>,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_offer_collection(who.clone(), collection_id, new_price)?;
+
+            Self::deposit_event(RawEvent::OfferCollection(who));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
+        pub fn cancel_offer_collection(
+            origin,
+            collection_id: CollectionId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_cancel_offer_collection(who.clone(), collection_id)?;
+
+            Self::deposit_event(RawEvent::CancelOfferCollection(who));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,4)]
+        pub fn buy_collection(
+            origin,
+            collection_id: CollectionId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_buy_collection(who.clone(), collection_id)?;
+
+            Self::deposit_event(RawEvent::BuyCollection(who));
+        }
+
         #[weight = 10_000 + T::DbWeight::get().writes(1)]
         pub fn set_approval_for_all(
             origin,
@@ -350,26 +912,67 @@ decl_module! {
         pub fn nft_fragmentation(
             origin,
             nft_id: NFTId,
-            total_supply: u64,
+            total_supply: T::Balance,
             symbol: Vec<u8>,
+            reserve_price: BalanceOf<T>,
         ) {
             let sender = ensure_signed(origin)?;
 
-            let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
-
-            ensure!(nft.owner == sender.clone(), Error::<T>::NotNFTOwner);
-
-            let token_id = T::Token::issue(sender.clone(), total_supply.clone(), symbol.clone());
-
-            let bound_token_id = Self::nft_shift_token(nft_id.clone());
-
-            ensure!(bound_token_id == None, Error::<T>::NFTAlreadyShiftINFragmentation);
-
-            <NFTShiftToken>::insert(&nft_id, &token_id);
+            Self::_fragment_nft(sender.clone(), nft_id, total_supply, symbol, reserve_price)?;
 
             Self::deposit_event(RawEvent::NFTFragmentation(sender));
         }
 
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,6)]
+        pub fn redeem_nft(
+            origin,
+            nft_id: NFTId,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_redeem_nft(who.clone(), nft_id.clone())?;
+
+            Self::deposit_event(RawEvent::RedeemNFT(who, nft_id));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,3)]
+        pub fn start_buyout(
+            origin,
+            nft_id: NFTId,
+            bid: BalanceOf<T>,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_start_buyout(who.clone(), nft_id.clone(), bid)?;
+
+            Self::deposit_event(RawEvent::BuyoutStarted(nft_id, who, bid));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,3)]
+        pub fn bid_buyout(
+            origin,
+            nft_id: NFTId,
+            bid: BalanceOf<T>,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            Self::_bid_buyout(who.clone(), nft_id.clone(), bid)?;
+
+            Self::deposit_event(RawEvent::BuyoutBid(nft_id, who, bid));
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,4)]
+        pub fn claim_proceeds(
+            origin,
+            token_id: Did,
+        ) {
+            let who = ensure_signed(origin)?;
+
+            let payout = Self::_claim_proceeds(who.clone(), token_id.clone())?;
+
+            Self::deposit_event(RawEvent::ProceedsClaimed(who, token_id, payout));
+        }
+
 
     }
 }
@@ -381,9 +984,15 @@ impl<T: Config> Module<T> {
         info: Vec<u8>,
         total_supply: u64,
         issuer: T::AccountId,
+        royalty: Option<RoyaltyInfo<T::AccountId>>,
+        transfer_policy: TransferPolicy,
     ) -> DispatchResult {
+        Self::_ensure_class_limits(&name, &info)?;
+        let deposit =
+            Self::_reserve_deposit(&issuer, T::ClassDepositBase::get(), name.len() + info.len())?;
+
         let nonce = Self::get_cnonce();
-        let random_seed = <randomness::Module<T>>::random_seed();
+        let random_seed = T::Randomness::random_seed();
         let encoded = (random_seed, issuer.clone(), nonce).encode();
         let did = blake2_256(&encoded);
         let new_class_id = ClassId { did };
@@ -392,11 +1001,17 @@ impl<T: Config> Module<T> {
             info: info.clone(),
             total_supply: total_supply.clone(),
             issuer: issuer.clone(),
+            transfer_policy,
         };
 
         <ClassInfos<T>>::insert(new_class_id.clone(), &new_class);
         <ClassCount>::put(nonce.clone() + 1);
         <ClassIndex>::insert(nonce.clone(), new_class_id.clone());
+        <ClassNonces>::insert(new_class_id.clone(), nonce);
+        <ClassDeposits<T>>::insert(new_class_id.clone(), deposit);
+        if let Some(royalty) = royalty {
+            <ClassRoyalties<T>>::insert(new_class_id.clone(), royalty);
+        }
         // Self::_add_class_to_class_list(new_class_id)?;
 
         Ok(())
@@ -410,87 +1025,530 @@ impl<T: Config> Module<T> {
     }
 }
 
+// Class roles
+impl<T: Config> Module<T> {
+    /// Admin and freezer default to the class owner
+    fn _class_roles(
+        class_id: &ClassId,
+        class_info: &ClassInfo<T::AccountId>,
+    ) -> ClassRoles<T::AccountId> {
+        Self::class_role_infos(class_id).unwrap_or_else(|| ClassRoles {
+            admin: class_info.issuer.clone(),
+            freezer: class_info.issuer.clone(),
+        })
+    }
+
+    fn _ensure_class_owner(
+        who: &T::AccountId,
+        class_id: &ClassId,
+    ) -> Result<ClassInfo<T::AccountId>, DispatchError> {
+        let class_info = Self::class_infos(class_id).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(class_info.issuer == *who, Error::<T>::NoPermission);
+        Ok(class_info)
+    }
+
+    fn _ensure_class_admin(
+        who: &T::AccountId,
+        class_id: &ClassId,
+    ) -> Result<ClassInfo<T::AccountId>, DispatchError> {
+        let class_info = Self::class_infos(class_id).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(
+            class_info.issuer == *who || Self::_class_roles(class_id, &class_info).admin == *who,
+            Error::<T>::NoPermission
+        );
+        Ok(class_info)
+    }
+
+    fn _ensure_class_minter(who: &T::AccountId, class_id: &ClassId) -> DispatchResult {
+        let class_info = Self::class_infos(class_id).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(
+            class_info.issuer == *who || Self::is_class_minter(class_id, who),
+            Error::<T>::NoPermission
+        );
+        Ok(())
+    }
+
+    fn _ensure_class_limits(name: &[u8], info: &[u8]) -> DispatchResult {
+        ensure!(
+            name.len() <= T::NameLimit::get() as usize,
+            Error::<T>::NameTooLong
+        );
+        ensure!(
+            info.len() <= T::InfoLimit::get() as usize,
+            Error::<T>::InfoTooLong
+        );
+        Ok(())
+    }
+
+    /// The class deposit moves to the new owner
+    #[transactional]
+    fn _transfer_class_ownership(
+        who: T::AccountId,
+        class_id: ClassId,
+        new_owner: T::AccountId,
+    ) -> DispatchResult {
+        let mut class_info = Self::_ensure_class_owner(&who, &class_id)?;
+
+        if let Some(mut deposit) = Self::class_deposits(&class_id) {
+            T::Currency::repatriate_reserved(
+                &deposit.depositor,
+                &new_owner,
+                deposit.amount,
+                BalanceStatus::Reserved,
+            )?;
+            deposit.depositor = new_owner.clone();
+            <ClassDeposits<T>>::insert(&class_id, deposit);
+        }
+        class_info.issuer = new_owner;
+        <ClassInfos<T>>::insert(class_id, class_info);
+
+        Ok(())
+    }
+
+    fn _set_class_roles(
+        who: T::AccountId,
+        class_id: ClassId,
+        admin: T::AccountId,
+        freezer: T::AccountId,
+    ) -> DispatchResult {
+        Self::_ensure_class_owner(&who, &class_id)?;
+
+        <ClassRoleInfos<T>>::insert(class_id, ClassRoles { admin, freezer });
+
+        Ok(())
+    }
+
+    /// Replaces every minter of the class; the owner can always mint
+    fn _set_class_minters(
+        who: T::AccountId,
+        class_id: ClassId,
+        minters: Vec<T::AccountId>,
+    ) -> DispatchResult {
+        Self::_ensure_class_admin(&who, &class_id)?;
+        ensure!(
+            minters.len() <= T::MaxClassMinters::get() as usize,
+            Error::<T>::TooManyMinters
+        );
+
+        <ClassMinters<T>>::remove_prefix(&class_id);
+        for minter in minters {
+            <ClassMinters<T>>::insert(&class_id, minter, true);
+        }
+
+        Ok(())
+    }
+
+    /// The class owner pays the deposit for the new bytes
+    #[transactional]
+    fn _update_class_metadata(
+        who: T::AccountId,
+        class_id: ClassId,
+        name: Vec<u8>,
+        info: Vec<u8>,
+    ) -> DispatchResult {
+        let mut class_info = Self::_ensure_class_admin(&who, &class_id)?;
+        ensure!(
+            !Self::is_metadata_frozen(AttributeTarget::Class(class_id.clone())),
+            Error::<T>::MetadataFrozen
+        );
+        Self::_ensure_class_limits(&name, &info)?;
+
+        Self::_release_deposit(<ClassDeposits<T>>::take(&class_id));
+        let deposit = Self::_reserve_deposit(
+            &class_info.issuer,
+            T::ClassDepositBase::get(),
+            name.len() + info.len(),
+        )?;
+        class_info.name = name;
+        class_info.info = info;
+        <ClassInfos<T>>::insert(&class_id, class_info);
+        <ClassDeposits<T>>::insert(class_id, deposit);
+
+        Ok(())
+    }
+
+    /// Releases the class deposit and those of its attributes
+    #[transactional]
+    fn _destroy_class(who: T::AccountId, class_id: ClassId) -> DispatchResult {
+        Self::_ensure_class_owner(&who, &class_id)?;
+        ensure!(
+            Self::class_supply(&class_id) == 0,
+            Error::<T>::ClassNotEmpty
+        );
+
+        Self::_remove_metadata(&AttributeTarget::Class(class_id.clone()));
+        Self::_release_deposit(<ClassDeposits<T>>::take(&class_id));
+        <ClassInfos<T>>::remove(&class_id);
+        <ClassRoyalties<T>>::remove(&class_id);
+        <ClassRoleInfos<T>>::remove(&class_id);
+        <ClassMinters<T>>::remove_prefix(&class_id);
+        <MysteryBoxes<T>>::remove(&class_id);
+        if let Some(nonce) = <ClassNonces>::take(&class_id) {
+            <ClassIndex>::remove(nonce);
+        }
+        <ClassMintIndex>::remove(&class_id);
+        <NFTByClassIndex>::remove_prefix(&class_id);
+        <ClassSupply>::remove(&class_id);
+
+        Ok(())
+    }
+}
+
+// Mystery box
+impl<T: Config> Module<T> {
+    fn _set_mystery_box(
+        who: T::AccountId,
+        class_id: ClassId,
+        metadata_hash: [u8; 32],
+        metadata_len: u64,
+        reveal_block: T::BlockNumber,
+    ) -> DispatchResult {
+        Self::_ensure_class_admin(&who, &class_id)?;
+        ensure!(
+            !<MysteryBoxes<T>>::contains_key(&class_id),
+            Error::<T>::MysteryBoxExists
+        );
+        ensure!(
+            Self::class_mint_index(class_id.clone()) == 0,
+            Error::<T>::ClassNotEmpty
+        );
+        ensure!(
+            reveal_block > <frame_system::Module<T>>::block_number(),
+            Error::<T>::InvalidRevealBlock
+        );
+
+        <MysteryBoxes<T>>::insert(
+            class_id,
+            MysteryBox {
+                metadata_hash,
+                metadata_len,
+                reveal_block,
+                seed: None,
+                revealed: false,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// The first attempt at or after `reveal_block` fixes the seed, and keeps it even when the
+    /// reveal itself fails, so the seed can't be picked by retrying in a later block
+    fn _commit_reveal_seed(class_id: &ClassId) -> DispatchResult {
+        let mut mystery_box =
+            Self::mystery_boxes(class_id).ok_or(Error::<T>::MysteryBoxNotExist)?;
+        if mystery_box.seed.is_none()
+            && <frame_system::Module<T>>::block_number() >= mystery_box.reveal_block
+        {
+            let random = T::Randomness::random(&(b"NFT2006/reveal", class_id).encode());
+            mystery_box.seed = Some(blake2_256(&random.encode()));
+            <MysteryBoxes<T>>::insert(class_id, mystery_box);
+        }
+
+        Ok(())
+    }
+
+    /// Shuffles the committed set and gives every minted NFT one entry.
+    /// The class owner pays the deposit for the revealed bytes.
+    #[transactional]
+    fn _reveal_mystery_box(class_id: ClassId, mut metadata: Vec<Vec<u8>>) -> DispatchResult {
+        let class_info = Self::class_infos(&class_id).ok_or(Error::<T>::ClassNotExist)?;
+        let mut mystery_box =
+            Self::mystery_boxes(&class_id).ok_or(Error::<T>::MysteryBoxNotExist)?;
+        let minted = Self::class_mint_index(class_id.clone());
+        helpers::check_reveal(
+            &mystery_box,
+            &<frame_system::Module<T>>::block_number(),
+            &metadata,
+            minted,
+        )
+        .map_err(Error::<T>::from)?;
+        let seed = mystery_box.seed.ok_or(Error::<T>::RevealTooEarly)?;
+
+        helpers::shuffle_metadata(&seed, &mut metadata, minted);
+        let mut bytes = 0;
+        for (i, entry) in metadata.into_iter().take(minted as usize).enumerate() {
+            Self::_ensure_nft_limits(&[], &entry)?;
+            bytes += entry.len();
+            if let Some(nft_id) = Self::nft_by_class_index(&class_id, i as u64 + 1) {
+                <NFTInfos<T>>::mutate(nft_id, |nft| {
+                    if let Some(nft) = nft {
+                        nft.metadata = entry;
+                    }
+                });
+            }
+        }
+
+        let deposit = Self::_reserve_deposit(&class_info.issuer, Zero::zero(), bytes)?;
+        <ClassDeposits<T>>::mutate(&class_id, |stored| match stored {
+            Some(stored) => stored.amount = stored.amount.saturating_add(deposit.amount),
+            None => *stored = Some(deposit),
+        });
+        mystery_box.revealed = true;
+        <MysteryBoxes<T>>::insert(class_id, mystery_box);
+
+        Ok(())
+    }
+}
+
 // NFT
 impl<T: Config> Module<T> {
+    /// Only the class owner and its minters may mint
     fn _mint_nft(
         class_id: ClassId,
         info: Vec<u8>,
         metadata: Vec<u8>,
         price: BalanceOf<T>,
         miner: T::AccountId,
-    ) -> Option<NFTId> {
-        if let Some(class_info) = Self::class_infos(class_id.clone()) {
-            let class_mint_index = Self::class_mint_index(class_id.clone()) + 1;
-            if class_info.total_supply >= class_mint_index {
-                let tnonce = Self::get_tnonce();
-                let random_seed = <randomness::Module<T>>::random_seed();
-                let encoded = (random_seed, miner.clone(), tnonce).encode();
-                let did = blake2_256(&encoded);
-                let new_nft_id = NFTId { did };
-
-                let new_nft = NFTInfo {
-                    class_id: class_id.clone(),
-                    index: class_mint_index.clone(),
-                    info: info.clone(),
-                    metadata: metadata.clone(),
-                    owner: miner.clone(),
-                    issuer: miner.clone(),
-                    price: price.clone(),
-                    status: NFTStatus::Normal,
-                };
-
-                <NFTInfos<T>>::insert(new_nft_id.clone(), &new_nft);
-                <NFTsCount>::put(tnonce.clone() + 1);
-                <NFTsIndex>::insert(tnonce.clone(), new_nft_id.clone());
-                <ClassMintIndex>::insert(class_id.clone(), class_mint_index.clone());
-                <NFTByClassIndex>::insert(
-                    class_id.clone(),
-                    class_mint_index.clone(),
-                    new_nft_id.clone(),
-                );
+        royalty: Option<RoyaltyInfo<T::AccountId>>,
+    ) -> Result<NFTId, DispatchError> {
+        Self::_ensure_class_minter(&miner, &class_id)?;
 
-                return Some(new_nft_id);
-            }
+        Self::_do_mint_nft(class_id, info, metadata, price, miner, royalty)
+    }
+
+    fn _do_mint_nft(
+        class_id: ClassId,
+        info: Vec<u8>,
+        metadata: Vec<u8>,
+        price: BalanceOf<T>,
+        miner: T::AccountId,
+        royalty: Option<RoyaltyInfo<T::AccountId>>,
+    ) -> Result<NFTId, DispatchError> {
+        let class_info = Self::class_infos(class_id.clone()).ok_or(Error::<T>::ClassNotExist)?;
+        let class_mint_index = Self::class_mint_index(class_id.clone()) + 1;
+        ensure!(
+            class_info.total_supply >= class_mint_index,
+            Error::<T>::IndexExceedTotalSupply
+        );
+        if let Some(mystery_box) = Self::mystery_boxes(&class_id) {
+            // a revealed box has handed out its metadata set
+            ensure!(!mystery_box.revealed, Error::<T>::MysteryBoxRevealed);
+            ensure!(
+                class_mint_index <= mystery_box.metadata_len,
+                Error::<T>::NotEnoughMetadata
+            );
+        }
+        Self::_ensure_nft_limits(&info, &metadata)?;
+        let deposit = Self::_reserve_deposit(
+            &miner,
+            T::NFTDepositBase::get(),
+            info.len() + metadata.len(),
+        )?;
+
+        let tnonce = Self::get_tnonce();
+        let random_seed = T::Randomness::random_seed();
+        let encoded = (random_seed, miner.clone(), tnonce).encode();
+        let did = blake2_256(&encoded);
+        let new_nft_id = NFTId { did };
+
+        let new_nft = NFTInfo {
+            class_id: class_id.clone(),
+            index: class_mint_index.clone(),
+            info: info.clone(),
+            metadata: metadata.clone(),
+            owner: miner.clone(),
+            issuer: miner.clone(),
+            price: price.clone(),
+            status: NFTStatus::Normal,
+        };
+
+        <NFTInfos<T>>::insert(new_nft_id.clone(), &new_nft);
+        <NFTsCount>::put(tnonce.clone() + 1);
+        <NFTsIndex>::insert(tnonce.clone(), new_nft_id.clone());
+        <ClassMintIndex>::insert(class_id.clone(), class_mint_index.clone());
+        <ClassSupply>::mutate(&class_id, |supply| *supply += 1);
+        <NFTByClassIndex>::insert(
+            class_id.clone(),
+            class_mint_index.clone(),
+            new_nft_id.clone(),
+        );
+        if let Some(royalty) = royalty {
+            <NFTRoyalties<T>>::insert(new_nft_id.clone(), royalty);
         }
-        None
+        <NFTDeposits<T>>::insert(new_nft_id.clone(), deposit);
+        let _ = Self::_add_nft_to_owned_nftids(miner.clone(), new_nft_id.clone());
+        let _ =
+            Self::_add_nft_to_owned_nft_sources(miner.clone(), class_id.clone(), class_mint_index);
+
+        Ok(new_nft_id)
     }
 
+    #[transactional]
     fn _transfer_single_nft(from: T::AccountId, to: T::AccountId, nft_id: NFTId) -> DispatchResult {
         let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
         ensure!(nft.owner == from.clone(), Error::<T>::NotNFTOwner);
         ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        ensure!(nft.status != NFTStatus::Locked, Error::<T>::NFTLocked);
+        ensure!(
+            nft.status != NFTStatus::Fractionalized,
+            Error::<T>::NFTFractionalized
+        );
+        ensure!(
+            nft.status != NFTStatus::InCollection,
+            Error::<T>::NFTInCollection
+        );
+        Self::_ensure_transferable(&nft.class_id, &from, &to)?;
 
+        Self::_clear_listing(&nft_id, &mut nft);
+        <RentOffers<T>>::remove(nft_id.clone());
         nft.owner = to.clone();
         <NFTInfos<T>>::insert(nft_id.clone(), &nft);
         Self::_remove_nft_from_owned_nftids(from.clone(), nft_id.clone())?;
         Self::_add_nft_to_owned_nftids(to.clone(), nft_id.clone())?;
+        Self::_remove_nft_from_owned_nft_sources(from.clone(), nft.class_id.clone(), nft.index)?;
+        Self::_add_nft_to_owned_nft_sources(to.clone(), nft.class_id.clone(), nft.index)?;
+
+        Self::_move_children(&nft_id, &from, &to)?;
 
         Ok(())
     }
 
-    fn _offer_nft(from: T::AccountId, nft_id: NFTId, new_price: BalanceOf<T>) -> DispatchResult {
+    #[transactional]
+    fn _offer_nft(
+        from: T::AccountId,
+        nft_id: NFTId,
+        new_price: BalanceOf<T>,
+        expiry: Option<T::BlockNumber>,
+    ) -> DispatchResult {
         let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
         ensure!(nft.owner == from.clone(), Error::<T>::NotNFTOwner);
         ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        let class_info =
+            Self::class_infos(nft.class_id.clone()).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(class_info.can_offer(&from), Error::<T>::NFTNotTransferable);
+        ensure!(nft.status != NFTStatus::Locked, Error::<T>::NFTLocked);
+        ensure!(
+            nft.status != NFTStatus::InCollection,
+            Error::<T>::NFTInCollection
+        );
+        if let Some(expiry) = expiry {
+            ensure!(
+                expiry > <frame_system::Module<T>>::block_number(),
+                Error::<T>::InvalidExpiry
+            );
+        }
 
         nft.price = new_price.clone();
         nft.status = NFTStatus::Offered;
         <NFTInfos<T>>::insert(nft_id.clone(), &nft);
 
+        // a re-offer replaces the pending expiry instead of adding a second one
+        Self::_remove_listing_expiry(&nft_id);
+        if let Some(expiry) = expiry {
+            <ListingExpiries<T>>::try_mutate(expiry, |nft_ids| -> DispatchResult {
+                ensure!(
+                    (nft_ids.len() as u32) < T::MaxExpiriesPerBlock::get(),
+                    Error::<T>::TooManyExpiries
+                );
+                nft_ids.push(nft_id.clone());
+                Ok(())
+            })?;
+            <NFTListingExpiry<T>>::insert(nft_id.clone(), expiry);
+        }
+
+        Ok(())
+    }
+
+    fn _cancel_offer_nft(from: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == from.clone(), Error::<T>::NotNFTOwner);
+        ensure!(nft.status == NFTStatus::Offered, Error::<T>::NFTNotForBuy);
+
+        Self::_clear_listing(&nft_id, &mut nft);
+        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+
         Ok(())
     }
 
+    fn _clear_listing(nft_id: &NFTId, nft: &mut NFTInfo<T::AccountId, BalanceOf<T>>) {
+        if nft.status == NFTStatus::Offered {
+            nft.status = NFTStatus::Normal;
+        }
+        Self::_remove_listing_expiry(nft_id);
+    }
+
+    fn _remove_listing_expiry(nft_id: &NFTId) {
+        if let Some(expiry) = <NFTListingExpiry<T>>::take(nft_id) {
+            <ListingExpiries<T>>::mutate_exists(expiry, |nft_ids| {
+                if let Some(ids) = nft_ids {
+                    ids.retain(|id| id != nft_id);
+                    if ids.is_empty() {
+                        *nft_ids = None;
+                    }
+                }
+            });
+        }
+    }
+
+    #[transactional]
     fn _buy_nft(who: T::AccountId, nft_id: NFTId) -> DispatchResult {
         let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
         let from = nft.owner.clone();
         ensure!(nft.owner != who.clone(), Error::<T>::NoPermission);
         ensure!(nft.status == NFTStatus::Offered, Error::<T>::NFTNotForBuy);
-        T::Currency::transfer(&who, &nft.owner, nft.price, ExistenceRequirement::KeepAlive)?;
-        nft.status = NFTStatus::Normal;
+        Self::_ensure_transferable(&nft.class_id, &from, &who)?;
+        Self::_pay_with_royalty(who.clone(), from.clone(), nft_id.clone(), nft.price)?;
+        Self::_clear_listing(&nft_id, &mut nft);
+        <RentOffers<T>>::remove(nft_id.clone());
         nft.owner = who.clone();
         Self::_remove_nft_from_owned_nftids(from.clone(), nft_id.clone())?;
         Self::_add_nft_to_owned_nftids(who.clone(), nft_id.clone())?;
+        Self::_remove_nft_from_owned_nft_sources(from.clone(), nft.class_id.clone(), nft.index)?;
+        Self::_add_nft_to_owned_nft_sources(who.clone(), nft.class_id.clone(), nft.index)?;
         <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+        Self::_move_children(&nft_id, &from, &who)?;
+
+        Ok(())
+    }
+
+    fn _ensure_transferable(
+        class_id: &ClassId,
+        from: &T::AccountId,
+        to: &T::AccountId,
+    ) -> DispatchResult {
+        let class_info = Self::class_infos(class_id).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(
+            class_info.can_transfer(from, to),
+            Error::<T>::NFTNotTransferable
+        );
+        Ok(())
+    }
+
+    fn _revoke_nft(who: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        let class_info =
+            Self::class_infos(nft.class_id.clone()).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(class_info.issuer == who, Error::<T>::NoPermission);
+        ensure!(
+            class_info.transfer_policy != TransferPolicy::Transferable,
+            Error::<T>::NFTNotRevocable
+        );
+
+        Self::_burn_nft(nft.owner, nft_id)
+    }
+
+    fn _royalty_of(nft_id: NFTId) -> Option<RoyaltyInfo<T::AccountId>> {
+        Self::nft_royalties(nft_id.clone())
+            .or_else(|| Self::nft_infos(nft_id).and_then(|nft| Self::class_royalties(nft.class_id)))
+    }
+
+    #[transactional]
+    fn _pay_with_royalty(
+        buyer: T::AccountId,
+        seller: T::AccountId,
+        nft_id: NFTId,
+        price: BalanceOf<T>,
+    ) -> DispatchResult {
+        let royalty = Self::_royalty_of(nft_id.clone());
+        if let Some((beneficiary, amount)) = helpers::pay_with_royalty::<_, T::Currency>(
+            PaymentSource::Free,
+            &buyer,
+            &seller,
+            price,
+            royalty,
+        )? {
+            Self::deposit_event(RawEvent::RoyaltyPaid(nft_id, beneficiary, amount));
+        }
 
         Ok(())
     }
@@ -499,7 +1557,34 @@ impl<T: Config> Module<T> {
         let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
         ensure!(nft.owner == who.clone(), Error::<T>::NoPermission);
         ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        ensure!(nft.status != NFTStatus::Locked, Error::<T>::NFTLocked);
+        ensure!(!Self::_has_children(&nft_id), Error::<T>::NFTHasChildren);
+        ensure!(
+            nft.status != NFTStatus::Fractionalized,
+            Error::<T>::NFTFractionalized
+        );
+        ensure!(
+            nft.status != NFTStatus::InCollection,
+            Error::<T>::NFTInCollection
+        );
+        Self::_clear_listing(&nft_id, &mut nft);
+        <RentOffers<T>>::remove(nft_id.clone());
+        if let Some(info) = <NFTUsers<T>>::take(&nft_id) {
+            <UserExpiries<T>>::mutate_exists(info.expiry, |nft_ids| {
+                if let Some(ids) = nft_ids {
+                    ids.retain(|id| id != &nft_id);
+                    if ids.is_empty() {
+                        *nft_ids = None;
+                    }
+                }
+            });
+        }
+        Self::_remove_nft_from_owned_nftids(who.clone(), nft_id.clone())?;
+        Self::_remove_nft_from_owned_nft_sources(who.clone(), nft.class_id.clone(), nft.index)?;
+        Self::_release_deposit(<NFTDeposits<T>>::take(&nft_id));
+        Self::_remove_metadata(&AttributeTarget::NFT(nft_id.clone()));
         nft.status = NFTStatus::Burned;
+        <ClassSupply>::mutate(&nft.class_id, |supply| *supply = supply.saturating_sub(1));
 
         <NFTInfos<T>>::insert(nft_id.clone(), &nft);
 
@@ -513,6 +1598,18 @@ impl<T: Config> Module<T> {
         nonce
     }
 
+    fn _ensure_nft_limits(info: &[u8], metadata: &[u8]) -> DispatchResult {
+        ensure!(
+            info.len() <= T::InfoLimit::get() as usize,
+            Error::<T>::InfoTooLong
+        );
+        ensure!(
+            metadata.len() <= T::InfoLimit::get() as usize,
+            Error::<T>::InfoTooLong
+        );
+        Ok(())
+    }
+
     fn _approve_single_nft(from: T::AccountId, to: T::AccountId, nft_id: NFTId) -> DispatchResult {
         let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
         ensure!(nft.owner == from.clone(), Error::<T>::NoPermission);
@@ -530,23 +1627,13 @@ impl<T: Config> Module<T> {
         class_id: ClassId,
         amount: u64,
     ) -> DispatchResult {
-        let owned_nfts = Self::owned_nft_source(from.clone());
-        for i in 0..owned_nfts.len() {
-            if owned_nfts[i].class_id == class_id.clone() {
-                ensure!(
-                    owned_nfts[i].amount >= amount.clone(),
-                    Error::<T>::NotEnoughtNFT
-                );
-                for j in 0..owned_nfts[i].nfts_indexs.len() {
-                    let nft_id = Self::nft_by_class_index(
-                        class_id.clone(),
-                        owned_nfts[i].nfts_indexs[j].clone(),
-                    )
-                    .ok_or(Error::<T>::NFTNotExist)?;
-
-                    Self::_transfer_single_nft(from.clone(), to.clone(), nft_id)?;
-                }
-            }
+        let class_info = Self::class_infos(class_id.clone()).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(
+            class_info.can_transfer(&from, &to),
+            Error::<T>::NFTNotTransferable
+        );
+        for nft_id in Self::_owned_class_nfts(from.clone(), class_id, amount)? {
+            Self::_transfer_single_nft(from.clone(), to.clone(), nft_id)?;
         }
         Ok(())
     }
@@ -557,45 +1644,15 @@ impl<T: Config> Module<T> {
         class_id: ClassId,
         amount: u64,
     ) -> DispatchResult {
-        let owned_nfts = Self::owned_nft_source(who.clone());
-        for i in 0..owned_nfts.len() {
-            if owned_nfts[i].class_id == class_id.clone() {
-                ensure!(
-                    owned_nfts[i].amount >= amount.clone(),
-                    Error::<T>::NotEnoughtNFT
-                );
-                for j in 0..owned_nfts[i].nfts_indexs.len() {
-                    let nft_id = Self::nft_by_class_index(
-                        class_id.clone(),
-                        owned_nfts[i].nfts_indexs[j].clone(),
-                    )
-                    .ok_or(Error::<T>::NFTNotExist)?;
-
-                    Self::_approve_single_nft(who.clone(), to.clone(), nft_id)?;
-                }
-            }
+        for nft_id in Self::_owned_class_nfts(who.clone(), class_id, amount)? {
+            Self::_approve_single_nft(who.clone(), to.clone(), nft_id)?;
         }
         Ok(())
     }
 
     fn _destroy_batch_nft(who: T::AccountId, class_id: ClassId, amount: u64) -> DispatchResult {
-        let owned_nfts = Self::owned_nft_source(who.clone());
-        for i in 0..owned_nfts.len() {
-            if owned_nfts[i].class_id == class_id.clone() {
-                ensure!(
-                    owned_nfts[i].amount >= amount.clone(),
-                    Error::<T>::NotEnoughtNFT
-                );
-                for j in 0..owned_nfts[i].nfts_indexs.len() {
-                    let nft_id = Self::nft_by_class_index(
-                        class_id.clone(),
-                        owned_nfts[i].nfts_indexs[j].clone(),
-                    )
-                    .ok_or(Error::<T>::NFTNotExist)?;
-
-                    Self::_burn_nft(who.clone(), nft_id)?;
-                }
-            }
+        for nft_id in Self::_owned_class_nfts(who.clone(), class_id, amount)? {
+            Self::_burn_nft(who.clone(), nft_id)?;
         }
         Ok(())
     }
@@ -603,27 +1660,34 @@ impl<T: Config> Module<T> {
 
 // collection
 impl<T: Config> Module<T> {
+    #[transactional]
     fn _coupled_collection(
         name: Vec<u8>,
         symbol: Vec<u8>,
         owner: T::AccountId,
         info: Vec<u8>,
         price: BalanceOf<T>,
-        source: Vec<NFTSource<ClassId>>,
+        members: Vec<(NFTType, NFTId)>,
     ) -> DispatchResult {
-        for i in 0..source.len() {
-            for j in 0..source[i].nfts_indexs.len() {
-                let nft_id = Self::nft_by_class_index(
-                    source[i].class_id.clone(),
-                    source[i].nfts_indexs[j].clone(),
-                )
-                .ok_or(Error::<T>::NFTNotExist)?;
-
-                Self::_collection_single_nft(owner.clone(), nft_id)?;
-            }
+        ensure!(
+            name.len() <= T::NameLimit::get() as usize
+                && symbol.len() <= T::NameLimit::get() as usize,
+            Error::<T>::NameTooLong
+        );
+        ensure!(
+            info.len() <= T::InfoLimit::get() as usize,
+            Error::<T>::InfoTooLong
+        );
+        for (nft_type, nft_id) in members.iter() {
+            Self::_collection_single_nft(owner.clone(), *nft_type, nft_id.clone())?;
         }
+        let deposit = Self::_reserve_deposit(
+            &owner,
+            T::CollectionDepositBase::get(),
+            name.len() + symbol.len() + info.len(),
+        )?;
         let cnonce = Self::get_cononce();
-        let random_seed = <randomness::Module<T>>::random_seed();
+        let random_seed = T::Randomness::random_seed();
         let encoded = (random_seed, owner.clone(), cnonce).encode();
         let did = blake2_256(&encoded);
         let new_collection_id = CollectionId { did };
@@ -634,29 +1698,45 @@ impl<T: Config> Module<T> {
             owner: owner.clone(),
             issuer: owner.clone(),
             price: price.clone(),
-            source: source.clone(),
+            members,
             status: CollectionStatus::Normal,
         };
 
         <Collections<T>>::insert(new_collection_id.clone(), &new_collection);
         <CollectionsCount>::put(cnonce.clone() + 1);
         <CollectionsIndex>::insert(cnonce.clone(), new_collection_id.clone());
+        <CollectionDeposits<T>>::insert(new_collection_id.clone(), deposit);
 
         Self::_add_collection_to_owned_collections(owner.clone(), new_collection_id.clone())?;
 
         Ok(())
     }
 
+    /// Members go back to `Normal` under the collection owner, who gets the deposit back.
+    #[transactional]
     fn _decoupled_collection(from: T::AccountId, collection_id: CollectionId) -> DispatchResult {
         let mut collection =
             Self::collections(collection_id.clone()).ok_or(Error::<T>::CollectionNotExist)?;
         ensure!(collection.owner == from.clone(), Error::<T>::NoPermission);
+        ensure!(
+            collection.status == CollectionStatus::Normal
+                || collection.status == CollectionStatus::Offered,
+            Error::<T>::CollectionNotActive
+        );
+
+        for (nft_type, nft_id) in collection.members.iter() {
+            Self::_release_collection_nft(collection.owner.clone(), *nft_type, nft_id.clone())?;
+        }
+
         collection.status = CollectionStatus::Decoupled;
         <Collections<T>>::insert(collection_id.clone(), &collection);
+        Self::_release_deposit(<CollectionDeposits<T>>::take(&collection_id));
+        Self::_remove_collection_from_owned_collections(from, collection_id)?;
 
         Ok(())
     }
 
+    #[transactional]
     fn _transfer_collection(
         who: T::AccountId,
         from: T::AccountId,
@@ -670,29 +1750,103 @@ impl<T: Config> Module<T> {
             Error::<T>::NotCollectionOwner
         );
 
-        let is_legal = CollectionStatus::Normal == collection.status;
+        let is_legal = CollectionStatus::Normal == collection.status
+            || CollectionStatus::Offered == collection.status;
         let is_owner = who == collection.owner;
         let is_approved_for_all = Self::is_approved_for_all((from.clone(), who.clone()));
 
-        ensure!(
-            is_legal || is_owner || is_approved_for_all,
-            Error::<T>::NoPermission
-        );
+        ensure!(is_legal, Error::<T>::CollectionNotActive);
+        ensure!(is_owner || is_approved_for_all, Error::<T>::NoPermission);
 
         collection.owner = to.clone();
-        <Collections<T>>::insert(collection_id.clone(), &collection);
-
-        Self::_remove_collection_from_owned_collections(from.clone(), collection_id.clone())?;
-        Self::_add_collection_to_owned_collections(to.clone(), collection_id.clone())?;
-        Ok(())
+        collection.status = CollectionStatus::Normal;
+        Self::_move_collection(&collection, collection_id, from, to)
     }
 
-    fn _approve_collection(
+    /// Moves the collection and every member NFT; the caller checks permissions.
+    fn _move_collection(
+        collection: &Collection<T::AccountId, BalanceOf<T>, CollectionStatus>,
+        collection_id: CollectionId,
         from: T::AccountId,
         to: T::AccountId,
-        collection_id: CollectionId,
     ) -> DispatchResult {
-        let collection =
+        for (nft_type, nft_id) in collection.members.iter() {
+            T::NFT::unlock_nft(*nft_type, nft_id.clone())?;
+            T::NFT::transfer_single_nft(*nft_type, from.clone(), to.clone(), nft_id.clone())?;
+            T::NFT::lock_nft(*nft_type, nft_id.clone())?;
+        }
+
+        <Collections<T>>::insert(collection_id.clone(), collection);
+        Self::_remove_collection_from_owned_collections(from, collection_id.clone())?;
+        Self::_add_collection_to_owned_collections(to, collection_id)?;
+        Ok(())
+    }
+
+    fn _offer_collection(
+        from: T::AccountId,
+        collection_id: CollectionId,
+        new_price: BalanceOf<T>,
+    ) -> DispatchResult {
+        let mut collection =
+            Self::collections(collection_id.clone()).ok_or(Error::<T>::CollectionNotExist)?;
+        ensure!(collection.owner == from, Error::<T>::NotCollectionOwner);
+        ensure!(
+            collection.status == CollectionStatus::Normal
+                || collection.status == CollectionStatus::Offered,
+            Error::<T>::CollectionNotActive
+        );
+
+        collection.price = new_price;
+        collection.status = CollectionStatus::Offered;
+        <Collections<T>>::insert(collection_id, &collection);
+
+        Ok(())
+    }
+
+    fn _cancel_offer_collection(from: T::AccountId, collection_id: CollectionId) -> DispatchResult {
+        let mut collection =
+            Self::collections(collection_id.clone()).ok_or(Error::<T>::CollectionNotExist)?;
+        ensure!(collection.owner == from, Error::<T>::NotCollectionOwner);
+        ensure!(
+            collection.status == CollectionStatus::Offered,
+            Error::<T>::CollectionNotForBuy
+        );
+
+        collection.status = CollectionStatus::Normal;
+        <Collections<T>>::insert(collection_id, &collection);
+
+        Ok(())
+    }
+
+    #[transactional]
+    fn _buy_collection(who: T::AccountId, collection_id: CollectionId) -> DispatchResult {
+        let mut collection =
+            Self::collections(collection_id.clone()).ok_or(Error::<T>::CollectionNotExist)?;
+        let from = collection.owner.clone();
+        ensure!(from != who, Error::<T>::NoPermission);
+        ensure!(
+            collection.status == CollectionStatus::Offered,
+            Error::<T>::CollectionNotForBuy
+        );
+
+        T::Currency::transfer(
+            &who,
+            &from,
+            collection.price,
+            ExistenceRequirement::KeepAlive,
+        )?;
+
+        collection.owner = who.clone();
+        collection.status = CollectionStatus::Normal;
+        Self::_move_collection(&collection, collection_id, from, who)
+    }
+
+    fn _approve_collection(
+        from: T::AccountId,
+        to: T::AccountId,
+        collection_id: CollectionId,
+    ) -> DispatchResult {
+        let collection =
             Self::collections(collection_id.clone()).ok_or(Error::<T>::CollectionNotExist)?;
         ensure!(collection.owner == from.clone(), Error::<T>::NoPermission);
         <CollectionApprovers<T>>::insert((to.clone(), collection_id.clone()), true);
@@ -700,25 +1854,62 @@ impl<T: Config> Module<T> {
         Ok(())
     }
 
-    fn _destroy_collection(from: T::AccountId, collection_id: CollectionId) -> DispatchResult {
+    /// Members are burned with `burn_nfts`, otherwise released like `_decoupled_collection`.
+    #[transactional]
+    fn _destroy_collection(
+        from: T::AccountId,
+        collection_id: CollectionId,
+        burn_nfts: bool,
+    ) -> DispatchResult {
         let mut collection =
             Self::collections(collection_id.clone()).ok_or(Error::<T>::CollectionNotExist)?;
         ensure!(collection.owner == from.clone(), Error::<T>::NoPermission);
+        ensure!(
+            collection.status == CollectionStatus::Normal
+                || collection.status == CollectionStatus::Offered,
+            Error::<T>::CollectionNotActive
+        );
+
+        for (nft_type, nft_id) in collection.members.iter() {
+            Self::_release_collection_nft(collection.owner.clone(), *nft_type, nft_id.clone())?;
+            if burn_nfts {
+                T::NFT::burn_nft(*nft_type, collection.owner.clone(), nft_id.clone())?;
+            }
+        }
+
         collection.status = CollectionStatus::Burned;
         <Collections<T>>::insert(collection_id.clone(), &collection);
-        // todo
-        // destroy nft
+        Self::_release_deposit(<CollectionDeposits<T>>::take(&collection_id));
+        Self::_remove_collection_from_owned_collections(from, collection_id)?;
 
         Ok(())
     }
 
-    fn _collection_single_nft(who: T::AccountId, nft_id: NFTId) -> DispatchResult {
-        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
-        ensure!(nft.owner == who.clone(), Error::<T>::NoPermission);
-        nft.status = NFTStatus::InCollection;
-        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+    /// Members stay locked in their own pallet while the collection exists.
+    fn _collection_single_nft(
+        who: T::AccountId,
+        nft_type: NFTType,
+        nft_id: NFTId,
+    ) -> DispatchResult {
+        ensure!(
+            T::NFT::owner_of(nft_type, nft_id.clone()) == Some(who),
+            Error::<T>::NoPermission
+        );
+        T::NFT::lock_nft(nft_type, nft_id)
+    }
 
-        Ok(())
+    fn _release_collection_nft(
+        owner: T::AccountId,
+        nft_type: NFTType,
+        nft_id: NFTId,
+    ) -> DispatchResult {
+        T::NFT::unlock_nft(nft_type, nft_id.clone())?;
+        match T::NFT::owner_of(nft_type, nft_id.clone()) {
+            Some(current) if current != owner => {
+                T::NFT::transfer_single_nft(nft_type, current, owner, nft_id)
+            }
+            _ => Ok(()),
+        }
     }
 
     fn _add_collection_to_owned_collections(
@@ -770,120 +1961,728 @@ impl<T: Config> Module<T> {
 impl<T: Config> Module<T> {
     pub fn _add_nft_to_owned_nftids(owner: T::AccountId, nft_id: NFTId) -> DispatchResult {
         ensure!(
-            !Self::owned_nft_ids(owner.clone()).contains(&nft_id),
+            !<OwnedNFTIds<T>>::contains_key(owner.clone(), nft_id.clone()),
             Error::<T>::NFTAlreadyOwned
         );
 
-        let mut owned_nfts = Self::owned_nft_ids(owner.clone());
+        <OwnedNFTIds<T>>::insert(owner.clone(), nft_id, ());
+        <OwnedNFTCount<T>>::mutate(owner, |count| *count += 1);
 
-        owned_nfts.push(nft_id.clone());
+        Ok(())
+    }
+    pub fn _remove_nft_from_owned_nftids(owner: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        ensure!(
+            <OwnedNFTIds<T>>::contains_key(owner.clone(), nft_id.clone()),
+            Error::<T>::NFTNotOwned
+        );
 
-        <OwnedNFTIds<T>>::insert(owner, owned_nfts);
+        <OwnedNFTIds<T>>::remove(owner.clone(), nft_id);
+        <OwnedNFTCount<T>>::mutate(owner, |count| *count = count.saturating_sub(1));
 
         Ok(())
     }
-    pub fn _remove_nft_from_owned_nftids(owner: T::AccountId, nft_id: NFTId) -> DispatchResult {
+
+    fn _add_nft_to_owned_nft_sources(
+        owner: T::AccountId,
+        class_id: ClassId,
+        nfts_index: u64,
+    ) -> DispatchResult {
+        let key = (owner, class_id);
+        ensure!(
+            !<OwnedClassNFTs<T>>::contains_key(key.clone(), nfts_index),
+            Error::<T>::NFTAlreadyOwned
+        );
+
+        <OwnedClassNFTs<T>>::insert(key.clone(), nfts_index, ());
+        <OwnedClassNFTCount<T>>::mutate(key, |count| *count += 1);
+
+        Ok(())
+    }
+
+    fn _remove_nft_from_owned_nft_sources(
+        owner: T::AccountId,
+        class_id: ClassId,
+        nfts_index: u64,
+    ) -> DispatchResult {
+        let key = (owner, class_id);
         ensure!(
-            Self::owned_nft_ids(owner.clone()).contains(&nft_id),
+            <OwnedClassNFTs<T>>::contains_key(key.clone(), nfts_index),
             Error::<T>::NFTNotOwned
         );
 
-        let mut owned_nfts = Self::owned_nft_ids(owner.clone());
+        <OwnedClassNFTs<T>>::remove(key.clone(), nfts_index);
+        <OwnedClassNFTCount<T>>::mutate(key, |count| *count = count.saturating_sub(1));
 
-        let mut j = 0;
+        Ok(())
+    }
 
-        for i in &owned_nfts {
-            if *i == nft_id.clone() {
-                owned_nfts.remove(j);
+    /// The first `amount` NFTs of `class_id` held by `owner`
+    fn _owned_class_nfts(
+        owner: T::AccountId,
+        class_id: ClassId,
+        amount: u64,
+    ) -> Result<Vec<NFTId>, DispatchError> {
+        ensure!(
+            Self::owned_class_nft_count((owner.clone(), class_id.clone())) >= amount,
+            Error::<T>::NotEnoughtNFT
+        );
 
-                break;
-            }
+        <OwnedClassNFTs<T>>::iter_prefix((owner, class_id.clone()))
+            .take(amount as usize)
+            .map(|(nfts_index, _)| {
+                Self::nft_by_class_index(class_id.clone(), nfts_index)
+                    .ok_or_else(|| Error::<T>::NFTNotExist.into())
+            })
+            .collect()
+    }
+}
+
+// Vault
+impl<T: Config> Module<T> {
+    /// Custody account for fractionalized NFTs and buyout proceeds
+    pub fn account_id() -> T::AccountId {
+        T::ModuleId::get().into_account()
+    }
+
+    #[transactional]
+    fn _fragment_nft(
+        who: T::AccountId,
+        nft_id: NFTId,
+        total_supply: T::Balance,
+        symbol: Vec<u8>,
+        reserve_price: BalanceOf<T>,
+    ) -> DispatchResult {
+        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == who, Error::<T>::NotNFTOwner);
+        ensure!(
+            nft.status == NFTStatus::Normal || nft.status == NFTStatus::Offered,
+            Error::<T>::NFTLocked
+        );
+        ensure!(
+            Self::nft_shift_token(nft_id.clone()).is_none(),
+            Error::<T>::NFTAlreadyShiftINFragmentation
+        );
+        ensure!(!Self::_has_children(&nft_id), Error::<T>::NFTHasChildren);
+        ensure!(!total_supply.is_zero(), Error::<T>::NotEnoughFragments);
+        Self::_ensure_transferable(&nft.class_id, &who, &Self::account_id())?;
+
+        // the vault administers the fragments so the curator can't dilute or seize them
+        let token_id =
+            T::Token::issue_capped(Self::account_id(), who.clone(), total_supply, symbol)?;
+
+        Self::_clear_listing(&nft_id, &mut nft);
+        <RentOffers<T>>::remove(nft_id.clone());
+        Self::_move_nft(&nft_id, &mut nft, Self::account_id())?;
+        nft.status = NFTStatus::Fractionalized;
+        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+
+        <NFTShiftToken>::insert(&nft_id, &token_id);
+        <Vaults<T>>::insert(
+            nft_id,
+            Vault {
+                token_id,
+                curator: who,
+                total_supply,
+                reserve_price,
+                status: VaultStatus::Active,
+                bidder: None,
+                bid: Zero::zero(),
+                auction_end: Zero::zero(),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Burns every fragment and hands the NFT to `who`.
+    #[transactional]
+    fn _redeem_nft(who: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        let vault = Self::vaults(nft_id.clone()).ok_or(Error::<T>::VaultNotExist)?;
+        ensure!(
+            vault.status == VaultStatus::Active,
+            Error::<T>::VaultNotActive
+        );
+        let supply = T::Token::total_supply(vault.token_id.clone());
+        ensure!(
+            T::Token::balance_of(who.clone(), vault.token_id.clone()) >= supply,
+            Error::<T>::NotEnoughFragments
+        );
+
+        T::Token::burn(who.clone(), vault.token_id, supply)?;
+        Self::_release_nft(nft_id, who)
+    }
+
+    fn _start_buyout(who: T::AccountId, nft_id: NFTId, bid: BalanceOf<T>) -> DispatchResult {
+        let mut vault = Self::vaults(nft_id.clone()).ok_or(Error::<T>::VaultNotExist)?;
+        ensure!(
+            vault.status == VaultStatus::Active,
+            Error::<T>::VaultNotActive
+        );
+        ensure!(
+            !T::Token::balance_of(who.clone(), vault.token_id.clone()).is_zero(),
+            Error::<T>::NotEnoughFragments
+        );
+        ensure!(bid >= vault.reserve_price, Error::<T>::BidTooLow);
+
+        T::Currency::reserve(&who, bid)?;
+
+        let auction_end = <frame_system::Module<T>>::block_number() + T::BuyoutPeriod::get();
+        vault.status = VaultStatus::Auction;
+        vault.bidder = Some(who);
+        vault.bid = bid;
+        vault.auction_end = auction_end;
+        <Vaults<T>>::insert(nft_id.clone(), vault);
+        <BuyoutEnds<T>>::append(auction_end, nft_id);
+
+        Ok(())
+    }
+
+    fn _bid_buyout(who: T::AccountId, nft_id: NFTId, bid: BalanceOf<T>) -> DispatchResult {
+        let mut vault = Self::vaults(nft_id.clone()).ok_or(Error::<T>::VaultNotExist)?;
+        ensure!(
+            vault.status == VaultStatus::Auction,
+            Error::<T>::VaultNotActive
+        );
+        ensure!(bid > vault.bid, Error::<T>::BidTooLow);
 
-            j += 1;
+        T::Currency::reserve(&who, bid)?;
+        if let Some(previous) = vault.bidder.take() {
+            T::Currency::unreserve(&previous, vault.bid);
         }
 
-        <OwnedNFTIds<T>>::insert(owner, owned_nfts);
+        vault.bidder = Some(who);
+        vault.bid = bid;
+        <Vaults<T>>::insert(nft_id, vault);
 
         Ok(())
     }
 
-    fn _add_nft_to_owned_nft_sources(owner: T::AccountId, class_id: ClassId, nfts_index: u64) {
-        if let Some(_nft_id) = Self::nft_by_class_index(class_id.clone(), nfts_index.clone()) {
-            let mut owned_nfts = Self::owned_nft_source(owner.clone());
-            let mut nfts_exist_flag = false;
-            let mut nft_exist_flag = false;
-            for i in owned_nfts.clone() {
-                if i.class_id == class_id.clone() {
-                    nfts_exist_flag = true;
-                    for j in i.nfts_indexs.clone() {
-                        if j == nfts_index.clone() {
-                            nft_exist_flag = true;
-                        }
-                    }
-                }
+    /// The winning bid is split pro-rata between fragment holders, claimed through `claim_proceeds`.
+    #[transactional]
+    fn _settle_buyout(nft_id: NFTId, now: T::BlockNumber) -> DispatchResult {
+        let vault = Self::vaults(nft_id.clone()).ok_or(Error::<T>::VaultNotExist)?;
+        ensure!(
+            vault.status == VaultStatus::Auction && vault.auction_end == now,
+            Error::<T>::VaultNotActive
+        );
+        let winner = vault.bidder.ok_or(Error::<T>::VaultNotActive)?;
+
+        // a plain transfer also opens the custody account on its first buyout
+        T::Currency::unreserve(&winner, vault.bid);
+        T::Currency::transfer(
+            &winner,
+            &Self::account_id(),
+            vault.bid,
+            ExistenceRequirement::AllowDeath,
+        )?;
+        <BuyoutProceeds<T>>::insert(vault.token_id, (vault.bid, vault.total_supply));
+        Self::_release_nft(nft_id.clone(), winner.clone())?;
+
+        Self::deposit_event(RawEvent::BuyoutSettled(nft_id, winner, vault.bid));
+
+        Ok(())
+    }
+
+    /// Burns the caller's fragments for their share of what is left of the proceeds.
+    /// Fragments burned outside the vault hand their share to the remaining holders.
+    #[transactional]
+    fn _claim_proceeds(who: T::AccountId, token_id: Did) -> Result<BalanceOf<T>, DispatchError> {
+        let (proceeds, outstanding) =
+            Self::buyout_proceeds(token_id.clone()).ok_or(Error::<T>::NoBuyoutProceeds)?;
+        let outstanding = outstanding.min(T::Token::total_supply(token_id.clone()));
+        let amount = T::Token::balance_of(who.clone(), token_id.clone()).min(outstanding);
+        ensure!(!amount.is_zero(), Error::<T>::NotEnoughFragments);
+
+        let payout = multiply_by_rational(
+            proceeds.saturated_into(),
+            amount.saturated_into(),
+            outstanding.saturated_into(),
+        )
+        .unwrap_or(0)
+        .saturated_into::<BalanceOf<T>>()
+        .min(proceeds);
+
+        T::Token::burn(who.clone(), token_id.clone(), amount)?;
+        T::Currency::transfer(
+            &Self::account_id(),
+            &who,
+            payout,
+            ExistenceRequirement::AllowDeath,
+        )?;
+
+        if amount == outstanding {
+            <BuyoutProceeds<T>>::remove(token_id);
+        } else {
+            <BuyoutProceeds<T>>::insert(
+                token_id,
+                (proceeds.saturating_sub(payout), outstanding - amount),
+            );
+        }
+
+        Ok(payout)
+    }
+
+    fn _release_nft(nft_id: NFTId, to: T::AccountId) -> DispatchResult {
+        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        Self::_move_nft(&nft_id, &mut nft, to)?;
+        nft.status = NFTStatus::Normal;
+        <NFTInfos<T>>::insert(nft_id.clone(), &nft);
+
+        <Vaults<T>>::remove(nft_id.clone());
+        <NFTShiftToken>::remove(nft_id);
+
+        Ok(())
+    }
+
+    fn _move_nft(
+        nft_id: &NFTId,
+        nft: &mut NFTInfo<T::AccountId, BalanceOf<T>>,
+        to: T::AccountId,
+    ) -> DispatchResult {
+        let from = sp_std::mem::replace(&mut nft.owner, to.clone());
+        Self::_remove_nft_from_owned_nftids(from.clone(), nft_id.clone())?;
+        Self::_add_nft_to_owned_nftids(to.clone(), nft_id.clone())?;
+        Self::_remove_nft_from_owned_nft_sources(from, nft.class_id.clone(), nft.index)?;
+        Self::_add_nft_to_owned_nft_sources(to, nft.class_id.clone(), nft.index)
+    }
+}
+
+// RPC
+impl<T: Config> Module<T> {
+    /// At most `page_size` NFTs owned by `owner`, skipping the first `page * page_size`
+    pub fn owned_nfts_paged(owner: T::AccountId, page: u32, page_size: u32) -> Vec<NFTId> {
+        <OwnedNFTIds<T>>::iter_prefix(owner)
+            .skip((page as usize).saturating_mul(page_size as usize))
+            .take(page_size as usize)
+            .map(|(nft_id, _)| nft_id)
+            .collect()
+    }
+
+    /// Same as `owned_nfts_paged`, restricted to one class
+    pub fn owned_class_nfts_paged(
+        owner: T::AccountId,
+        class_id: ClassId,
+        page: u32,
+        page_size: u32,
+    ) -> Vec<NFTId> {
+        <OwnedClassNFTs<T>>::iter_prefix((owner, class_id.clone()))
+            .skip((page as usize).saturating_mul(page_size as usize))
+            .take(page_size as usize)
+            .filter_map(|(nfts_index, _)| Self::nft_by_class_index(class_id.clone(), nfts_index))
+            .collect()
+    }
+}
+
+// Migration
+impl<T: Config> Module<T> {
+    /// V1 kept a `Vec` per account; V2 moves to double maps with counters.
+    /// The old vectors missed some mints, so the indexes are rebuilt from `NFTInfos`.
+    /// V1 classes had no transfer policy and become `Transferable`.
+    fn migrate_to_v2() -> Weight {
+        if Self::storage_version() != Releases::V1 {
+            return 0;
+        }
+
+        let mut class_count = 0u64;
+        <ClassInfos<T>>::translate(|_, old: ClassInfoV1<T::AccountId>| {
+            class_count += 1;
+            Some(old.into())
+        });
+
+        <OwnedNFTIds<T>>::remove_all();
+        <OwnedNFTCount<T>>::remove_all();
+        remove_storage_prefix(b"NFT2006", b"OwnedNFTSource", &[]);
+        <OwnedClassNFTs<T>>::remove_all();
+        <OwnedClassNFTCount<T>>::remove_all();
+
+        let mut count = 0u64;
+        for (nft_id, nft) in <NFTInfos<T>>::iter() {
+            count += 1;
+            if nft.status != NFTStatus::Burned {
+                let _ = Self::_add_nft_to_owned_nftids(nft.owner.clone(), nft_id);
+                let _ = Self::_add_nft_to_owned_nft_sources(nft.owner, nft.class_id, nft.index);
             }
-            if !nft_exist_flag {
-                if nfts_exist_flag {
-                    for i in 0..owned_nfts.len() {
-                        if owned_nfts[i].class_id == class_id.clone() {
-                            owned_nfts[i].amount += 1;
-                            owned_nfts[i].nfts_indexs.push(nfts_index.clone());
+        }
+
+        <PalletStorageVersion>::put(Releases::V2);
+
+        T::DbWeight::get().reads_writes(1 + class_count + 3 * count, 1 + class_count + 4 * count)
+    }
+
+    /// V2 collections listed NFT2006 class indexes and parked members in `InCollection`.
+    /// V3 lists `(NFTType, NFTId)` members and locks those of live collections.
+    /// Class nonces and live supplies are backfilled for `destroy_class`.
+    fn migrate_to_v3() -> Weight {
+        if Self::storage_version() != Releases::V2 {
+            return 0;
+        }
+
+        let mut class_count = 0u64;
+        for (nonce, class_id) in <ClassIndex>::iter() {
+            class_count += 1;
+            <ClassNonces>::insert(class_id, nonce);
+        }
+
+        <ClassSupply>::remove_all();
+        let mut nft_count = 0u64;
+        <NFTInfos<T>>::translate(|_, mut nft: NFTInfo<T::AccountId, BalanceOf<T>>| {
+            nft_count += 1;
+            if nft.status == NFTStatus::InCollection {
+                nft.status = NFTStatus::Normal;
+            }
+            if nft.status != NFTStatus::Burned {
+                <ClassSupply>::mutate(&nft.class_id, |supply| *supply += 1);
+            }
+            Some(nft)
+        });
+
+        let mut members_count = 0u64;
+        <Collections<T>>::translate(|_, old: CollectionV2<T::AccountId, BalanceOf<T>>| {
+            let members: Vec<(NFTType, NFTId)> =
+                old.source
+                    .iter()
+                    .flat_map(|source| {
+                        source.nfts_indexs.iter().filter_map(move |index| {
+                            Self::nft_by_class_index(&source.class_id, index)
+                        })
+                    })
+                    .map(|nft_id| (NFTType::NFT2006, nft_id))
+                    .collect();
+            let live =
+                old.status == CollectionStatus::Normal || old.status == CollectionStatus::Offered;
+            for (_, nft_id) in members.iter() {
+                members_count += 1;
+                if live {
+                    <NFTInfos<T>>::mutate(nft_id, |nft| {
+                        if let Some(nft) = nft {
+                            nft.status = NFTStatus::Locked;
                         }
-                    }
-                    <OwnedNFTSource<T>>::insert(owner, owned_nfts);
-                } else {
-                    let new_owned_nfts = NFTSource {
-                        class_id: class_id.clone(),
-                        amount: 1,
-                        nfts_indexs: vec![nfts_index.clone()],
-                    };
-                    owned_nfts.push(new_owned_nfts);
-                    <OwnedNFTSource<T>>::insert(owner, owned_nfts);
+                    });
                 }
             }
+
+            Some(Collection {
+                name: old.name,
+                symbol: old.symbol,
+                info: old.info,
+                owner: old.owner,
+                issuer: old.issuer,
+                price: old.price,
+                members,
+                status: old.status,
+            })
+        });
+
+        <PalletStorageVersion>::put(Releases::V3);
+
+        T::DbWeight::get().reads_writes(
+            1 + class_count + 2 * nft_count + 2 * members_count,
+            1 + class_count + 2 * nft_count + members_count,
+        )
+    }
+}
+
+/// `Collection` layout before `Releases::V3`
+#[derive(Decode)]
+struct CollectionV2<AccountId, Balance> {
+    name: Vec<u8>,
+    symbol: Vec<u8>,
+    info: Vec<u8>,
+    owner: AccountId,
+    issuer: AccountId,
+    price: Balance,
+    source: Vec<NFTSource<ClassId>>,
+    status: CollectionStatus,
+}
+
+// Composition
+impl<T: Config> Module<T> {
+    /// The child stays locked in place; its ownership follows the root of the tree
+    #[transactional]
+    fn _attach_nft(who: T::AccountId, nft_id: NFTId, parent: (NFTType, NFTId)) -> DispatchResult {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == who, Error::<T>::NotNFTOwner);
+        ensure!(
+            Self::nft_parents(nft_id.clone()).is_none(),
+            Error::<T>::NFTAlreadyAttached
+        );
+        let child = (NFTType::NFT2006, nft_id.clone());
+        helpers::check_attach::<_, BalanceOf<T>, T::NFT>(
+            &who,
+            &child,
+            &parent,
+            T::MaxNestingDepth::get(),
+        )
+        .map_err(Error::<T>::from)?;
+
+        Self::_lock_nft(nft_id.clone())?;
+        T::NFT::add_child(parent.0, parent.1.clone(), child)?;
+        <NFTParents>::insert(nft_id, parent);
+
+        Ok(())
+    }
+
+    #[transactional]
+    fn _detach_nft(who: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        let (parent_type, parent_id) =
+            Self::nft_parents(nft_id.clone()).ok_or(Error::<T>::NFTNotAttached)?;
+        ensure!(
+            T::NFT::owner_of(parent_type, parent_id.clone()) == Some(who),
+            Error::<T>::NoPermission
+        );
+
+        T::NFT::remove_child(parent_type, parent_id, (NFTType::NFT2006, nft_id.clone()))?;
+        <NFTParents>::remove(nft_id.clone());
+        Self::_unlock_nft(nft_id)
+    }
+
+    fn _move_children(nft_id: &NFTId, from: &T::AccountId, to: &T::AccountId) -> DispatchResult {
+        let children = <NFTChildren>::iter_prefix(nft_id)
+            .map(|(child, _)| child)
+            .collect();
+        helpers::move_children::<_, BalanceOf<T>, T::NFT>(children, from, to)
+    }
+
+    fn _has_children(nft_id: &NFTId) -> bool {
+        <NFTChildren>::iter_prefix(nft_id).next().is_some()
+    }
+}
+
+// Deposit
+impl<T: Config> Module<T> {
+    /// `base` plus `DepositPerByte` for every stored byte
+    fn _reserve_deposit(
+        who: &T::AccountId,
+        base: BalanceOf<T>,
+        bytes: usize,
+    ) -> Result<StorageDeposit<T::AccountId, BalanceOf<T>>, DispatchError> {
+        helpers::reserve_deposit::<_, T::Currency>(who, base, T::DepositPerByte::get(), bytes)
+    }
+
+    fn _release_deposit(deposit: Option<StorageDeposit<T::AccountId, BalanceOf<T>>>) {
+        helpers::release_deposit::<_, T::Currency>(deposit)
+    }
+}
+
+// Metadata
+impl<T: Config> Module<T> {
+    /// Class attributes belong to the class admin, NFT attributes to the NFT issuer
+    fn _ensure_metadata_issuer(who: &T::AccountId, target: &AttributeTarget) -> DispatchResult {
+        match target {
+            AttributeTarget::Class(class_id) => {
+                Self::_ensure_class_admin(who, class_id)?;
+            }
+            AttributeTarget::NFT(nft_id) => {
+                let nft = Self::nft_infos(nft_id).ok_or(Error::<T>::NFTNotExist)?;
+                ensure!(nft.issuer == *who, Error::<T>::NoPermission);
+                ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+            }
         }
+        ensure!(
+            !Self::is_metadata_frozen(target),
+            Error::<T>::MetadataFrozen
+        );
+        Ok(())
     }
 
-    fn _remove_nft_from_owned_nft_sources(
-        owner: T::AccountId,
-        class_id: ClassId,
-        nfts_index: u64,
+    /// Overwriting an attribute releases the old deposit and reserves one for the new bytes
+    #[transactional]
+    fn _set_attribute(
+        who: T::AccountId,
+        target: AttributeTarget,
+        key: Vec<u8>,
+        value: AttributeValue,
     ) -> DispatchResult {
-        let _nft = Self::nft_by_class_index(class_id.clone(), nfts_index.clone())
-            .ok_or(Error::<T>::NFTNotExist)?;
-        let mut owned_nfts = Self::owned_nft_source(owner.clone());
-        let mut nfts_exist_flag = false;
-        let mut nft_exist_flag = false;
-        for i in owned_nfts.clone() {
-            if i.class_id == class_id.clone() {
-                nfts_exist_flag = true;
-                for j in i.nfts_indexs.clone() {
-                    if j == nfts_index.clone() {
-                        nft_exist_flag = true;
-                    }
-                }
+        Self::_ensure_metadata_issuer(&who, &target)?;
+        ensure!(
+            key.len() <= T::AttributeKeyLimit::get() as usize,
+            Error::<T>::AttributeKeyTooLong
+        );
+        ensure!(
+            value.encoded_size() <= T::AttributeValueLimit::get() as usize,
+            Error::<T>::AttributeValueTooLong
+        );
+
+        let attribute = helpers::reserve_attribute::<_, T::Currency>(
+            who,
+            value,
+            key.len(),
+            T::AttributeDepositBase::get(),
+            T::AttributeDepositPerByte::get(),
+            Self::attributes(&target, &key),
+        )?;
+        <Attributes<T>>::insert(target, key, attribute);
+
+        Ok(())
+    }
+
+    fn _clear_attribute(
+        who: T::AccountId,
+        target: AttributeTarget,
+        key: Vec<u8>,
+    ) -> DispatchResult {
+        Self::_ensure_metadata_issuer(&who, &target)?;
+        let old = Self::attributes(&target, &key).ok_or(Error::<T>::AttributeNotExist)?;
+
+        T::Currency::unreserve(&old.depositor, old.deposit);
+        <Attributes<T>>::remove(target, key);
+
+        Ok(())
+    }
+
+    /// There is no unfreeze; deposits of frozen attributes stay reserved
+    fn _freeze_metadata(who: T::AccountId, target: AttributeTarget) -> DispatchResult {
+        match &target {
+            AttributeTarget::Class(class_id) => {
+                let class_info = Self::class_infos(class_id).ok_or(Error::<T>::ClassNotExist)?;
+                ensure!(
+                    class_info.issuer == who
+                        || Self::_class_roles(class_id, &class_info).freezer == who,
+                    Error::<T>::NoPermission
+                );
             }
+            AttributeTarget::NFT(_) => Self::_ensure_metadata_issuer(&who, &target)?,
         }
-        ensure!(nfts_exist_flag == true, Error::<T>::NFTNotExist);
-        ensure!(nft_exist_flag == true, Error::<T>::NFTNotExist);
-
-        for i in 0..owned_nfts.len() {
-            if owned_nfts[i].class_id == class_id.clone() {
-                let mut k = 0;
-                for j in owned_nfts[i].nfts_indexs.clone() {
-                    if j == nfts_index.clone() {
-                        owned_nfts[i].nfts_indexs.remove(k);
-                        owned_nfts[i].amount -= 1;
-                        break;
-                    }
-                    k += 1;
-                }
-            }
+
+        <FrozenMetadata>::insert(target, true);
+
+        Ok(())
+    }
+
+    /// Drops every attribute of `target`, frozen or not, and returns their deposits
+    fn _remove_metadata(target: &AttributeTarget) {
+        for (_, attribute) in <Attributes<T>>::iter_prefix(target) {
+            T::Currency::unreserve(&attribute.depositor, attribute.deposit);
         }
-        <OwnedNFTSource<T>>::insert(owner, owned_nfts);
+        <Attributes<T>>::remove_prefix(target);
+        <FrozenMetadata>::remove(target);
+    }
+}
+
+// Lock
+impl<T: Config> Module<T> {
+    fn _lock_nft(nft_id: NFTId) -> DispatchResult {
+        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        ensure!(
+            nft.status == NFTStatus::Normal || nft.status == NFTStatus::Offered,
+            Error::<T>::NFTLocked
+        );
+
+        Self::_clear_listing(&nft_id, &mut nft);
+        nft.status = NFTStatus::Locked;
+        <NFTInfos<T>>::insert(nft_id, &nft);
+
+        Ok(())
+    }
+
+    fn _unlock_nft(nft_id: NFTId) -> DispatchResult {
+        let mut nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.status == NFTStatus::Locked, Error::<T>::NFTNotLocked);
+
+        nft.status = NFTStatus::Normal;
+        <NFTInfos<T>>::insert(nft_id, &nft);
+
+        Ok(())
+    }
+}
+
+// Rental
+impl<T: Config> Module<T> {
+    fn _user_of(nft_id: NFTId) -> Option<T::AccountId> {
+        helpers::active_user(
+            Self::nft_users(nft_id),
+            &<frame_system::Module<T>>::block_number(),
+        )
+    }
+
+    fn _set_user(
+        who: T::AccountId,
+        nft_id: NFTId,
+        user: T::AccountId,
+        expiry: T::BlockNumber,
+    ) -> DispatchResult {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == who, Error::<T>::NotNFTOwner);
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+
+        Self::_update_user(nft_id, user, expiry)
+    }
+
+    fn _update_user(nft_id: NFTId, user: T::AccountId, expiry: T::BlockNumber) -> DispatchResult {
+        ensure!(
+            expiry > <frame_system::Module<T>>::block_number(),
+            Error::<T>::InvalidExpiry
+        );
+        // an active rental can not be cut short by the owner
+        ensure!(
+            Self::_user_of(nft_id.clone()).is_none(),
+            Error::<T>::NFTInUse
+        );
+
+        <UserExpiries<T>>::try_mutate(expiry, |nft_ids| {
+            helpers::schedule_expiry(nft_ids, &nft_id, T::MaxExpiriesPerBlock::get())
+        })
+        .map_err(Error::<T>::from)?;
+        <NFTUsers<T>>::insert(nft_id, NFTUser { user, expiry });
+
+        Ok(())
+    }
+
+    fn _offer_rent_nft(
+        from: T::AccountId,
+        nft_id: NFTId,
+        price_per_block: BalanceOf<T>,
+        max_duration: T::BlockNumber,
+    ) -> DispatchResult {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == from, Error::<T>::NotNFTOwner);
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        ensure!(!max_duration.is_zero(), Error::<T>::InvalidDuration);
+
+        <RentOffers<T>>::insert(
+            nft_id,
+            RentOffer {
+                price_per_block,
+                max_duration,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn _cancel_offer_rent_nft(from: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == from, Error::<T>::NotNFTOwner);
+        ensure!(
+            <RentOffers<T>>::contains_key(nft_id.clone()),
+            Error::<T>::NFTNotForRent
+        );
+
+        <RentOffers<T>>::remove(nft_id);
 
         Ok(())
     }
+
+    #[transactional]
+    fn _rent_nft(
+        who: T::AccountId,
+        nft_id: NFTId,
+        duration: T::BlockNumber,
+    ) -> Result<T::BlockNumber, DispatchError> {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner != who, Error::<T>::NoPermission);
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        let offer = Self::rent_offers(nft_id.clone()).ok_or(Error::<T>::NFTNotForRent)?;
+        let cost = helpers::rent_cost(&offer, duration).map_err(Error::<T>::from)?;
+
+        let expiry = <frame_system::Module<T>>::block_number().saturating_add(duration);
+        Self::_update_user(nft_id, who.clone(), expiry)?;
+
+        T::Currency::transfer(&who, &nft.owner, cost, ExistenceRequirement::KeepAlive)?;
+
+        Ok(expiry)
+    }
 }
 
 impl<T: Config> NFT2006Manager<T::AccountId, BalanceOf<T>> for Module<T> {
@@ -894,7 +2693,14 @@ impl<T: Config> NFT2006Manager<T::AccountId, BalanceOf<T>> for Module<T> {
         total_supply: u64,
         issuer: T::AccountId,
     ) -> DispatchResult {
-        Self::_create_class(name, info, total_supply, issuer)
+        Self::_create_class(
+            name,
+            info,
+            total_supply,
+            issuer,
+            None,
+            TransferPolicy::Transferable,
+        )
     }
 
     fn get_class(class_id: ClassId) -> Option<ClassInfo<T::AccountId>> {
@@ -909,13 +2715,21 @@ impl<T: Config> NFT2006Manager<T::AccountId, BalanceOf<T>> for Module<T> {
         price: BalanceOf<T>,
         miner: T::AccountId,
     ) -> Option<NFTId> {
-        Self::_mint_nft(class_id, info, metadata, price, miner)
+        Self::_mint_nft(class_id, info, metadata, price, miner, None).ok()
     }
 
     fn get_nft(nft_id: NFTId) -> Option<NFTInfo<T::AccountId, BalanceOf<T>>> {
         Self::nft_infos(nft_id)
     }
 
+    fn royalty_of(nft_id: NFTId) -> Option<RoyaltyInfo<T::AccountId>> {
+        Self::_royalty_of(nft_id)
+    }
+
+    fn user_of(nft_id: NFTId) -> Option<T::AccountId> {
+        Self::_user_of(nft_id)
+    }
+
     fn get_nft_by_index(class_id: ClassId, index: u64) -> Option<NFTId> {
         Self::nft_by_class_index(class_id, index)
     }
@@ -962,14 +2776,14 @@ impl<T: Config> NFT2006Manager<T::AccountId, BalanceOf<T>> for Module<T> {
         owner: T::AccountId,
         info: Vec<u8>,
         price: BalanceOf<T>,
-        source: Vec<NFTSource<ClassId>>,
+        members: Vec<(NFTType, NFTId)>,
     ) -> DispatchResult {
-        Self::_coupled_collection(name, symbol, owner, info, price, source)
+        Self::_coupled_collection(name, symbol, owner, info, price, members)
     }
 
     fn get_collection(
         collection_id: CollectionId,
-    ) -> Option<Collection<T::AccountId, BalanceOf<T>, CollectionStatus, ClassId>> {
+    ) -> Option<Collection<T::AccountId, BalanceOf<T>, CollectionStatus>> {
         Self::collections(collection_id)
     }
 
@@ -990,8 +2804,12 @@ impl<T: Config> NFT2006Manager<T::AccountId, BalanceOf<T>> for Module<T> {
         Self::_transfer_collection(who, from, to, collection_id)
     }
 
-    fn destroy_collection(who: T::AccountId, collection_id: CollectionId) -> DispatchResult {
-        Self::_destroy_collection(who, collection_id)
+    fn destroy_collection(
+        who: T::AccountId,
+        collection_id: CollectionId,
+        burn_nfts: bool,
+    ) -> DispatchResult {
+        Self::_destroy_collection(who, collection_id, burn_nfts)
     }
 
     fn approve_collection(
@@ -1006,3 +2824,86 @@ impl<T: Config> NFT2006Manager<T::AccountId, BalanceOf<T>> for Module<T> {
         Self::_set_approval_for_all(who, to, approved)
     }
 }
+
+impl<T: Config> NonFungibleManager<T::AccountId, BalanceOf<T>> for Module<T> {
+    fn get_class(class_id: ClassId) -> Option<ClassInfo<T::AccountId>> {
+        Self::class_infos(class_id)
+    }
+
+    fn get_nft(nft_id: NFTId) -> Option<NFTInfo<T::AccountId, BalanceOf<T>>> {
+        Self::nft_infos(nft_id)
+    }
+
+    fn owner_of(nft_id: NFTId) -> Option<T::AccountId> {
+        match Self::nft_parents(nft_id.clone()) {
+            Some((parent_type, parent_id)) => T::NFT::owner_of(parent_type, parent_id),
+            None => Self::nft_infos(nft_id)
+                .filter(|nft| nft.status != NFTStatus::Burned)
+                .map(|nft| nft.owner),
+        }
+    }
+
+    fn royalty_of(nft_id: NFTId) -> Option<RoyaltyInfo<T::AccountId>> {
+        Self::_royalty_of(nft_id)
+    }
+
+    fn user_of(nft_id: NFTId) -> Option<T::AccountId> {
+        Self::_user_of(nft_id)
+    }
+
+    fn mint_nft(
+        class_id: ClassId,
+        info: Vec<u8>,
+        metadata: Vec<u8>,
+        price: BalanceOf<T>,
+        miner: T::AccountId,
+    ) -> Option<NFTId> {
+        Self::_mint_nft(class_id, info, metadata, price, miner, None).ok()
+    }
+
+    fn transfer_single_nft(from: T::AccountId, to: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        Self::_transfer_single_nft(from, to, nft_id)
+    }
+
+    fn lock_nft(nft_id: NFTId) -> DispatchResult {
+        Self::_lock_nft(nft_id)
+    }
+
+    fn unlock_nft(nft_id: NFTId) -> DispatchResult {
+        Self::_unlock_nft(nft_id)
+    }
+
+    fn burn_nft(who: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        Self::_burn_nft(who, nft_id)
+    }
+
+    fn approve_single_nft(who: T::AccountId, to: T::AccountId, nft_id: NFTId) -> DispatchResult {
+        Self::_approve_single_nft(who, to, nft_id)
+    }
+
+    fn parent_of(nft_id: NFTId) -> Option<(NFTType, NFTId)> {
+        Self::nft_parents(nft_id)
+    }
+
+    fn children_of(nft_id: NFTId) -> Vec<(NFTType, NFTId)> {
+        <NFTChildren>::iter_prefix(nft_id)
+            .map(|(child, _)| child)
+            .collect()
+    }
+
+    fn add_child(nft_id: NFTId, child: (NFTType, NFTId)) -> DispatchResult {
+        let nft = Self::nft_infos(nft_id.clone()).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        <NFTChildren>::insert(nft_id, child, ());
+        Ok(())
+    }
+
+    fn remove_child(nft_id: NFTId, child: (NFTType, NFTId)) -> DispatchResult {
+        ensure!(
+            <NFTChildren>::contains_key(&nft_id, &child),
+            Error::<T>::NFTNotAttached
+        );
+        <NFTChildren>::remove(nft_id, child);
+        Ok(())
+    }
+}
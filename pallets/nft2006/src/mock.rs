@@ -1,10 +1,13 @@
-use crate as pallet_nft1155;
+use crate as pallet_nft2006;
 use sp_core::H256;
 use frame_support::parameter_types;
+use codec::Encode;
+use sp_io::hashing::blake2_256;
 use sp_runtime::{
-	traits::{BlakeTwo256, IdentityLookup}, testing::Header,
+	traits::{BlakeTwo256, IdentityLookup}, testing::Header, ModuleId,
 };
 use frame_system as system;
+use utilities::{BufferIndex, CommonManager, DAOManager, Did, ValueStruct};
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -18,7 +21,8 @@ frame_support::construct_runtime!(
 	{
 		System: frame_system::{Module, Call, Config, Storage, Event<T>},
 		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
-		NFT1155Module: pallet_nft1155::{Module, Call, Storage, Event<T>},
+		TokenModule: pallet_token::{Module, Call, Storage, Event<T>},
+		NFT2006Module: pallet_nft2006::{Module, Call, Storage, Event<T>},
 	}
 );
 
@@ -26,6 +30,24 @@ parameter_types! {
 	pub const BlockHashCount: u64 = 250;
 	pub const SS58Prefix: u8 = 42;
 	pub const ExistentialDeposit: u64 = 1;
+	pub const TokenDepositBase: u64 = 0;
+	pub const SymbolLimit: u32 = 8;
+	pub const UriLimit: u32 = 64;
+	pub const NFT2006ModuleId: ModuleId = ModuleId(*b"dnft/frg");
+	pub const BuyoutPeriod: u64 = 10;
+	pub const MaxNestingDepth: u32 = 2;
+	pub const AttributeDepositBase: u64 = 0;
+	pub const AttributeDepositPerByte: u64 = 0;
+	pub const AttributeKeyLimit: u32 = 32;
+	pub const AttributeValueLimit: u32 = 64;
+	pub const ClassDepositBase: u64 = 0;
+	pub const NFTDepositBase: u64 = 0;
+	pub const CollectionDepositBase: u64 = 10;
+	pub const DepositPerByte: u64 = 0;
+	pub const NameLimit: u32 = 16;
+	pub const InfoLimit: u32 = 64;
+	pub const MaxClassMinters: u32 = 4;
+	pub const MaxExpiriesPerBlock: u32 = 2;
 }
 
 impl system::Config for Test {
@@ -62,21 +84,86 @@ impl pallet_balances::Config for Test {
 	type AccountStore = System;
 	type WeightInfo = ();
 }
-impl pallet_nft1155::Config for Test {
+
+impl pallet_token::Config for Test {
+	type Event = Event;
+	type Common = MockCommon;
+	type Balance = u64;
+	type Currency = Balances;
+	type TokenDepositBase = TokenDepositBase;
+	type DepositPerByte = DepositPerByte;
+	type SymbolLimit = SymbolLimit;
+	type NameLimit = NameLimit;
+	type UriLimit = UriLimit;
+	type DAO = MockDAO;
+}
+
+impl pallet_nft2006::Config for Test {
 	type Event = Event;
 	type Currency = Balances;
+	type Balance = u64;
+	type Token = TokenModule;
+	type NFT = utilities::NonFungibleAdapter<NFT2006Module, NFT2006Module, NFT2006Module>;
+	type ModuleId = NFT2006ModuleId;
+	type BuyoutPeriod = BuyoutPeriod;
+	type MaxNestingDepth = MaxNestingDepth;
+	type AttributeDepositBase = AttributeDepositBase;
+	type AttributeDepositPerByte = AttributeDepositPerByte;
+	type AttributeKeyLimit = AttributeKeyLimit;
+	type AttributeValueLimit = AttributeValueLimit;
+	type ClassDepositBase = ClassDepositBase;
+	type NFTDepositBase = NFTDepositBase;
+	type CollectionDepositBase = CollectionDepositBase;
+	type DepositPerByte = DepositPerByte;
+	type NameLimit = NameLimit;
+	type InfoLimit = InfoLimit;
+	type MaxClassMinters = MaxClassMinters;
+	type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+	type Randomness = randomness::Module<Test>;
+}
+
+/// Only `generate_did` is used by the token pallet
+pub struct MockCommon;
+impl CommonManager<u64> for MockCommon {
+	fn generate_did(from: u64, nonce: u64) -> Did {
+		Did { did: blake2_256(&(from, nonce).encode()) }
+	}
+	fn generate_hash(from: u64, nonce: u64) -> H256 {
+		H256::from(blake2_256(&(from, nonce).encode()))
+	}
+	fn add_to_queue(_id: u32, _integer: u32, _boolean: bool) {}
+	fn add_multiple(_id: u32, _integers: Vec<u32>, _boolean: bool) {}
+	fn pop_from_queue(_id: u32) {}
+	fn get_buffer_range(_id: u32) -> (BufferIndex, BufferIndex) {
+		(0, 0)
+	}
+	fn get_buffer_value(_id: u32, _index: BufferIndex) -> ValueStruct {
+		ValueStruct::default()
+	}
+}
+
+pub struct MockDAO;
+impl DAOManager<u64, u64> for MockDAO {
+	fn get_dao_account() -> u64 {
+		100
+	}
+	fn get_dao_tax() -> u64 {
+		0
+	}
 }
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
 	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
 
-    pallet_balances::GenesisConfig::<Test>{
-		balances: vec![(200, 500)],
-    }.assimilate_storage(&mut t).unwrap();
-	
+	pallet_balances::GenesisConfig::<Test>{
+		balances: vec![(1, 1000), (2, 1000), (3, 1000), (4, 1000)],
+	}.assimilate_storage(&mut t).unwrap();
+
+	pallet_nft2006::GenesisConfig::default().assimilate_storage::<Test>(&mut t).unwrap();
+
 	let mut t: sp_io::TestExternalities = t.into();
 
-    t.execute_with(|| System::set_block_number(1) );
-    t
+	t.execute_with(|| System::set_block_number(1) );
+	t
 }
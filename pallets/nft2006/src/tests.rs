@@ -1,41 +1,274 @@
-use crate::mock::*;
-use frame_support::assert_ok;
-use sp_io::hashing::blake2_256;
-use utilities::ClassId;
+use crate::{mock::*, Error};
+use frame_support::{assert_noop, assert_ok, traits::OnInitialize};
+use utilities::{
+    CollectionStatus, Did, NFTId, NFTStatus, NFTType, TokenManager, TransferPolicy, VaultStatus,
+};
+
+/// An NFT of account 1 split into 100 fragments with a reserve price of 100
+fn fragment_nft() -> (NFTId, Did) {
+    assert_ok!(NFT2006Module::create_class(
+        Origin::signed(1),
+        vec![1],
+        vec![2],
+        1000,
+        None,
+        TransferPolicy::Transferable
+    ));
+    let class_id = NFT2006Module::class_index(0);
+    let nft_id = NFT2006Module::_mint_nft(class_id, vec![1], vec![2], 0, 1, None).unwrap();
+    assert_ok!(NFT2006Module::nft_fragmentation(
+        Origin::signed(1),
+        nft_id.clone(),
+        100,
+        b"FRG".to_vec(),
+        100
+    ));
+    let token_id = NFT2006Module::nft_shift_token(nft_id.clone()).unwrap();
+    (nft_id, token_id)
+}
 
 #[test]
-fn create_class_should_work() {
+fn fragment_nft_should_hold_nft_in_vault() {
     new_test_ext().execute_with(|| {
-        assert_ok!(NFT1155Module::create_class(
+        let (nft_id, token_id) = fragment_nft();
+
+        let nft = NFT2006Module::nft_infos(nft_id.clone()).unwrap();
+        assert_eq!(nft.owner, NFT2006Module::account_id());
+        assert_eq!(nft.status, NFTStatus::Fractionalized);
+        assert_eq!(TokenModule::balance_of((1, token_id)), 100);
+        let vault = NFT2006Module::vaults(nft_id.clone()).unwrap();
+        assert_eq!(vault.curator, 1);
+        assert_eq!(vault.status, VaultStatus::Active);
+
+        assert_noop!(
+            NFT2006Module::nft_fragmentation(Origin::signed(1), nft_id, 10, b"TWO".to_vec(), 0),
+            Error::<Test>::NotNFTOwner
+        );
+    })
+}
+
+#[test]
+fn curator_should_not_administer_fragments() {
+    new_test_ext().execute_with(|| {
+        let (_, token_id) = fragment_nft();
+        assert_eq!(
+            TokenModule::token(&token_id).unwrap().owner,
+            NFT2006Module::account_id()
+        );
+        assert_eq!(TokenModule::supply_cap(&token_id), Some(100));
+
+        assert_noop!(
+            TokenModule::mint(Origin::signed(1), token_id.clone(), 1, 1),
+            pallet_token::Error::<Test>::NoPermission
+        );
+        assert_ok!(TokenModule::transfer(
             Origin::signed(1),
-            vec![1],
-            vec![2],
-            1000
+            2,
+            token_id.clone(),
+            30,
+            None
         ));
+        assert_noop!(
+            TokenModule::burn_from(Origin::signed(1), token_id.clone(), 2, 30),
+            pallet_token::Error::<Test>::NoPermission
+        );
+        assert_noop!(
+            TokenModule::freeze_account(Origin::signed(1), token_id.clone(), 2),
+            pallet_token::Error::<Test>::NoPermission
+        );
+        assert_noop!(
+            TokenModule::set_supply_cap(Origin::signed(1), token_id.clone(), None),
+            pallet_token::Error::<Test>::NoPermission
+        );
+        assert_eq!(TokenModule::balance_of((2, token_id)), 30);
     })
 }
 
 #[test]
-fn mint_nft_should_work() {
+fn redeem_nft_should_need_every_fragment() {
     new_test_ext().execute_with(|| {
-        let did = blake2_256(b"test");
-        let new_class_id = ClassId { did };
-        assert_ok!(NFT1155Module::mint_nft(
+        let (nft_id, token_id) = fragment_nft();
+        assert_ok!(TokenModule::transfer(
             Origin::signed(1),
-            new_class_id,
-            vec![1],
-            vec![2],
-            100
+            2,
+            token_id.clone(),
+            30,
+            None
+        ));
+        assert_noop!(
+            NFT2006Module::redeem_nft(Origin::signed(1), nft_id.clone()),
+            Error::<Test>::NotEnoughFragments
+        );
+
+        assert_ok!(TokenModule::transfer(
+            Origin::signed(2),
+            1,
+            token_id.clone(),
+            30,
+            None
         ));
+        assert_ok!(NFT2006Module::redeem_nft(Origin::signed(1), nft_id.clone()));
+        let nft = NFT2006Module::nft_infos(nft_id.clone()).unwrap();
+        assert_eq!(nft.owner, 1);
+        assert_eq!(nft.status, NFTStatus::Normal);
+        assert_eq!(TokenModule::total_supply(token_id), 0);
+        assert_eq!(NFT2006Module::vaults(nft_id), None);
     })
 }
 
 #[test]
-fn transfer_nft_should_work() {
+fn buyout_should_refund_outbid_bidder() {
     new_test_ext().execute_with(|| {
-        let did = blake2_256(b"test");
-        let new_class_id = ClassId { did };
-        let nft_id = NFT1155Module::_mint_nft(new_class_id, vec![1], vec![2], 100, 1);
-        assert_eq!(nft_id, None);
+        let (nft_id, token_id) = fragment_nft();
+        assert_noop!(
+            NFT2006Module::start_buyout(Origin::signed(2), nft_id.clone(), 200),
+            Error::<Test>::NotEnoughFragments
+        );
+        assert_ok!(TokenModule::transfer(
+            Origin::signed(1),
+            2,
+            token_id,
+            30,
+            None
+        ));
+        assert_noop!(
+            NFT2006Module::start_buyout(Origin::signed(2), nft_id.clone(), 50),
+            Error::<Test>::BidTooLow
+        );
+        assert_ok!(NFT2006Module::start_buyout(
+            Origin::signed(2),
+            nft_id.clone(),
+            200
+        ));
+        assert_eq!(Balances::reserved_balance(2), 200);
+        assert_noop!(
+            NFT2006Module::redeem_nft(Origin::signed(1), nft_id.clone()),
+            Error::<Test>::VaultNotActive
+        );
+
+        assert_noop!(
+            NFT2006Module::bid_buyout(Origin::signed(3), nft_id.clone(), 200),
+            Error::<Test>::BidTooLow
+        );
+        assert_ok!(NFT2006Module::bid_buyout(Origin::signed(3), nft_id, 300));
+        assert_eq!(Balances::reserved_balance(2), 0);
+        assert_eq!(Balances::reserved_balance(3), 300);
+    })
+}
+
+#[test]
+fn settle_buyout_should_hand_nft_to_winner() {
+    new_test_ext().execute_with(|| {
+        let (nft_id, token_id) = fragment_nft();
+        assert_ok!(NFT2006Module::start_buyout(
+            Origin::signed(1),
+            nft_id.clone(),
+            300
+        ));
+        let auction_end = NFT2006Module::vaults(nft_id.clone()).unwrap().auction_end;
+        assert_ok!(NFT2006Module::bid_buyout(
+            Origin::signed(3),
+            nft_id.clone(),
+            400
+        ));
+
+        NFT2006Module::on_initialize(auction_end - 1);
+        assert!(NFT2006Module::vaults(nft_id.clone()).is_some());
+
+        NFT2006Module::on_initialize(auction_end);
+        assert_eq!(NFT2006Module::nft_infos(nft_id.clone()).unwrap().owner, 3);
+        assert_eq!(NFT2006Module::vaults(nft_id), None);
+        assert_eq!(Balances::total_balance(&3), 600);
+        assert_eq!(Balances::free_balance(NFT2006Module::account_id()), 400);
+        assert_eq!(NFT2006Module::buyout_proceeds(token_id), Some((400, 100)));
+    })
+}
+
+#[test]
+fn claim_proceeds_should_pay_every_remaining_fragment() {
+    new_test_ext().execute_with(|| {
+        let (nft_id, token_id) = fragment_nft();
+        assert_ok!(TokenModule::transfer(
+            Origin::signed(1),
+            2,
+            token_id.clone(),
+            30,
+            None
+        ));
+        assert_ok!(NFT2006Module::start_buyout(
+            Origin::signed(2),
+            nft_id.clone(),
+            300
+        ));
+        let auction_end = NFT2006Module::vaults(nft_id).unwrap().auction_end;
+        NFT2006Module::on_initialize(auction_end);
+
+        assert_noop!(
+            NFT2006Module::claim_proceeds(Origin::signed(3), token_id.clone()),
+            Error::<Test>::NotEnoughFragments
+        );
+        assert_ok!(NFT2006Module::claim_proceeds(
+            Origin::signed(2),
+            token_id.clone()
+        ));
+        assert_eq!(Balances::free_balance(2), 790);
+
+        // fragments burned outside the vault leave their share to the other holders
+        assert_ok!(TokenModule::burn(Origin::signed(1), token_id.clone(), 20));
+        assert_ok!(NFT2006Module::claim_proceeds(
+            Origin::signed(1),
+            token_id.clone()
+        ));
+        assert_eq!(Balances::free_balance(1), 1210);
+        assert_eq!(Balances::free_balance(NFT2006Module::account_id()), 0);
+        assert_eq!(NFT2006Module::buyout_proceeds(token_id.clone()), None);
+        assert_noop!(
+            NFT2006Module::claim_proceeds(Origin::signed(1), token_id),
+            Error::<Test>::NoBuyoutProceeds
+        );
+    })
+}
+
+#[test]
+fn decoupled_collection_should_return_deposit() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT2006Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT2006Module::class_index(0);
+        let nft_id = NFT2006Module::_mint_nft(class_id, vec![1], vec![2], 0, 1, None).unwrap();
+        assert_ok!(NFT2006Module::coupled_collection(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            vec![3],
+            0,
+            vec![(NFTType::NFT2006, nft_id.clone())]
+        ));
+        assert_eq!(Balances::reserved_balance(1), 10);
+
+        let collection_id = NFT2006Module::collections_index(0);
+        assert_ok!(NFT2006Module::decoupled_collection(
+            Origin::signed(1),
+            collection_id.clone()
+        ));
+        assert_eq!(Balances::reserved_balance(1), 0);
+        assert_eq!(
+            NFT2006Module::collection_deposits(collection_id.clone()),
+            None
+        );
+        assert_eq!(
+            NFT2006Module::collections(collection_id).unwrap().status,
+            CollectionStatus::Decoupled
+        );
+        assert_eq!(
+            NFT2006Module::nft_infos(nft_id).unwrap().status,
+            NFTStatus::Normal
+        );
     })
 }
@@ -1,7 +1,18 @@
 use crate::mock::*;
-use frame_support::assert_ok;
+use crate::{Error, PalletStorageVersion};
+use codec::Encode;
+use frame_support::{
+    assert_err, assert_noop, assert_ok,
+    storage::migration::{get_storage_value, put_storage_value},
+    traits::{OnInitialize, OnRuntimeUpgrade},
+    Blake2_128Concat, StorageHasher, StorageValue, Twox64Concat,
+};
 use sp_io::hashing::blake2_256;
-use utilities::ClassId;
+use sp_runtime::{testing::TestSignature, Perbill};
+use utilities::{
+    AttributeTarget, AttributeValue, ClassId, MintDrop, MintVoucher, NFT721Manager, NFTId,
+    NFTStatus, NFTType, Releases, RoyaltyInfo, TransferPolicy,
+};
 
 #[test]
 fn create_class_should_work() {
@@ -10,7 +21,9 @@ fn create_class_should_work() {
             Origin::signed(1),
             vec![1],
             vec![2],
-            1000
+            1000,
+            None,
+            TransferPolicy::Transferable
         ));
     })
 }
@@ -18,14 +31,33 @@ fn create_class_should_work() {
 #[test]
 fn mint_nft_should_work() {
     new_test_ext().execute_with(|| {
-        let did = blake2_256(b"test");
-        let new_class_id = ClassId { did };
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        assert_noop!(
+            NFT721Module::mint_nft(
+                Origin::signed(2),
+                class_id.clone(),
+                vec![1],
+                vec![2],
+                100,
+                None
+            ),
+            Error::<Test>::NoPermission
+        );
         assert_ok!(NFT721Module::mint_nft(
             Origin::signed(1),
-            new_class_id,
+            class_id,
             vec![1],
             vec![2],
-            100
+            100,
+            None
         ));
     })
 }
@@ -35,7 +67,974 @@ fn transfer_nft_should_work() {
     new_test_ext().execute_with(|| {
         let did = blake2_256(b"test");
         let new_class_id = ClassId { did };
-        let nft_id = NFT721Module::_mint_nft(new_class_id, vec![1], vec![2], 100, 1);
-        assert_eq!(nft_id, None);
+        assert_noop!(
+            NFT721Module::_mint_nft(new_class_id, vec![1], vec![2], 100, 1, None),
+            Error::<Test>::ClassNotExist
+        );
+    })
+}
+
+#[test]
+fn buy_nft_should_pay_royalty() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            Some(RoyaltyInfo {
+                beneficiary: 1,
+                rate: Perbill::from_percent(10),
+            }),
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        assert_ok!(NFT721Module::set_class_minters(
+            Origin::signed(1),
+            class_id.clone(),
+            vec![2]
+        ));
+        let nft_id = NFT721Module::_mint_nft(class_id, vec![1], vec![2], 100, 2, None).unwrap();
+        assert_ok!(NFT721Module::offer_nft(
+            Origin::signed(2),
+            nft_id.clone(),
+            100,
+            None
+        ));
+        assert_ok!(NFT721Module::buy_nft(Origin::signed(200), nft_id.clone()));
+        assert_eq!(Balances::free_balance(1), 10);
+        assert_eq!(Balances::free_balance(2), 90);
+        assert_eq!(NFT721Module::nft_infos(nft_id).unwrap().owner, 200);
+    })
+}
+
+#[test]
+fn transfer_from_should_work_for_approved() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        let nft_id = NFT721Module::_mint_nft(class_id, vec![1], vec![2], 100, 1, None).unwrap();
+        assert_noop!(
+            NFT721Module::transfer_from(Origin::signed(2), 1, 3, nft_id.clone()),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(NFT721Module::approve(Origin::signed(1), 2, nft_id.clone()));
+        assert_ok!(NFT721Module::transfer_from(
+            Origin::signed(2),
+            1,
+            3,
+            nft_id.clone()
+        ));
+        assert_eq!(NFT721Module::nft_infos(nft_id.clone()).unwrap().owner, 3);
+        assert_eq!(NFT721Module::nft_approvers(nft_id), None);
+    })
+}
+
+#[test]
+fn offer_nft_should_expire() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        let nft_id = NFT721Module::_mint_nft(class_id, vec![1], vec![2], 100, 1, None).unwrap();
+        assert_ok!(NFT721Module::offer_nft(
+            Origin::signed(1),
+            nft_id.clone(),
+            100,
+            Some(5)
+        ));
+        assert_eq!(
+            NFT721Module::nft_infos(nft_id.clone()).unwrap().status,
+            NFTStatus::Offered
+        );
+
+        NFT721Module::on_initialize(5);
+
+        assert_eq!(
+            NFT721Module::nft_infos(nft_id.clone()).unwrap().status,
+            NFTStatus::Normal
+        );
+        assert_noop!(
+            NFT721Module::buy_nft(Origin::signed(200), nft_id),
+            Error::<Test>::NFTNotForBuy
+        );
+    })
+}
+
+#[test]
+fn offer_nft_should_bound_expiries_per_block() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        let mut nft_ids = vec![];
+        for _ in 0..3 {
+            nft_ids.push(
+                NFT721Module::_mint_nft(class_id.clone(), vec![1], vec![2], 100, 1, None).unwrap(),
+            );
+        }
+        for nft_id in nft_ids[..2].iter() {
+            assert_ok!(NFT721Module::offer_nft(
+                Origin::signed(1),
+                nft_id.clone(),
+                100,
+                Some(5)
+            ));
+        }
+        // re-offering replaces the pending entry
+        assert_ok!(NFT721Module::offer_nft(
+            Origin::signed(1),
+            nft_ids[0].clone(),
+            200,
+            Some(5)
+        ));
+        assert_eq!(NFT721Module::listing_expiries(5).len(), 2);
+        assert_noop!(
+            NFT721Module::offer_nft(Origin::signed(1), nft_ids[2].clone(), 100, Some(5)),
+            Error::<Test>::TooManyExpiries
+        );
+
+        assert_ok!(NFT721Module::cancel_offer_nft(
+            Origin::signed(1),
+            nft_ids[0].clone()
+        ));
+        assert_eq!(NFT721Module::listing_expiries(5), vec![nft_ids[1].clone()]);
+        assert_ok!(NFT721Module::offer_nft(
+            Origin::signed(1),
+            nft_ids[2].clone(),
+            100,
+            Some(5)
+        ));
+    })
+}
+
+#[test]
+fn redeem_voucher_should_work() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let voucher = MintVoucher {
+            class_id: NFT721Module::class_index(0),
+            metadata: vec![3],
+            price: 50,
+            nonce: 7,
+            expiry: 10,
+        };
+        let signature = TestSignature(1, (b"NFT721", &voucher).encode());
+        assert_noop!(
+            NFT721Module::redeem_voucher(
+                Origin::signed(200),
+                voucher.clone(),
+                TestSignature(2, (b"NFT721", &voucher).encode())
+            ),
+            Error::<Test>::InvalidSignature
+        );
+        assert_ok!(NFT721Module::redeem_voucher(
+            Origin::signed(200),
+            voucher.clone(),
+            signature.clone()
+        ));
+        let nft_id = NFT721Module::nfts_index(0);
+        let nft = NFT721Module::nft_infos(nft_id).unwrap();
+        assert_eq!(nft.owner, 200);
+        assert_eq!(nft.issuer, 1);
+        assert_eq!(Balances::free_balance(1), 50);
+        assert_noop!(
+            NFT721Module::redeem_voucher(Origin::signed(200), voucher, signature),
+            Error::<Test>::VoucherAlreadyUsed
+        );
+    })
+}
+
+#[test]
+fn soulbound_nft_should_not_transfer() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Soulbound
+        ));
+        let class_id = NFT721Module::class_index(0);
+        assert_ok!(NFT721Module::set_class_minters(
+            Origin::signed(1),
+            class_id.clone(),
+            vec![2]
+        ));
+        let nft_id = NFT721Module::_mint_nft(class_id, vec![1], vec![2], 100, 2, None).unwrap();
+        assert_noop!(
+            NFT721Module::transfer_nft(Origin::signed(2), 2, 3, nft_id.clone()),
+            Error::<Test>::NFTNotTransferable
+        );
+        assert_noop!(
+            NFT721Module::offer_nft(Origin::signed(2), nft_id.clone(), 100, None),
+            Error::<Test>::NFTNotTransferable
+        );
+        assert_noop!(
+            NFT721Module::revoke_nft(Origin::signed(2), nft_id.clone()),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(NFT721Module::revoke_nft(Origin::signed(1), nft_id.clone()));
+        assert_eq!(
+            NFT721Module::nft_infos(nft_id).unwrap().status,
+            NFTStatus::Burned
+        );
+    })
+}
+
+#[test]
+fn locked_nft_should_not_transfer() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        assert_ok!(NFT721Module::set_class_minters(
+            Origin::signed(1),
+            class_id.clone(),
+            vec![2]
+        ));
+        let nft_id = NFT721Module::_mint_nft(class_id, vec![1], vec![2], 100, 2, None).unwrap();
+        assert_ok!(NFT721Module::_lock_nft(nft_id.clone()));
+        assert_noop!(
+            NFT721Module::transfer_nft(Origin::signed(2), 2, 3, nft_id.clone()),
+            Error::<Test>::NFTLocked
+        );
+        assert_noop!(
+            NFT721Module::burn_nft(Origin::signed(2), nft_id.clone()),
+            Error::<Test>::NFTLocked
+        );
+        assert_ok!(NFT721Module::_unlock_nft(nft_id.clone()));
+        assert_ok!(NFT721Module::transfer_nft(
+            Origin::signed(2),
+            2,
+            3,
+            nft_id.clone()
+        ));
+        assert_eq!(NFT721Module::nft_infos(nft_id).unwrap().owner, 3);
+    })
+}
+
+#[test]
+fn attached_nft_should_move_with_parent() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        assert_ok!(NFT721Module::set_class_minters(
+            Origin::signed(1),
+            class_id.clone(),
+            vec![2]
+        ));
+        let nft_ids: Vec<NFTId> = (0..4)
+            .map(|_| {
+                NFT721Module::_mint_nft(class_id.clone(), vec![1], vec![2], 100, 2, None).unwrap()
+            })
+            .collect();
+        let (root, child, grandchild, extra) = (
+            nft_ids[0].clone(),
+            nft_ids[1].clone(),
+            nft_ids[2].clone(),
+            nft_ids[3].clone(),
+        );
+
+        assert_noop!(
+            NFT721Module::attach_nft(
+                Origin::signed(3),
+                child.clone(),
+                NFTType::NFT721,
+                root.clone()
+            ),
+            Error::<Test>::NotNFTOwner
+        );
+        assert_ok!(NFT721Module::attach_nft(
+            Origin::signed(2),
+            child.clone(),
+            NFTType::NFT721,
+            root.clone()
+        ));
+        assert_ok!(NFT721Module::attach_nft(
+            Origin::signed(2),
+            grandchild.clone(),
+            NFTType::NFT721,
+            child.clone()
+        ));
+        assert_noop!(
+            NFT721Module::attach_nft(
+                Origin::signed(2),
+                root.clone(),
+                NFTType::NFT721,
+                grandchild.clone()
+            ),
+            Error::<Test>::NestingCycle
+        );
+        assert_noop!(
+            NFT721Module::attach_nft(
+                Origin::signed(2),
+                extra.clone(),
+                NFTType::NFT721,
+                grandchild.clone()
+            ),
+            Error::<Test>::NestingTooDeep
+        );
+        assert_noop!(
+            NFT721Module::transfer_nft(Origin::signed(2), 2, 3, child.clone()),
+            Error::<Test>::NFTLocked
+        );
+        assert_noop!(
+            NFT721Module::burn_nft(Origin::signed(2), root.clone()),
+            Error::<Test>::NFTHasChildren
+        );
+
+        assert_ok!(NFT721Module::transfer_nft(
+            Origin::signed(2),
+            2,
+            3,
+            root.clone()
+        ));
+        assert_eq!(NFT721Module::nft_infos(child.clone()).unwrap().owner, 3);
+        assert_eq!(
+            NFT721Module::nft_infos(grandchild.clone()).unwrap().owner,
+            3
+        );
+        assert_eq!(
+            NFT721Module::nft_infos(child.clone()).unwrap().status,
+            NFTStatus::Locked
+        );
+
+        assert_noop!(
+            NFT721Module::detach_nft(Origin::signed(2), grandchild.clone()),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(NFT721Module::detach_nft(
+            Origin::signed(3),
+            grandchild.clone()
+        ));
+        assert_eq!(NFT721Module::nft_parents(grandchild.clone()), None);
+        assert_eq!(
+            NFT721Module::nft_infos(grandchild).unwrap().status,
+            NFTStatus::Normal
+        );
+    })
+}
+
+#[test]
+fn rent_nft_should_work() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        assert_ok!(NFT721Module::set_class_minters(
+            Origin::signed(1),
+            class_id.clone(),
+            vec![2]
+        ));
+        let nft_id = NFT721Module::_mint_nft(class_id, vec![1], vec![2], 100, 2, None).unwrap();
+        assert_ok!(NFT721Module::offer_rent_nft(
+            Origin::signed(2),
+            nft_id.clone(),
+            5,
+            10
+        ));
+        assert_noop!(
+            NFT721Module::rent_nft(Origin::signed(200), nft_id.clone(), 11),
+            Error::<Test>::InvalidDuration
+        );
+        assert_ok!(NFT721Module::rent_nft(
+            Origin::signed(200),
+            nft_id.clone(),
+            4
+        ));
+        assert_eq!(Balances::free_balance(2), 20);
+        assert_eq!(NFT721Module::user_of(nft_id.clone()), Some(200));
+        assert_noop!(
+            NFT721Module::set_user(Origin::signed(2), nft_id.clone(), 3, 10),
+            Error::<Test>::NFTInUse
+        );
+
+        NFT721Module::on_initialize(5);
+
+        assert_eq!(NFT721Module::user_of(nft_id.clone()), None);
+        assert_eq!(NFT721Module::nft_users(nft_id), None);
+    })
+}
+
+#[test]
+fn genesis_should_start_at_latest_version() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(NFT721Module::storage_version(), Releases::V2);
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Soulbound
+        ));
+        let class_id = NFT721Module::class_index(0);
+        for _ in 0..2 {
+            NFT721Module::_mint_nft(class_id.clone(), vec![1], vec![2], 100, 1, None).unwrap();
+        }
+
+        NFT721Module::on_runtime_upgrade();
+
+        assert_eq!(NFT721Module::storage_version(), Releases::V2);
+        assert_eq!(
+            NFT721Module::class_infos(class_id).unwrap().transfer_policy,
+            TransferPolicy::Soulbound
+        );
+        assert_eq!(NFT721Module::owned_nft_count(1), 2);
+    })
+}
+
+#[test]
+fn migrate_to_v2_should_rebuild_owned_index() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        let mut nft_ids = vec![];
+        for _ in 0..3 {
+            nft_ids.push(
+                NFT721Module::_mint_nft(class_id.clone(), vec![1], vec![2], 100, 1, None).unwrap(),
+            );
+        }
+        // V1 layout: a single vector per account
+        let old_key = Blake2_128Concat::hash(&1u64.encode());
+        put_storage_value(b"NFT721", b"OwnedNFTs", &old_key, nft_ids[..1].to_vec());
+        PalletStorageVersion::put(Releases::V1);
+
+        NFT721Module::on_runtime_upgrade();
+
+        assert_eq!(NFT721Module::storage_version(), Releases::V2);
+        assert_eq!(NFT721Module::owned_nft_count(1), 3);
+        assert_eq!(NFT721Module::class_supply(class_id), 3);
+        assert_eq!(NFT721Module::owned_nfts_paged(1, 0, 2).len(), 2);
+        assert_eq!(NFT721Module::owned_nfts_paged(1, 1, 2).len(), 1);
+        assert_eq!(
+            get_storage_value::<Vec<NFTId>>(b"NFT721", b"OwnedNFTs", &old_key),
+            None
+        );
+    })
+}
+
+#[test]
+fn migrate_to_v2_should_default_transfer_policy() {
+    new_test_ext().execute_with(|| {
+        let class_id = ClassId { did: [7u8; 32] };
+        // V1 layout: name, info, total_supply, issuer
+        let key = Twox64Concat::hash(&class_id.encode());
+        put_storage_value(
+            b"NFT721",
+            b"ClassInfos",
+            &key,
+            (vec![1u8], vec![2u8], 1000u64, 1u64),
+        );
+        PalletStorageVersion::put(Releases::V1);
+
+        NFT721Module::on_runtime_upgrade();
+
+        let class_info = NFT721Module::class_infos(class_id).unwrap();
+        assert_eq!(class_info.name, vec![1]);
+        assert_eq!(class_info.total_supply, 1000);
+        assert_eq!(class_info.issuer, 1);
+        assert_eq!(class_info.transfer_policy, TransferPolicy::Transferable);
+    })
+}
+
+#[test]
+fn set_attribute_should_reserve_deposit_until_frozen() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(200),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        let nft_id = NFT721Module::_mint_nft(class_id, vec![1], vec![2], 100, 200, None).unwrap();
+        let target = AttributeTarget::NFT(nft_id);
+
+        assert_noop!(
+            NFT721Module::set_attribute(
+                Origin::signed(1),
+                target.clone(),
+                b"color".to_vec(),
+                AttributeValue::Bool(true)
+            ),
+            Error::<Test>::NoPermission
+        );
+        assert_noop!(
+            NFT721Module::set_attribute(
+                Origin::signed(200),
+                target.clone(),
+                vec![0; 33],
+                AttributeValue::Bool(true)
+            ),
+            Error::<Test>::AttributeKeyTooLong
+        );
+
+        // base 10, plus 5 key bytes and 5 encoded value bytes
+        assert_ok!(NFT721Module::set_attribute(
+            Origin::signed(200),
+            target.clone(),
+            b"color".to_vec(),
+            AttributeValue::Text(b"red".to_vec())
+        ));
+        assert_eq!(Balances::reserved_balance(200), 20);
+        assert_ok!(NFT721Module::set_attribute(
+            Origin::signed(200),
+            target.clone(),
+            b"color".to_vec(),
+            AttributeValue::Bool(true)
+        ));
+        assert_eq!(Balances::reserved_balance(200), 17);
+        assert_ok!(NFT721Module::clear_attribute(
+            Origin::signed(200),
+            target.clone(),
+            b"color".to_vec()
+        ));
+        assert_eq!(Balances::reserved_balance(200), 0);
+        assert_eq!(NFT721Module::attributes(&target, b"color".to_vec()), None);
+
+        assert_ok!(NFT721Module::set_attribute(
+            Origin::signed(200),
+            target.clone(),
+            b"level".to_vec(),
+            AttributeValue::Integer(3)
+        ));
+        assert_ok!(NFT721Module::freeze_metadata(
+            Origin::signed(200),
+            target.clone()
+        ));
+        assert_noop!(
+            NFT721Module::clear_attribute(Origin::signed(200), target.clone(), b"level".to_vec()),
+            Error::<Test>::MetadataFrozen
+        );
+        assert_noop!(
+            NFT721Module::set_attribute(
+                Origin::signed(200),
+                target,
+                b"color".to_vec(),
+                AttributeValue::Bool(false)
+            ),
+            Error::<Test>::MetadataFrozen
+        );
+    })
+}
+
+#[test]
+fn burn_nft_should_clear_frozen_attributes() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(200),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        let nft_id = NFT721Module::_mint_nft(class_id, vec![1], vec![2], 100, 200, None).unwrap();
+        let target = AttributeTarget::NFT(nft_id.clone());
+        assert_ok!(NFT721Module::set_attribute(
+            Origin::signed(200),
+            target.clone(),
+            b"level".to_vec(),
+            AttributeValue::Integer(3)
+        ));
+        assert_ok!(NFT721Module::freeze_metadata(
+            Origin::signed(200),
+            target.clone()
+        ));
+        assert!(Balances::reserved_balance(200) > 0);
+
+        assert_ok!(NFT721Module::burn_nft(Origin::signed(200), nft_id));
+        assert_eq!(Balances::reserved_balance(200), 0);
+        assert_eq!(NFT721Module::attributes(&target, b"level".to_vec()), None);
+        assert!(!NFT721Module::is_metadata_frozen(target.clone()));
+
+        assert_noop!(
+            NFT721Module::set_attribute(
+                Origin::signed(200),
+                target.clone(),
+                b"level".to_vec(),
+                AttributeValue::Integer(4)
+            ),
+            Error::<Test>::NFTBurned
+        );
+        assert_noop!(
+            NFT721Module::freeze_metadata(Origin::signed(200), target),
+            Error::<Test>::NFTBurned
+        );
+    })
+}
+
+#[test]
+fn burn_nft_should_return_deposit() {
+    new_test_ext().execute_with(|| {
+        set_deposit_per_byte(2);
+        assert_noop!(
+            NFT721Module::create_class(
+                Origin::signed(200),
+                vec![1; 17],
+                vec![2],
+                1000,
+                None,
+                TransferPolicy::Transferable
+            ),
+            Error::<Test>::NameTooLong
+        );
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(200),
+            vec![1],
+            vec![2, 3],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        assert_eq!(Balances::reserved_balance(200), 6);
+
+        let class_id = NFT721Module::class_index(0);
+        assert_ok!(NFT721Module::set_class_minters(
+            Origin::signed(200),
+            class_id.clone(),
+            vec![1]
+        ));
+        assert_noop!(
+            NFT721Module::_mint_nft(class_id.clone(), vec![1], vec![2], 100, 1, None),
+            pallet_balances::Error::<Test, _>::InsufficientBalance
+        );
+        let nft_id =
+            NFT721Module::_mint_nft(class_id, vec![1], vec![2, 3, 4], 100, 200, None).unwrap();
+        assert_eq!(Balances::reserved_balance(200), 14);
+
+        assert_ok!(NFT721Module::burn_nft(Origin::signed(200), nft_id.clone()));
+        assert_eq!(Balances::reserved_balance(200), 6);
+        assert_eq!(NFT721Module::nft_deposits(nft_id), None);
+    })
+}
+
+#[test]
+fn class_roles_should_guard_class_management() {
+    new_test_ext().execute_with(|| {
+        set_deposit_per_byte(1);
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(200),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        assert_eq!(Balances::reserved_balance(200), 2);
+
+        assert_noop!(
+            NFT721Module::set_class_minters(Origin::signed(2), class_id.clone(), vec![2]),
+            Error::<Test>::NoPermission
+        );
+        assert_noop!(
+            NFT721Module::set_class_minters(Origin::signed(200), class_id.clone(), vec![1; 5]),
+            Error::<Test>::TooManyMinters
+        );
+        assert_ok!(NFT721Module::set_class_roles(
+            Origin::signed(200),
+            class_id.clone(),
+            2,
+            3
+        ));
+        assert_ok!(NFT721Module::set_class_minters(
+            Origin::signed(2),
+            class_id.clone(),
+            vec![4]
+        ));
+        assert!(NFT721Module::is_class_minter(class_id.clone(), 4));
+        assert_ok!(NFT721Module::update_class_metadata(
+            Origin::signed(2),
+            class_id.clone(),
+            vec![1, 2],
+            vec![3, 4]
+        ));
+        assert_eq!(Balances::reserved_balance(200), 4);
+        assert_noop!(
+            NFT721Module::freeze_metadata(
+                Origin::signed(2),
+                AttributeTarget::Class(class_id.clone())
+            ),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(NFT721Module::freeze_metadata(
+            Origin::signed(3),
+            AttributeTarget::Class(class_id.clone())
+        ));
+        assert_noop!(
+            NFT721Module::update_class_metadata(
+                Origin::signed(2),
+                class_id.clone(),
+                vec![1],
+                vec![]
+            ),
+            Error::<Test>::MetadataFrozen
+        );
+
+        let nft_id =
+            NFT721Module::_mint_nft(class_id.clone(), vec![], vec![], 100, 4, None).unwrap();
+        assert_eq!(NFT721Module::class_supply(class_id.clone()), 1);
+        assert_noop!(
+            NFT721Module::destroy_class(Origin::signed(200), class_id.clone()),
+            Error::<Test>::ClassNotEmpty
+        );
+        assert_ok!(NFT721Module::burn_nft(Origin::signed(4), nft_id));
+        assert_eq!(NFT721Module::class_supply(class_id.clone()), 0);
+
+        assert_ok!(Balances::transfer(Origin::signed(200), 5, 100));
+        assert_ok!(NFT721Module::transfer_class_ownership(
+            Origin::signed(200),
+            class_id.clone(),
+            5
+        ));
+        assert_eq!(Balances::reserved_balance(200), 0);
+        assert_eq!(Balances::reserved_balance(5), 4);
+        assert_noop!(
+            NFT721Module::destroy_class(Origin::signed(200), class_id.clone()),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(NFT721Module::destroy_class(
+            Origin::signed(5),
+            class_id.clone()
+        ));
+        assert_eq!(Balances::reserved_balance(5), 0);
+        assert_eq!(NFT721Module::class_infos(class_id.clone()), None);
+        assert!(!NFT721Module::is_class_minter(class_id.clone(), 4));
+        assert_eq!(NFT721Module::class_nonce(class_id.clone()), None);
+        assert_eq!(NFT721Module::class_mint_index(class_id.clone()), 0);
+        assert_eq!(NFT721Module::nft_by_class_index(class_id, 1), None);
+    })
+}
+
+#[test]
+fn public_mint_should_follow_drop() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        let leaf = |who: u64| blake2_256(&who.encode());
+        let (left, right) = if leaf(200) <= leaf(3) {
+            (leaf(200), leaf(3))
+        } else {
+            (leaf(3), leaf(200))
+        };
+        let drop = MintDrop {
+            start: 5,
+            end: 10,
+            price: 50,
+            per_account_limit: 2,
+            allowlist_root: Some(blake2_256(&[left, right].concat())),
+        };
+        assert_noop!(
+            NFT721Module::set_mint_drop(
+                Origin::signed(1),
+                class_id.clone(),
+                MintDrop {
+                    end: 5,
+                    ..drop.clone()
+                }
+            ),
+            Error::<Test>::InvalidDropWindow
+        );
+        assert_ok!(NFT721Module::set_mint_drop(
+            Origin::signed(1),
+            class_id.clone(),
+            drop
+        ));
+
+        assert_noop!(
+            NFT721Module::public_mint(Origin::signed(200), class_id.clone(), vec![leaf(3)]),
+            Error::<Test>::DropNotOpen
+        );
+        System::set_block_number(5);
+        assert_noop!(
+            NFT721Module::public_mint(Origin::signed(4), class_id.clone(), vec![leaf(3)]),
+            Error::<Test>::NotInAllowlist
+        );
+        for _ in 0..2 {
+            assert_ok!(NFT721Module::public_mint(
+                Origin::signed(200),
+                class_id.clone(),
+                vec![leaf(3)]
+            ));
+        }
+        assert_noop!(
+            NFT721Module::public_mint(Origin::signed(200), class_id.clone(), vec![leaf(3)]),
+            Error::<Test>::DropLimitReached
+        );
+        assert_eq!(Balances::free_balance(1), 100);
+        assert_eq!(NFT721Module::owned_nft_count(200), 2);
+        assert_eq!(NFT721Module::class_mint_index(class_id), 2);
+        let nft_id = NFT721Module::owned_nfts_paged(200, 0, 1)[0].clone();
+        let nft = NFT721Module::nft_infos(nft_id).unwrap();
+        assert_eq!((nft.owner, nft.issuer), (200, 1));
+    })
+}
+
+#[test]
+fn mystery_box_should_reject_metadata_of_another_length() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        let metadata = vec![vec![1], vec![2], vec![3]];
+        assert_ok!(NFT721Module::set_mystery_box(
+            Origin::signed(1),
+            class_id.clone(),
+            blake2_256(&metadata.encode()),
+            4,
+            5
+        ));
+        NFT721Module::_mint_nft(class_id.clone(), vec![], vec![], 100, 1, None).unwrap();
+
+        System::set_block_number(5);
+        assert_err!(
+            NFT721Module::reveal_mystery_box(Origin::signed(3), class_id.clone(), metadata),
+            Error::<Test>::MetadataLengthMismatch
+        );
+        assert!(!NFT721Module::mystery_boxes(&class_id).unwrap().revealed);
+    })
+}
+
+#[test]
+fn mystery_box_should_reveal_committed_metadata() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            None,
+            TransferPolicy::Transferable
+        ));
+        let class_id = NFT721Module::class_index(0);
+        let metadata = vec![vec![1], vec![2], vec![3]];
+        let metadata_hash = blake2_256(&metadata.encode());
+        assert_noop!(
+            NFT721Module::set_mystery_box(Origin::signed(1), class_id.clone(), metadata_hash, 3, 1),
+            Error::<Test>::InvalidRevealBlock
+        );
+        assert_ok!(NFT721Module::set_mystery_box(
+            Origin::signed(1),
+            class_id.clone(),
+            metadata_hash,
+            3,
+            5
+        ));
+
+        let nft_ids: Vec<NFTId> = (0..3)
+            .map(|_| {
+                NFT721Module::_mint_nft(class_id.clone(), vec![], vec![], 100, 1, None).unwrap()
+            })
+            .collect();
+        assert_noop!(
+            NFT721Module::_mint_nft(class_id.clone(), vec![], vec![], 100, 1, None),
+            Error::<Test>::NotEnoughMetadata
+        );
+        assert_noop!(
+            NFT721Module::reveal_mystery_box(Origin::signed(3), class_id.clone(), metadata.clone()),
+            Error::<Test>::RevealTooEarly
+        );
+        System::set_block_number(5);
+        // a failed attempt still fixes the seed
+        assert_err!(
+            NFT721Module::reveal_mystery_box(Origin::signed(3), class_id.clone(), vec![vec![1]]),
+            Error::<Test>::MetadataHashMismatch
+        );
+        let seed = NFT721Module::mystery_boxes(&class_id).unwrap().seed;
+        assert!(seed.is_some());
+        System::set_block_number(8);
+        assert_ok!(NFT721Module::reveal_mystery_box(
+            Origin::signed(3),
+            class_id.clone(),
+            metadata.clone()
+        ));
+        assert_eq!(NFT721Module::mystery_boxes(&class_id).unwrap().seed, seed);
+
+        let mut revealed: Vec<Vec<u8>> = nft_ids
+            .iter()
+            .map(|nft_id| NFT721Module::nft_infos(nft_id).unwrap().metadata)
+            .collect();
+        revealed.sort();
+        assert_eq!(revealed, metadata);
+        assert_noop!(
+            NFT721Module::_mint_nft(class_id.clone(), vec![], vec![], 100, 1, None),
+            Error::<Test>::MysteryBoxRevealed
+        );
+        assert_noop!(
+            NFT721Module::reveal_mystery_box(Origin::signed(3), class_id, metadata),
+            Error::<Test>::MysteryBoxRevealed
+        );
     })
 }
@@ -2,11 +2,14 @@
 #![allow(clippy::string_lit_as_bytes)]
 
 use frame_support::{
-    decl_error, decl_event, decl_module, decl_storage, dispatch::Vec, ensure, StorageMap,
-    StorageValue,
+    decl_error, decl_event, decl_module, decl_storage, dispatch::Vec, ensure, Parameter,
+    StorageMap, StorageValue,
 };
 use frame_system::ensure_signed;
-use sp_runtime::DispatchResult;
+use sp_runtime::{
+    traits::{AtLeast32BitUnsigned, Member},
+    DispatchResult,
+};
 use sp_std::{
     cmp::{Eq, PartialEq},
     prelude::*,
@@ -19,7 +22,9 @@ use utilities::{
 
 pub trait Config: frame_system::Config {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
-    type Token: TokenManager<Self::AccountId>;
+    /// Pool reserves, shares and trade amounts, matching the `Token` balance type
+    type Balance: Parameter + Member + AtLeast32BitUnsigned + Default + Copy;
+    type Token: TokenManager<Self::AccountId, Self::Balance>;
     type Common: CommonManager<Self::AccountId>;
     type TradePair: TradePairManager<Self::AccountId>;
 }
@@ -28,7 +33,7 @@ decl_storage! {
     trait Store for Module<T: Config> as Amm {
 
         ///	LiquidityPoolId => TradePair
-        LiquidityPools get(fn liquidity_pools): map hasher(blake2_128_concat) Did => Option<LiquidityPool>;
+        LiquidityPools get(fn liquidity_pools): map hasher(blake2_128_concat) Did => Option<LiquidityPool<T::Balance>>;
         /// Index => LiquidityPoolId
         LiquidityPoolIdByIndex get(fn liquidity_pool_id_by_index): map hasher(blake2_128_concat) u64 => Option<Did>;
         /// Index
@@ -38,11 +43,11 @@ decl_storage! {
         /// AccountId => Vec<Index>
         OwnedLiquidityPools get(fn owned_liquidity_pools):map hasher(blake2_128_concat) T::AccountId  => Vec<Did>;
         /// (AccountId,LiquidityPoolId)=> Share
-        OwnedLiquidityPoolShare get(fn owned_liquidity_pool_share):map hasher(blake2_128_concat) (T::AccountId,Did)  => u64;
+        OwnedLiquidityPoolShare get(fn owned_liquidity_pool_share):map hasher(blake2_128_concat) (T::AccountId,Did)  => T::Balance;
 
 
         /// AmmOrderId => AmmOrder
-        AmmOrders get(fn amm_orders): map hasher(blake2_128_concat) Did => Option<AmmOrder<T::AccountId>>;
+        AmmOrders get(fn amm_orders): map hasher(blake2_128_concat) Did => Option<AmmOrder<T::AccountId, T::Balance>>;
         /// Index => AmmOrderId
         AmmOrderIdByIndex get(fn amm_order_id_by_index): map hasher(blake2_128_concat) u64 => Option<Did>;
         /// Index
@@ -61,12 +66,13 @@ decl_event!(
     pub enum Event<T>
     where
         <T as frame_system::Config>::AccountId,
+        <T as Config>::Balance,
     {
         TradePairCreated(AccountId, Did, TradePair),
-        LiquidityPoolInited(AccountId, Did, u64, u64),
-        LiquidityAdded(AccountId, Did, u64),
-        TradeDoned(AccountId, Did, Did, u64),
-        LiquidityRemoved(AccountId, Did, u64),
+        LiquidityPoolInited(AccountId, Did, Balance, Balance),
+        LiquidityAdded(AccountId, Did, Balance),
+        TradeDoned(AccountId, Did, Did, Balance),
+        LiquidityRemoved(AccountId, Did, Balance),
     }
 );
 
@@ -94,24 +100,24 @@ decl_module! {
 
         type Error = Error<T>;
         #[weight = 1_000_000]
-        pub fn init_liquidity_pool(origin, tp_id: Did, base_amount: u64, quote_amount: u64){
+        pub fn init_liquidity_pool(origin, tp_id: Did, base_amount: T::Balance, quote_amount: T::Balance){
             let sender = ensure_signed(origin)?;
 
             Self::_init_liquidity_pool(sender, tp_id, base_amount, quote_amount)?;
         }
         #[weight = 1_000_000]
-        pub fn add_liquidity(origin, lpid: Did, liquidity_share: u64){
+        pub fn add_liquidity(origin, lpid: Did, liquidity_share: T::Balance){
             let sender = ensure_signed(origin)?;
 
             Self::_add_liquidity(sender, lpid, liquidity_share)?;
         }
         #[weight = 1_000_000]
-        pub fn trade(origin, lpid: Did,  token_have: Did, trade_amount: u64, token_want: Did){
+        pub fn trade(origin, lpid: Did,  token_have: Did, trade_amount: T::Balance, token_want: Did){
             let sender = ensure_signed(origin)?;
             Self::_trade(sender, lpid, token_have, trade_amount, token_want)?;
         }
         #[weight = 1_000_000]
-        pub fn remove_liquidity(origin, tp_id: Did, lp_share: u64) {
+        pub fn remove_liquidity(origin, tp_id: Did, lp_share: T::Balance) {
             let sender = ensure_signed(origin)?;
             Self::do_remove_liquidity(sender, tp_id, lp_share)?;
         }
@@ -123,8 +129,8 @@ impl<T: Config> Module<T> {
     fn _init_liquidity_pool(
         sender: T::AccountId,
         tpid: Did,
-        base_amount: u64,
-        quote_amount: u64,
+        base_amount: T::Balance,
+        quote_amount: T::Balance,
     ) -> DispatchResult {
         let trade_pair_raw = T::TradePair::get_trade_pair(tpid.clone());
 
@@ -155,7 +161,7 @@ impl<T: Config> Module<T> {
         };
 
         Nonce::mutate(|n| *n += 1);
-        <LiquidityPools>::insert(lpid.clone(), lp.clone());
+        <LiquidityPools<T>>::insert(lpid.clone(), lp.clone());
 
         let index = Self::liquidity_pool_index();
         <LiquidityPoolIdByIndex>::insert(index.clone(), tpid.clone());
@@ -179,7 +185,11 @@ impl<T: Config> Module<T> {
         Ok(())
     }
 
-    fn _add_liquidity(sender: T::AccountId, lpid: Did, liquidity_share: u64) -> DispatchResult {
+    fn _add_liquidity(
+        sender: T::AccountId,
+        lpid: Did,
+        liquidity_share: T::Balance,
+    ) -> DispatchResult {
         let liquidity_pool_raw = Self::liquidity_pools(lpid.clone());
         ensure!(
             liquidity_pool_raw.is_some(),
@@ -238,7 +248,7 @@ impl<T: Config> Module<T> {
         sender: T::AccountId,
         lpid: Did,
         token_have: Did,
-        token_have_amount: u64,
+        token_have_amount: T::Balance,
         token_want: Did,
     ) -> DispatchResult {
         let liquidity_pool_raw = Self::liquidity_pools(lpid.clone());
@@ -257,7 +267,7 @@ impl<T: Config> Module<T> {
                 || (tpid1.is_some() && tpid1.unwrap() == liquidity_pool.tpid),
             Error::<T>::NoMatchingLiquidityPool
         );
-        let mut token_want_amount: u64;
+        let mut token_want_amount: T::Balance;
         if token_have == liquidity_pool.token0 {
             ensure!(
                 liquidity_pool.token0_amount > token_have_amount,
@@ -266,7 +276,8 @@ impl<T: Config> Module<T> {
             token_want_amount = liquidity_pool.token1_amount.clone();
             liquidity_pool.token0_amount += token_have_amount.clone();
             liquidity_pool.token1_amount = liquidity_pool.k_last
-                / (liquidity_pool.token0_amount.clone() - token_have_amount.clone() * 30);
+                / (liquidity_pool.token0_amount.clone()
+                    - token_have_amount.clone() * T::Balance::from(30u32));
             token_want_amount -= liquidity_pool.token1_amount;
             liquidity_pool.k_last =
                 liquidity_pool.token0_amount.clone() * liquidity_pool.token1_amount.clone();
@@ -281,7 +292,8 @@ impl<T: Config> Module<T> {
             token_want_amount = liquidity_pool.token0_amount.clone();
             liquidity_pool.token1_amount += token_have_amount.clone();
             liquidity_pool.token0_amount = liquidity_pool.k_last
-                / (liquidity_pool.token1_amount.clone() - token_have_amount.clone() * 30);
+                / (liquidity_pool.token1_amount.clone()
+                    - token_have_amount.clone() * T::Balance::from(30u32));
             token_want_amount -= liquidity_pool.token0_amount;
             liquidity_pool.k_last =
                 liquidity_pool.token0_amount.clone() * liquidity_pool.token1_amount.clone();
@@ -309,7 +321,7 @@ impl<T: Config> Module<T> {
     fn do_remove_liquidity(
         sender: T::AccountId,
         lpid: Did,
-        liquidity_share: u64,
+        liquidity_share: T::Balance,
     ) -> DispatchResult {
         let liquidity_pool_raw = Self::liquidity_pools(lpid.clone());
         ensure!(
@@ -367,10 +379,10 @@ impl<T: Config> Module<T> {
         lpid: Did,
         sender: T::AccountId,
         token_have: Did,
-        token_have_amount: u64,
+        token_have_amount: T::Balance,
         token_want: Did,
-        token_want_amount: u64,
-        token_swap_price: u64,
+        token_want_amount: T::Balance,
+        token_swap_price: T::Balance,
     ) -> DispatchResult {
         let nonce = Nonce::get();
 
@@ -399,12 +411,15 @@ impl<T: Config> Module<T> {
     }
 }
 impl<T: Config> Module<T> {
-    pub fn _update_liquidity_pool(lpid: Did, new_liquidity_pool: LiquidityPool) -> DispatchResult {
+    pub fn _update_liquidity_pool(
+        lpid: Did,
+        new_liquidity_pool: LiquidityPool<T::Balance>,
+    ) -> DispatchResult {
         ensure!(
             Self::liquidity_pools(lpid.clone()).is_some(),
             Error::<T>::NoMatchingLiquidityPool
         );
-        <LiquidityPools>::insert(lpid, new_liquidity_pool);
+        <LiquidityPools<T>>::insert(lpid, new_liquidity_pool);
         Ok(())
     }
 }
@@ -457,13 +472,13 @@ impl<T: Config> Module<T> {
     }
 
     //OwnedLiquidityPoolShares
-    fn _add_owned_liquidity_pools_share(sender: T::AccountId, lpid: Did, share: u64) {
+    fn _add_owned_liquidity_pools_share(sender: T::AccountId, lpid: Did, share: T::Balance) {
         let mut shares = Self::owned_liquidity_pool_share((sender.clone(), lpid.clone()));
         shares += share;
         <OwnedLiquidityPoolShare<T>>::insert((sender, lpid), shares);
     }
 
-    fn _remove_owned_liquidity_pools_share(sender: T::AccountId, lpid: Did, share: u64) {
+    fn _remove_owned_liquidity_pools_share(sender: T::AccountId, lpid: Did, share: T::Balance) {
         let mut shares = Self::owned_liquidity_pool_share((sender.clone(), lpid.clone()));
         shares -= share;
         <OwnedLiquidityPoolShare<T>>::insert((sender, lpid), shares);
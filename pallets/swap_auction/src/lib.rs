@@ -6,33 +6,45 @@ use frame_support::{
     decl_error, decl_event, decl_module, decl_storage,
     dispatch::Vec,
     ensure,
-    traits::{Currency, Get, Randomness, Time},
-    StorageMap, StorageValue,
+    traits::{Currency, Get, Randomness, ReservableCurrency, Time},
+    transactional,
+    weights::Weight,
+    StorageDoubleMap, StorageMap, StorageValue,
 };
 use frame_system::ensure_signed;
 use randomness;
 use sp_io::hashing::blake2_256;
-use sp_runtime::DispatchResult;
+use sp_runtime::{
+    traits::{Saturating, Zero},
+    DispatchError, DispatchResult,
+};
 use sp_std::{
     cmp::{Eq, PartialEq},
     prelude::*,
 };
 use utilities::{
-    Auction, AuctionId, AuctionStatus, AuctionType, BidInfo, NFT1155Manager, NFT2006Manager,
-    NFT721Manager, NFTId, NFTType,
+    helpers::{self, PaymentSource},
+    Auction, AuctionId, AuctionStatus, AuctionType, BidInfo, Bundle, BundleId, ClassInfo,
+    MultiNonFungibleManager, NFTId, NFTInfo, NFTOffer, NFTStatus, NFTType,
 };
 
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
 type MomentOf<T> = <<T as Config>::Time as Time>::Moment;
 type BalanceOf<T> =
     <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
 pub trait Config: frame_system::Config {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
-    type Currency: Currency<Self::AccountId>;
+    type Currency: ReservableCurrency<Self::AccountId>;
     type Time: Time;
-    type NFT721: NFT721Manager<Self::AccountId, BalanceOf<Self>>;
-    type NFT1155: NFT1155Manager<Self::AccountId, BalanceOf<Self>>;
-    type NFT2006: NFT2006Manager<Self::AccountId, BalanceOf<Self>>;
+    type NFT: MultiNonFungibleManager<Self::AccountId, BalanceOf<Self>>;
+    type MaxBundleItems: Get<u32>;
+    /// Most offers expiring in a single block
+    type MaxExpiriesPerBlock: Get<u32>;
 }
 
 decl_storage! {
@@ -44,8 +56,17 @@ decl_storage! {
 
         pub Bids get(fn bids): map hasher(blake2_128_concat) AuctionId => Vec<BidInfo<T::AccountId, BalanceOf<T>, MomentOf<T>>>;
 
+        // Offer
+        pub Offers get(fn offers):
+        double_map hasher(twox_64_concat) NFTId, hasher(twox_64_concat) T::AccountId => Option<NFTOffer<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+        pub OfferExpiries get(fn offer_expiries): map hasher(twox_64_concat) T::BlockNumber => Vec<(NFTId, T::AccountId)>;
+
+        // Bundle
+        pub Bundles get(fn bundles): map hasher(twox_64_concat) BundleId => Option<Bundle<T::AccountId, BalanceOf<T>>>;
+
         // Nonce
         pub ANonce get(fn anonce): u64;
+        pub BNonce get(fn bnonce): u64;
 
     }
 }
@@ -54,11 +75,21 @@ decl_event!(
     pub enum Event<T>
     where
         <T as frame_system::Config>::AccountId,
+        Balance = BalanceOf<T>,
     {
         LanuchAuction(AccountId),
         BidAuction(AccountId),
         ConfirmAuction(AccountId),
         CancelAuction(AccountId),
+        RoyaltyPaid(NFTId, AccountId, Balance),
+        OfferMade(AccountId, NFTId, Balance),
+        OfferAccepted(AccountId, AccountId, NFTId, Balance),
+        OfferCanceled(AccountId, NFTId),
+        OfferExpired(AccountId, NFTId),
+        BundleListed(AccountId, BundleId, Balance),
+        /// seller, buyer, bundle, price
+        BundleSold(AccountId, AccountId, BundleId, Balance),
+        BundleCanceled(AccountId, BundleId),
     }
 );
 
@@ -66,8 +97,28 @@ decl_error! {
     /// Error for the trade module.
     pub enum Error for Module<T: Config> {
         AuctionNotExist,
+        AuctionNotActive,
         NotAuctionOwner,
-
+        BidNotExist,
+        NFTNotExist,
+        NFTBurned,
+        NotNFTOwner,
+        CanNotOfferOwnNFT,
+        ZeroOffer,
+        OfferExists,
+        OfferNotExist,
+        OfferExpired,
+        InvalidExpiry,
+        ClassNotExist,
+        NFTNotTransferable,
+        BundleNotExist,
+        NotBundleSeller,
+        EmptyBundle,
+        TooManyBundleItems,
+        DuplicateBundleItem,
+        CanNotBuyOwnBundle,
+        InsufficientReserve,
+        TooManyExpiries,
     }
 }
 
@@ -123,18 +174,103 @@ decl_module! {
         pub fn cancel_bid(origin, auction_id: AuctionId){
             let sender = ensure_signed(origin)?;
 
-            let mut auction = Self::auctions(auction_id.clone()).ok_or(Error::<T>::AuctionNotExist)?;
+            Self::_cancel_auction(auction_id, sender.clone())?;
 
-            ensure!(auction.owner == sender.clone(), Error::<T>::NotAuctionOwner);
+            Self::deposit_event(RawEvent::CancelAuction(sender));
 
-            auction.status = AuctionStatus::Canceled;
+        }
 
-            <Auctions<T>>::insert(auction_id.clone(), &auction);
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3,2)]
+        pub fn make_offer(
+            origin,
+            nft_type: NFTType,
+            nft_id: NFTId,
+            amount: BalanceOf<T>,
+            expiry: T::BlockNumber,
+        ) {
+            let sender = ensure_signed(origin)?;
 
-            Self::deposit_event(RawEvent::CancelAuction(sender));
+            Self::_make_offer(sender.clone(), nft_type, nft_id.clone(), amount, expiry)?;
+
+            Self::deposit_event(RawEvent::OfferMade(sender, nft_id, amount));
+
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4,6)]
+        pub fn accept_offer(origin, nft_id: NFTId, bidder: T::AccountId) {
+            let sender = ensure_signed(origin)?;
+
+            let amount = Self::_accept_offer(sender.clone(), nft_id.clone(), bidder.clone())?;
+
+            Self::deposit_event(RawEvent::OfferAccepted(sender, bidder, nft_id, amount));
 
         }
 
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1,2)]
+        pub fn cancel_offer(origin, nft_id: NFTId) {
+            let sender = ensure_signed(origin)?;
+
+            let offer = Self::offers(nft_id.clone(), sender.clone()).ok_or(Error::<T>::OfferNotExist)?;
+
+            T::Currency::unreserve(&sender, offer.amount);
+            Self::_remove_offer(&nft_id, &sender, offer.expiry);
+
+            Self::deposit_event(RawEvent::OfferCanceled(sender, nft_id));
+
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2 + items.len() as u64, 2 + items.len() as u64)]
+        pub fn list_bundle(
+            origin,
+            items: Vec<(NFTType, NFTId)>,
+            price: BalanceOf<T>,
+        ) {
+            let sender = ensure_signed(origin)?;
+
+            let bundle_id = Self::_list_bundle(sender.clone(), items, price)?;
+
+            Self::deposit_event(RawEvent::BundleListed(sender, bundle_id, price));
+
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1 + T::MaxBundleItems::get() as u64, 1 + T::MaxBundleItems::get() as u64)]
+        pub fn cancel_bundle(origin, bundle_id: BundleId) {
+            let sender = ensure_signed(origin)?;
+
+            Self::_cancel_bundle(sender.clone(), bundle_id.clone())?;
+
+            Self::deposit_event(RawEvent::BundleCanceled(sender, bundle_id));
+
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1 + 4 * T::MaxBundleItems::get() as u64, 1 + 6 * T::MaxBundleItems::get() as u64)]
+        pub fn buy_bundle(origin, bundle_id: BundleId) {
+            let sender = ensure_signed(origin)?;
+
+            let (seller, price) = Self::_buy_bundle(sender.clone(), bundle_id.clone())?;
+
+            Self::deposit_event(RawEvent::BundleSold(seller, sender, bundle_id, price));
+
+        }
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            let expired = <OfferExpiries<T>>::take(now);
+            let count = expired.len() as u64;
+
+            for (nft_id, bidder) in expired {
+                if let Some(offer) = Self::offers(nft_id.clone(), bidder.clone()) {
+                    // the bidder may have cancelled and re-offered with another expiry
+                    if offer.expiry == now {
+                        T::Currency::unreserve(&bidder, offer.amount);
+                        <Offers<T>>::remove(nft_id.clone(), bidder.clone());
+                        Self::deposit_event(RawEvent::OfferExpired(bidder, nft_id));
+                    }
+                }
+            }
+
+            T::DbWeight::get().reads_writes(1 + count, 1 + 2 * count)
+        }
+
 
 
     }
@@ -174,15 +310,22 @@ impl<T: Config> Module<T> {
         Ok(())
     }
 
+    /// Every bid stays reserved until the auction is confirmed or canceled
+    #[transactional]
     fn _bid_auction(
         auction_id: AuctionId,
         price: BalanceOf<T>,
         time: MomentOf<T>,
         sender: T::AccountId,
     ) -> DispatchResult {
-        let _auction = Self::auctions(auction_id.clone()).ok_or(Error::<T>::AuctionNotExist)?;
+        let auction = Self::auctions(auction_id.clone()).ok_or(Error::<T>::AuctionNotExist)?;
+        ensure!(
+            auction.status == AuctionStatus::Created,
+            Error::<T>::AuctionNotActive
+        );
         //To Do
         //check bid action legal
+        T::Currency::reserve(&sender, price)?;
         let new_bid = BidInfo {
             bidder: sender.clone(),
             price: price.clone(),
@@ -200,42 +343,121 @@ impl<T: Config> Module<T> {
         Ok(())
     }
 
+    /// The winning bid pays out of its reserve, every other bid is released
+    #[transactional]
     fn _confirm_auction(
         auction_id: AuctionId,
         sender: T::AccountId,
         winner: T::AccountId,
     ) -> DispatchResult {
-        // to do
-        // this action has a coditdion that the winner bidder should have send money to the NFT holder
-        // this should be autoly handled here in the future.
-
         let mut auction = Self::auctions(auction_id.clone()).ok_or(Error::<T>::AuctionNotExist)?;
+        ensure!(
+            auction.status == AuctionStatus::Created,
+            Error::<T>::AuctionNotActive
+        );
+        Self::_ensure_transferable(&auction.nft_type, &auction.nft_id, &sender, &winner)?;
+
+        let mut bids = Self::bids(&auction_id);
+        let winning_index = bids
+            .iter()
+            .enumerate()
+            .filter(|(_, bid)| bid.bidder == winner && bid.is_legal)
+            .max_by(|(_, a), (_, b)| a.price.cmp(&b.price))
+            .map(|(i, _)| i)
+            .ok_or(Error::<T>::BidNotExist)?;
+        bids[winning_index].is_winner = true;
+        let price = bids[winning_index].price;
+        for (i, bid) in bids.iter().enumerate() {
+            if i != winning_index {
+                T::Currency::unreserve(&bid.bidder, bid.price);
+            }
+        }
+
+        <Bids<T>>::insert(auction_id.clone(), &bids);
 
         auction.status = AuctionStatus::Confirmed;
 
         <Auctions<T>>::insert(auction_id.clone(), &auction);
 
-        match auction.nft_type {
-            NFTType::NFT721 => T::NFT721::transfer_single_nft(
-                sender.clone(),
-                winner.clone(),
-                auction.nft_id.clone(),
-            )?,
-            NFTType::NFT1155 => T::NFT1155::transfer_single_nft(
-                sender.clone(),
-                winner.clone(),
-                auction.nft_id.clone(),
-            )?,
-            NFTType::NFT2006 => T::NFT2006::transfer_single_nft(
-                sender.clone(),
-                winner.clone(),
-                auction.nft_id.clone(),
-            )?,
-        };
+        Self::_pay_with_royalty(&auction.nft_type, &auction.nft_id, &winner, &sender, price)?;
+
+        Self::_transfer_nft(&auction.nft_type, sender, winner, auction.nft_id.clone())?;
+
+        Ok(())
+    }
 
+    fn _cancel_auction(auction_id: AuctionId, sender: T::AccountId) -> DispatchResult {
+        let mut auction = Self::auctions(auction_id.clone()).ok_or(Error::<T>::AuctionNotExist)?;
+        ensure!(auction.owner == sender, Error::<T>::NotAuctionOwner);
+        ensure!(
+            auction.status == AuctionStatus::Created,
+            Error::<T>::AuctionNotActive
+        );
+
+        for bid in Self::bids(&auction_id) {
+            T::Currency::unreserve(&bid.bidder, bid.price);
+        }
+        auction.status = AuctionStatus::Canceled;
+        <Auctions<T>>::insert(auction_id, &auction);
+
+        Ok(())
+    }
+
+    /// Pays `price` out of funds the buyer already reserved
+    fn _pay_with_royalty(
+        nft_type: &NFTType,
+        nft_id: &NFTId,
+        buyer: &T::AccountId,
+        seller: &T::AccountId,
+        price: BalanceOf<T>,
+    ) -> DispatchResult {
+        ensure!(
+            T::Currency::reserved_balance(buyer) >= price,
+            Error::<T>::InsufficientReserve
+        );
+        let royalty = T::NFT::royalty_of(*nft_type, nft_id.clone());
+        if let Some((beneficiary, amount)) = helpers::pay_with_royalty::<_, T::Currency>(
+            PaymentSource::Reserved,
+            buyer,
+            seller,
+            price,
+            royalty,
+        )? {
+            Self::deposit_event(RawEvent::RoyaltyPaid(nft_id.clone(), beneficiary, amount));
+        }
+
+        Ok(())
+    }
+
+    fn _get_nft(nft_type: &NFTType, nft_id: &NFTId) -> Option<NFTInfo<T::AccountId, BalanceOf<T>>> {
+        T::NFT::get_nft(*nft_type, nft_id.clone())
+    }
+
+    fn _ensure_transferable(
+        nft_type: &NFTType,
+        nft_id: &NFTId,
+        from: &T::AccountId,
+        to: &T::AccountId,
+    ) -> DispatchResult {
+        let nft = Self::_get_nft(nft_type, nft_id).ok_or(Error::<T>::NFTNotExist)?;
+        let class_info: ClassInfo<T::AccountId> =
+            T::NFT::get_class(*nft_type, nft.class_id).ok_or(Error::<T>::ClassNotExist)?;
+        ensure!(
+            class_info.can_transfer(from, to),
+            Error::<T>::NFTNotTransferable
+        );
         Ok(())
     }
 
+    fn _transfer_nft(
+        nft_type: &NFTType,
+        from: T::AccountId,
+        to: T::AccountId,
+        nft_id: NFTId,
+    ) -> DispatchResult {
+        T::NFT::transfer_single_nft(*nft_type, from, to, nft_id)
+    }
+
     // nonce
     fn get_anonce() -> u64 {
         let nonce = <ANonce>::get();
@@ -243,3 +465,189 @@ impl<T: Config> Module<T> {
         nonce
     }
 }
+
+// Offer
+impl<T: Config> Module<T> {
+    #[transactional]
+    fn _make_offer(
+        bidder: T::AccountId,
+        nft_type: NFTType,
+        nft_id: NFTId,
+        amount: BalanceOf<T>,
+        expiry: T::BlockNumber,
+    ) -> DispatchResult {
+        let nft = Self::_get_nft(&nft_type, &nft_id).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+        ensure!(nft.owner != bidder, Error::<T>::CanNotOfferOwnNFT);
+        ensure!(!amount.is_zero(), Error::<T>::ZeroOffer);
+        ensure!(
+            expiry > <frame_system::Module<T>>::block_number(),
+            Error::<T>::InvalidExpiry
+        );
+        ensure!(
+            !<Offers<T>>::contains_key(nft_id.clone(), bidder.clone()),
+            Error::<T>::OfferExists
+        );
+
+        <OfferExpiries<T>>::try_mutate(expiry, |offers| -> DispatchResult {
+            ensure!(
+                (offers.len() as u32) < T::MaxExpiriesPerBlock::get(),
+                Error::<T>::TooManyExpiries
+            );
+            offers.push((nft_id.clone(), bidder.clone()));
+            Ok(())
+        })?;
+        T::Currency::reserve(&bidder, amount)?;
+
+        let new_offer = NFTOffer {
+            bidder: bidder.clone(),
+            nft_type,
+            nft_id: nft_id.clone(),
+            amount,
+            expiry,
+        };
+
+        <Offers<T>>::insert(nft_id, bidder, &new_offer);
+
+        Ok(())
+    }
+
+    #[transactional]
+    fn _accept_offer(
+        owner: T::AccountId,
+        nft_id: NFTId,
+        bidder: T::AccountId,
+    ) -> Result<BalanceOf<T>, DispatchError> {
+        let offer =
+            Self::offers(nft_id.clone(), bidder.clone()).ok_or(Error::<T>::OfferNotExist)?;
+        ensure!(
+            offer.expiry > <frame_system::Module<T>>::block_number(),
+            Error::<T>::OfferExpired
+        );
+        let nft = Self::_get_nft(&offer.nft_type, &nft_id).ok_or(Error::<T>::NFTNotExist)?;
+        ensure!(nft.owner == owner, Error::<T>::NotNFTOwner);
+        Self::_ensure_transferable(&offer.nft_type, &nft_id, &owner, &bidder)?;
+
+        Self::_remove_offer(&nft_id, &bidder, offer.expiry);
+
+        Self::_pay_with_royalty(&offer.nft_type, &nft_id, &bidder, &owner, offer.amount)?;
+        Self::_transfer_nft(&offer.nft_type, owner, bidder, nft_id)?;
+
+        Ok(offer.amount)
+    }
+
+    /// Drops the offer and its pending expiry, so a re-offer can not be scheduled twice
+    fn _remove_offer(nft_id: &NFTId, bidder: &T::AccountId, expiry: T::BlockNumber) {
+        <Offers<T>>::remove(nft_id, bidder);
+        <OfferExpiries<T>>::mutate_exists(expiry, |offers| {
+            if let Some(entries) = offers {
+                entries.retain(|(id, who)| id != nft_id || who != bidder);
+                if entries.is_empty() {
+                    *offers = None;
+                }
+            }
+        });
+    }
+}
+
+// Bundle
+impl<T: Config> Module<T> {
+    /// Locks every item, so none of them can be moved or listed elsewhere until sold or canceled
+    #[transactional]
+    fn _list_bundle(
+        seller: T::AccountId,
+        items: Vec<(NFTType, NFTId)>,
+        price: BalanceOf<T>,
+    ) -> Result<BundleId, DispatchError> {
+        ensure!(!items.is_empty(), Error::<T>::EmptyBundle);
+        ensure!(
+            items.len() <= T::MaxBundleItems::get() as usize,
+            Error::<T>::TooManyBundleItems
+        );
+
+        for (i, (nft_type, nft_id)) in items.iter().enumerate() {
+            ensure!(
+                !items[..i].contains(&(*nft_type, nft_id.clone())),
+                Error::<T>::DuplicateBundleItem
+            );
+            let nft = Self::_get_nft(nft_type, nft_id).ok_or(Error::<T>::NFTNotExist)?;
+            ensure!(nft.status != NFTStatus::Burned, Error::<T>::NFTBurned);
+            ensure!(nft.owner == seller, Error::<T>::NotNFTOwner);
+            T::NFT::lock_nft(*nft_type, nft_id.clone())?;
+        }
+
+        let nonce = Self::get_bnonce();
+        let random_seed = <randomness::Module<T>>::random_seed();
+        let encoded = (random_seed, seller.clone(), nonce).encode();
+        let did = blake2_256(&encoded);
+        let bundle_id = BundleId { did };
+
+        <Bundles<T>>::insert(
+            bundle_id.clone(),
+            Bundle {
+                seller,
+                items,
+                price,
+            },
+        );
+
+        Ok(bundle_id)
+    }
+
+    #[transactional]
+    fn _cancel_bundle(seller: T::AccountId, bundle_id: BundleId) -> DispatchResult {
+        let bundle = Self::bundles(bundle_id.clone()).ok_or(Error::<T>::BundleNotExist)?;
+        ensure!(bundle.seller == seller, Error::<T>::NotBundleSeller);
+
+        for (nft_type, nft_id) in bundle.items {
+            T::NFT::unlock_nft(nft_type, nft_id)?;
+        }
+        <Bundles<T>>::remove(bundle_id);
+
+        Ok(())
+    }
+
+    /// Any failing item rolls back the payment and the items already transferred.
+    /// Royalties are paid per item on an even share of the price.
+    #[transactional]
+    fn _buy_bundle(
+        buyer: T::AccountId,
+        bundle_id: BundleId,
+    ) -> Result<(T::AccountId, BalanceOf<T>), DispatchError> {
+        let bundle = Self::bundles(bundle_id.clone()).ok_or(Error::<T>::BundleNotExist)?;
+        ensure!(bundle.seller != buyer, Error::<T>::CanNotBuyOwnBundle);
+
+        <Bundles<T>>::remove(bundle_id);
+        T::Currency::reserve(&buyer, bundle.price)?;
+
+        let count: BalanceOf<T> = (bundle.items.len() as u32).into();
+        let share = bundle.price / count;
+        let mut remaining = bundle.price;
+        for (i, (nft_type, nft_id)) in bundle.items.iter().enumerate() {
+            let amount = if i + 1 == bundle.items.len() {
+                remaining
+            } else {
+                share
+            };
+            remaining = remaining.saturating_sub(amount);
+
+            T::NFT::unlock_nft(*nft_type, nft_id.clone())?;
+            Self::_ensure_transferable(nft_type, nft_id, &bundle.seller, &buyer)?;
+            Self::_pay_with_royalty(nft_type, nft_id, &buyer, &bundle.seller, amount)?;
+            Self::_transfer_nft(
+                nft_type,
+                bundle.seller.clone(),
+                buyer.clone(),
+                nft_id.clone(),
+            )?;
+        }
+
+        Ok((bundle.seller, bundle.price))
+    }
+
+    fn get_bnonce() -> u64 {
+        let nonce = <BNonce>::get();
+        <BNonce>::mutate(|n| *n += 1u64);
+        nonce
+    }
+}
@@ -0,0 +1,133 @@
+use crate as pallet_swap_auction;
+use sp_core::H256;
+use frame_support::{parameter_types, traits::Time};
+use sp_runtime::{
+	traits::{BlakeTwo256, IdentityLookup}, testing::{Header, TestSignature, UintAuthorityId},
+};
+use frame_system as system;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+		NFT721Module: pallet_nft721::{Module, Call, Storage, Event<T>},
+		SwapAuctionModule: pallet_swap_auction::{Module, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxNestingDepth: u32 = 2;
+	pub const AttributeDepositBase: u64 = 0;
+	pub const AttributeDepositPerByte: u64 = 0;
+	pub const AttributeKeyLimit: u32 = 32;
+	pub const AttributeValueLimit: u32 = 64;
+	pub const ClassDepositBase: u64 = 0;
+	pub const NFTDepositBase: u64 = 0;
+	pub const DepositPerByte: u64 = 0;
+	pub const NameLimit: u32 = 16;
+	pub const InfoLimit: u32 = 64;
+	pub const MaxClassMinters: u32 = 4;
+	pub const MaxExpiriesPerBlock: u32 = 2;
+	pub const MaxBundleItems: u32 = 4;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+impl pallet_nft721::Config for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type Signature = TestSignature;
+	type PublicKey = UintAuthorityId;
+	type NFT = NFTAdapter;
+	type MaxNestingDepth = MaxNestingDepth;
+	type AttributeDepositBase = AttributeDepositBase;
+	type AttributeDepositPerByte = AttributeDepositPerByte;
+	type AttributeKeyLimit = AttributeKeyLimit;
+	type AttributeValueLimit = AttributeValueLimit;
+	type ClassDepositBase = ClassDepositBase;
+	type NFTDepositBase = NFTDepositBase;
+	type DepositPerByte = DepositPerByte;
+	type NameLimit = NameLimit;
+	type InfoLimit = InfoLimit;
+	type MaxClassMinters = MaxClassMinters;
+	type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+	type Randomness = randomness::Module<Test>;
+}
+
+impl pallet_swap_auction::Config for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type Time = MockTime;
+	type NFT = NFTAdapter;
+	type MaxBundleItems = MaxBundleItems;
+	type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+}
+
+/// Every standard is served by the 721 pallet
+pub type NFTAdapter = utilities::NonFungibleAdapter<NFT721Module, NFT721Module, NFT721Module>;
+
+pub struct MockTime;
+impl Time for MockTime {
+	type Moment = u64;
+	fn now() -> u64 {
+		0
+	}
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+	pallet_balances::GenesisConfig::<Test>{
+		balances: vec![(1, 100), (2, 100), (3, 1000), (4, 1000)],
+	}.assimilate_storage(&mut t).unwrap();
+
+	let mut t: sp_io::TestExternalities = t.into();
+
+	t.execute_with(|| System::set_block_number(1) );
+	t
+}
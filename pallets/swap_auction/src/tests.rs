@@ -0,0 +1,262 @@
+use crate::mock::*;
+use crate::{Bundles, Error};
+use frame_support::{
+    assert_noop, assert_ok,
+    traits::{Currency, ReservableCurrency},
+    IterableStorageMap,
+};
+use sp_runtime::Perbill;
+use utilities::{AuctionStatus, AuctionType, NFTId, NFTType, RoyaltyInfo, TransferPolicy};
+
+/// A class paying 10% royalties to account 1, with one NFT held by `owner`
+fn mint_nft(owner: u64) -> NFTId {
+    if NFT721Module::class_count() == 0 {
+        assert_ok!(NFT721Module::create_class(
+            Origin::signed(1),
+            vec![1],
+            vec![2],
+            1000,
+            Some(RoyaltyInfo {
+                beneficiary: 1,
+                rate: Perbill::from_percent(10),
+            }),
+            TransferPolicy::Transferable
+        ));
+    }
+    let class_id = NFT721Module::class_index(0);
+    assert_ok!(NFT721Module::mint_nft(
+        Origin::signed(1),
+        class_id.clone(),
+        vec![1],
+        vec![2],
+        0,
+        None
+    ));
+    let index = NFT721Module::class_mint_index(class_id.clone());
+    let nft_id = NFT721Module::nft_by_class_index(class_id, index).unwrap();
+    assert_ok!(NFT721Module::transfer_nft(
+        Origin::signed(1),
+        1,
+        owner,
+        nft_id.clone()
+    ));
+    nft_id
+}
+
+#[test]
+fn confirm_auction_should_pay_royalty_from_reserved_bid() {
+    new_test_ext().execute_with(|| {
+        let nft_id = mint_nft(2);
+        assert_ok!(SwapAuctionModule::lanuch_auction(
+            Origin::signed(2),
+            AuctionType::EighshAuction,
+            NFTType::NFT721,
+            nft_id.clone(),
+            None,
+            None,
+            None
+        ));
+        let auction_id = SwapAuctionModule::auction_index(0);
+
+        assert_ok!(SwapAuctionModule::bid(
+            Origin::signed(3),
+            auction_id.clone(),
+            200,
+            1
+        ));
+        assert_ok!(SwapAuctionModule::bid(
+            Origin::signed(4),
+            auction_id.clone(),
+            150,
+            2
+        ));
+        assert_eq!(Balances::reserved_balance(3), 200);
+        assert_eq!(Balances::reserved_balance(4), 150);
+
+        assert_ok!(SwapAuctionModule::confirm_bid_result(
+            Origin::signed(2),
+            auction_id.clone(),
+            3
+        ));
+        assert_eq!(Balances::free_balance(1), 120);
+        assert_eq!(Balances::free_balance(2), 280);
+        assert_eq!(Balances::total_balance(&3), 800);
+        assert_eq!(Balances::reserved_balance(4), 0);
+        assert_eq!(NFT721Module::nft_infos(nft_id).unwrap().owner, 3);
+
+        assert_noop!(
+            SwapAuctionModule::confirm_bid_result(Origin::signed(2), auction_id.clone(), 3),
+            Error::<Test>::AuctionNotActive
+        );
+        assert_noop!(
+            SwapAuctionModule::bid(Origin::signed(4), auction_id, 300, 3),
+            Error::<Test>::AuctionNotActive
+        );
+    })
+}
+
+#[test]
+fn cancel_auction_should_release_bids() {
+    new_test_ext().execute_with(|| {
+        let nft_id = mint_nft(2);
+        assert_ok!(SwapAuctionModule::lanuch_auction(
+            Origin::signed(2),
+            AuctionType::EighshAuction,
+            NFTType::NFT721,
+            nft_id,
+            None,
+            None,
+            None
+        ));
+        let auction_id = SwapAuctionModule::auction_index(0);
+        assert_ok!(SwapAuctionModule::bid(
+            Origin::signed(3),
+            auction_id.clone(),
+            200,
+            1
+        ));
+
+        assert_ok!(SwapAuctionModule::cancel_bid(
+            Origin::signed(2),
+            auction_id.clone()
+        ));
+        assert_eq!(Balances::reserved_balance(3), 0);
+        assert_eq!(
+            SwapAuctionModule::auctions(auction_id.clone())
+                .unwrap()
+                .status,
+            AuctionStatus::Canceled
+        );
+
+        assert_noop!(
+            SwapAuctionModule::bid(Origin::signed(3), auction_id.clone(), 200, 2),
+            Error::<Test>::AuctionNotActive
+        );
+        assert_noop!(
+            SwapAuctionModule::confirm_bid_result(Origin::signed(2), auction_id, 3),
+            Error::<Test>::AuctionNotActive
+        );
+    })
+}
+
+#[test]
+fn confirm_auction_should_fail_when_bid_is_no_longer_reserved() {
+    new_test_ext().execute_with(|| {
+        let nft_id = mint_nft(2);
+        assert_ok!(SwapAuctionModule::lanuch_auction(
+            Origin::signed(2),
+            AuctionType::EighshAuction,
+            NFTType::NFT721,
+            nft_id,
+            None,
+            None,
+            None
+        ));
+        let auction_id = SwapAuctionModule::auction_index(0);
+        assert_ok!(SwapAuctionModule::bid(
+            Origin::signed(3),
+            auction_id.clone(),
+            200,
+            1
+        ));
+        Balances::unreserve(&3, 200);
+        assert_noop!(
+            SwapAuctionModule::confirm_bid_result(Origin::signed(2), auction_id, 3),
+            Error::<Test>::InsufficientReserve
+        );
+    })
+}
+
+#[test]
+fn accept_offer_should_pay_royalty() {
+    new_test_ext().execute_with(|| {
+        let nft_id = mint_nft(2);
+        assert_noop!(
+            SwapAuctionModule::make_offer(
+                Origin::signed(3),
+                NFTType::NFT721,
+                nft_id.clone(),
+                0,
+                10
+            ),
+            Error::<Test>::ZeroOffer
+        );
+        assert_ok!(SwapAuctionModule::make_offer(
+            Origin::signed(3),
+            NFTType::NFT721,
+            nft_id.clone(),
+            100,
+            10
+        ));
+        assert_eq!(Balances::reserved_balance(3), 100);
+
+        assert_ok!(SwapAuctionModule::accept_offer(
+            Origin::signed(2),
+            nft_id.clone(),
+            3
+        ));
+        assert_eq!(Balances::free_balance(1), 110);
+        assert_eq!(Balances::free_balance(2), 190);
+        assert_eq!(Balances::total_balance(&3), 900);
+        assert_eq!(NFT721Module::nft_infos(nft_id).unwrap().owner, 3);
+    })
+}
+
+#[test]
+fn make_offer_should_bound_expiries_per_block() {
+    new_test_ext().execute_with(|| {
+        let nft_id = mint_nft(2);
+        assert_ok!(SwapAuctionModule::make_offer(
+            Origin::signed(3),
+            NFTType::NFT721,
+            nft_id.clone(),
+            100,
+            10
+        ));
+        assert_ok!(SwapAuctionModule::cancel_offer(
+            Origin::signed(3),
+            nft_id.clone()
+        ));
+        assert_eq!(SwapAuctionModule::offer_expiries(10).len(), 0);
+
+        // a cancelled offer no longer holds a slot
+        for bidder in 3..5 {
+            assert_ok!(SwapAuctionModule::make_offer(
+                Origin::signed(bidder),
+                NFTType::NFT721,
+                nft_id.clone(),
+                100,
+                10
+            ));
+        }
+        assert_eq!(SwapAuctionModule::offer_expiries(10).len(), 2);
+        assert_noop!(
+            SwapAuctionModule::make_offer(Origin::signed(1), NFTType::NFT721, nft_id, 50, 10),
+            Error::<Test>::TooManyExpiries
+        );
+    })
+}
+
+#[test]
+fn buy_bundle_should_pay_royalty_per_item() {
+    new_test_ext().execute_with(|| {
+        let first = mint_nft(2);
+        let second = mint_nft(2);
+        assert_ok!(SwapAuctionModule::list_bundle(
+            Origin::signed(2),
+            vec![
+                (NFTType::NFT721, first.clone()),
+                (NFTType::NFT721, second.clone())
+            ],
+            300
+        ));
+        let (bundle_id, _) = Bundles::<Test>::iter().next().unwrap();
+
+        assert_ok!(SwapAuctionModule::buy_bundle(Origin::signed(3), bundle_id));
+        assert_eq!(Balances::free_balance(1), 130);
+        assert_eq!(Balances::free_balance(2), 370);
+        assert_eq!(Balances::total_balance(&3), 700);
+        assert_eq!(NFT721Module::nft_infos(first).unwrap().owner, 3);
+        assert_eq!(NFT721Module::nft_infos(second).unwrap().owner, 3);
+    })
+}
@@ -4,10 +4,13 @@
 
 use frame_support::{
     decl_error, decl_event, decl_module, decl_storage, dispatch::Vec, ensure, traits::Time,
-    StorageMap, StorageValue,
+    Parameter, StorageMap, StorageValue,
 };
 use frame_system::ensure_signed;
-use sp_runtime::DispatchResult;
+use sp_runtime::{
+    traits::{AtLeast32BitUnsigned, CheckedMul, Member, Zero},
+    DispatchResult,
+};
 use sp_std::{
     cmp::{Eq, PartialEq},
     prelude::*,
@@ -26,7 +29,9 @@ type MomentOf<T> = <<T as Config>::Time as Time>::Moment;
 pub trait Config: frame_system::Config {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
     type Time: Time;
-    type Token: TokenManager<Self::AccountId>;
+    /// Order prices and amounts, matching the `Token` balance type
+    type Balance: Parameter + Member + AtLeast32BitUnsigned + Default + Copy;
+    type Token: TokenManager<Self::AccountId, Self::Balance>;
     type Common: CommonManager<Self::AccountId>;
     type TradePair: TradePairManager<Self::AccountId>;
 }
@@ -34,9 +39,10 @@ pub trait Config: frame_system::Config {
 decl_event!(
     pub enum Event<T> where
         <T as frame_system::Config>::AccountId,
+        <T as Config>::Balance,
     {
 
-        LimitOrderCreated(AccountId, Did, OrderType, u64, u64),
+        LimitOrderCreated(AccountId, Did, OrderType, Balance, Balance),
 
         OrderCanceled(AccountId, Did),
     }
@@ -68,7 +74,7 @@ decl_storage! {
     trait Store for Module<T: Config> as OrderBook {
 
         /// OrderId => Order
-        pub Orders get(fn order): map hasher(blake2_128_concat) Did => Option<LimitOrder<T::AccountId, MomentOf<T>>>;
+        pub Orders get(fn order): map hasher(blake2_128_concat) Did => Option<LimitOrder<T::AccountId, MomentOf<T>, T::Balance>>;
         /// Index => OrderId
         pub OrderIdByIndex get(fn order_id_by_index): map hasher(blake2_128_concat) u32 => Option<Did>;
         /// Index
@@ -111,7 +117,7 @@ decl_module! {
         type Error = Error<T>;
         fn deposit_event() = default;
        #[weight = 10_000]
-        pub fn create_limit_order(origin, tpid: Did, otype: OrderType, price: u64, sell_amount: u64)  {
+        pub fn create_limit_order(origin, tpid: Did, otype: OrderType, price: T::Balance, sell_amount: T::Balance)  {
             let sender = ensure_signed(origin)?;
 
             Self::_create_limit_order(sender, tpid, otype, price, sell_amount)?;
@@ -133,8 +139,8 @@ impl<T: Config> Module<T> {
         sender: T::AccountId,
         tpid: Did,
         otype: OrderType,
-        price: u64,
-        amount: u64,
+        price: T::Balance,
+        amount: T::Balance,
     ) -> DispatchResult {
         let trade_pair_raw = T::TradePair::get_trade_pair(tpid.clone());
         ensure!(trade_pair_raw.is_some(), Error::<T>::NoMatchingTradePair);
@@ -221,19 +227,16 @@ impl<T: Config> Module<T> {
     fn _ensure_bounds_of_limit_order_create(
         sender: T::AccountId,
         quote: Did,
-        price: u64,
-        amount: u64,
+        price: T::Balance,
+        amount: T::Balance,
     ) -> DispatchResult {
-        ensure!(
-            price > 0 && price <= u64::max_value(),
-            Error::<T>::BoundsCheckFailed
-        );
-        ensure!(
-            amount > 0 && amount <= u64::max_value(),
-            Error::<T>::BoundsCheckFailed
-        );
+        ensure!(!price.is_zero(), Error::<T>::BoundsCheckFailed);
+        ensure!(!amount.is_zero(), Error::<T>::BoundsCheckFailed);
+        let cost = amount
+            .checked_mul(&price)
+            .ok_or(Error::<T>::OverflowError)?;
         let balance = T::Token::balance_of(sender.clone(), quote.clone());
-        ensure!(balance >= amount * price, Error::<T>::BoundsCheckFailed);
+        ensure!(balance >= cost, Error::<T>::BoundsCheckFailed);
         T::Token::ensure_free_balance(sender.clone(), quote.clone(), amount.clone())?;
         T::Token::freeze(sender.clone(), quote.clone(), amount.clone())?;
         Ok(())
@@ -241,7 +244,7 @@ impl<T: Config> Module<T> {
     ///param bounds check
     fn _ensure_bounds_of_limit_order_cancel(
         sender: T::AccountId,
-        order: LimitOrder<T::AccountId, MomentOf<T>>,
+        order: LimitOrder<T::AccountId, MomentOf<T>, T::Balance>,
     ) -> DispatchResult {
         ensure!(order.owner == sender, Error::<T>::CanOnlyCancelOwnOrder);
 
@@ -251,8 +254,10 @@ impl<T: Config> Module<T> {
         );
         Ok(())
     }
-    pub fn is_limit_order_finished(order: LimitOrder<T::AccountId, MomentOf<T>>) -> bool {
-        (order.remained_amount == 0 && order.status == OrderStatus::Filled)
+    pub fn is_limit_order_finished(
+        order: LimitOrder<T::AccountId, MomentOf<T>, T::Balance>,
+    ) -> bool {
+        (order.remained_amount.is_zero() && order.status == OrderStatus::Filled)
             || order.status == OrderStatus::Canceled
     }
 }
@@ -8,13 +8,11 @@ use frame_support::{
 use frame_system::ensure_signed;
 use sp_runtime::DispatchResult;
 use sp_std::prelude::*;
-use utilities::{ClassId, DAOManager, NFT1155Manager, NFT2006Manager, NFT721Manager, NFTId};
+use utilities::{ClassId, DAOManager, MultiNonFungibleManager, NFTId, NFTType};
 pub trait Config: frame_system::Config {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
     type Currency: Currency<Self::AccountId>;
-    type NFT721: NFT721Manager<Self::AccountId, BalanceOf<Self>>;
-    type NFT1155: NFT1155Manager<Self::AccountId, BalanceOf<Self>>;
-    type NFT2006: NFT2006Manager<Self::AccountId, BalanceOf<Self>>;
+    type NFT: MultiNonFungibleManager<Self::AccountId, BalanceOf<Self>>;
     type DAO: DAOManager<Self::AccountId, BalanceOf<Self>>;
 }
 
@@ -23,11 +21,7 @@ decl_event!(
         <T as frame_system::Config>::AccountId,
     {
 
-        MintNFT721WithTax(AccountId),
-
-        MintNFT1155WithTax(AccountId),
-
-        MintNFT2006WithTax(AccountId),
+        MintNFTWithTax(AccountId, NFTType),
 
         PayNFTTax(AccountId),
 
@@ -69,57 +63,9 @@ decl_module! {
         fn deposit_event() = default;
 
         #[weight = 10_000]
-         pub fn mint_nft721_with_tax(
-            origin,
-            class_id: ClassId,
-            info: Vec<u8>,
-            metadata: Vec<u8>,
-            price: BalanceOf<T>,
-        ) {
-            let who = ensure_signed(origin)?;
-
-            let nft_id = T::NFT721::mint_nft(class_id.clone(), info.clone(), metadata.clone(), price.clone(), who.clone());
-
-            ensure!(nft_id != None, Error::<T>::NFTMintERR);
-
-            let mut nids = Self::nft_in_tax(who.clone());
-
-             nids.push(nft_id.unwrap());
-
-            <NFTInTax<T>>::insert(&who, &nids);
-
-            Self::deposit_event(RawEvent::MintNFT721WithTax(who));
-
-        }
-
-        #[weight = 10_000]
-         pub fn mint_nft1155_with_tax(
-            origin,
-            class_id: ClassId,
-            info: Vec<u8>,
-            metadata: Vec<u8>,
-            price: BalanceOf<T>,
-        ) {
-            let who = ensure_signed(origin)?;
-
-            let nft_id = T::NFT1155::mint_nft(class_id.clone(), info.clone(), metadata.clone(), price.clone(), who.clone());
-
-            ensure!(nft_id != None, Error::<T>::NFTMintERR);
-
-            let mut nids = Self::nft_in_tax(who.clone());
-
-            nids.push(nft_id.unwrap());
-
-            <NFTInTax<T>>::insert(&who, &nids);
-
-            Self::deposit_event(RawEvent::MintNFT721WithTax(who));
-
-        }
-
-
-        #[weight = 10_000]
-         pub fn mint_nft2006_with_tax(
+         pub fn mint_nft_with_tax(
             origin,
+            nft_type: NFTType,
             class_id: ClassId,
             info: Vec<u8>,
             metadata: Vec<u8>,
@@ -127,7 +73,7 @@ decl_module! {
         ) {
             let who = ensure_signed(origin)?;
 
-            let nft_id = T::NFT2006::mint_nft(class_id.clone(), info.clone(), metadata.clone(), price.clone(), who.clone());
+            let nft_id = T::NFT::mint_nft(nft_type, class_id.clone(), info.clone(), metadata.clone(), price, who.clone());
 
             ensure!(nft_id != None, Error::<T>::NFTMintERR);
 
@@ -137,7 +83,7 @@ decl_module! {
 
             <NFTInTax<T>>::insert(&who, &nids);
 
-            Self::deposit_event(RawEvent::MintNFT721WithTax(who));
+            Self::deposit_event(RawEvent::MintNFTWithTax(who, nft_type));
 
         }
 
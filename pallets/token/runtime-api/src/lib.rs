@@ -0,0 +1,11 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use utilities::{Did, TokenMetadata};
+
+sp_api::decl_runtime_apis! {
+    pub trait TokenApi {
+        /// The token registered under `symbol`, matched case-insensitively
+        fn token_by_symbol(symbol: Vec<u8>) -> Option<(Did, TokenMetadata)>;
+    }
+}
@@ -2,32 +2,70 @@
 #![allow(clippy::string_lit_as_bytes)]
 
 use frame_support::{
-    decl_error, decl_event, decl_module, decl_storage, dispatch::Vec, ensure, StorageMap,
-    StorageValue,
+    decl_error, decl_event, decl_module, decl_storage,
+    dispatch::Vec,
+    ensure,
+    traits::{Currency, Get, ReservableCurrency},
+    transactional, IterableStorageMap, Parameter, StorageMap, StorageValue,
 };
 use frame_system::ensure_signed;
-use sp_runtime::DispatchResult;
+use sp_runtime::{
+    traits::{AtLeast32BitUnsigned, CheckedAdd, CheckedSub, Member, Saturating, Zero},
+    DispatchError, DispatchResult,
+};
 use sp_std::{
     cmp::{Eq, PartialEq},
     prelude::*,
 };
-use utilities::{CommonManager, Did, Token, TokenManager};
+use utilities::{
+    CommonManager, DAOManager, Did, StorageDeposit, Token, TokenManager, TokenMetadata, TokenRoles,
+};
 
-// #[cfg(test)]
-// mod tests;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
 
 pub trait Config: frame_system::Config {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
     type Common: CommonManager<Self::AccountId>;
+    /// Token amounts; the `Currency` only pays storage deposits
+    type Balance: Parameter + Member + AtLeast32BitUnsigned + Default + Copy;
+    type Currency: ReservableCurrency<Self::AccountId>;
+    /// Reserved per token, plus `DepositPerByte` for its symbol
+    type TokenDepositBase: Get<BalanceOf<Self>>;
+    type DepositPerByte: Get<BalanceOf<Self>>;
+    type SymbolLimit: Get<u32>;
+    type NameLimit: Get<u32>;
+    type UriLimit: Get<u32>;
+    /// The DAO account verifies token metadata
+    type DAO: DAOManager<Self::AccountId, BalanceOf<Self>>;
 }
 
+type BalanceOf<T> =
+    <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
 decl_storage! {
     trait Store for Module<T: Config> as Token {
-        pub Tokens get(fn token): map hasher(blake2_128_concat) Did => Option<Token<T::AccountId>>;
-        pub Balances get(fn balance_of): map hasher(blake2_128_concat) (T::AccountId, Did) => u64;
-        pub StaticBalances get(fn static_balance_of): map hasher(blake2_128_concat) (Did, Did) => u64;
-        pub FreeBalances get(fn free_balance_of): map hasher(blake2_128_concat) (T::AccountId, Did) => u64;
-        pub FreezedBalances get(fn freezed_balance_of): map hasher(blake2_128_concat) (T::AccountId, Did) => u64;
+        pub Tokens get(fn token): map hasher(blake2_128_concat) Did => Option<Token<T::AccountId, T::Balance>>;
+        pub TokenDeposits get(fn token_deposit): map hasher(blake2_128_concat) Did => Option<StorageDeposit<T::AccountId, BalanceOf<T>>>;
+        pub TokenRoleInfos get(fn token_role_infos): map hasher(blake2_128_concat) Did => Option<TokenRoles<T::AccountId>>;
+        pub SupplyCaps get(fn supply_cap): map hasher(blake2_128_concat) Did => Option<T::Balance>;
+        pub FrozenAccounts get(fn is_account_frozen): map hasher(blake2_128_concat) (T::AccountId, Did) => bool;
+
+        // Metadata
+        pub TokenMetadatas get(fn token_metadata): map hasher(blake2_128_concat) Did => Option<TokenMetadata>;
+        pub MetadataDeposits get(fn metadata_deposit): map hasher(blake2_128_concat) Did => Option<StorageDeposit<T::AccountId, BalanceOf<T>>>;
+        /// Upper-cased symbol, held as long as the token exists
+        pub SymbolToToken get(fn symbol_to_token): map hasher(blake2_128_concat) Vec<u8> => Option<Did>;
+
+        // Balance
+        pub Balances get(fn balance_of): map hasher(blake2_128_concat) (T::AccountId, Did) => T::Balance;
+        pub StaticBalances get(fn static_balance_of): map hasher(blake2_128_concat) (Did, Did) => T::Balance;
+        pub FreeBalances get(fn free_balance_of): map hasher(blake2_128_concat) (T::AccountId, Did) => T::Balance;
+        pub FreezedBalances get(fn freezed_balance_of): map hasher(blake2_128_concat) (T::AccountId, Did) => T::Balance;
+        /// owner, spender, token
+        pub Allowances get(fn allowance): map hasher(blake2_128_concat) (T::AccountId, T::AccountId, Did) => T::Balance;
 
         pub OwnedTokens get(fn owned_token): map hasher(blake2_128_concat) (T::AccountId, u64) => Option<Did>;
         pub OwnedTokensIndex get(fn owned_token_index): map hasher(blake2_128_concat) T::AccountId => u64;
@@ -41,13 +79,28 @@ decl_event!(
     pub enum Event<T>
     where
         AccountId = <T as frame_system::Config>::AccountId,
+        Balance = <T as Config>::Balance,
     {
-        Issued(AccountId, Did, u64),
-        Transferd(AccountId, AccountId, Did, u64, Option<Vec<u8>>),
-        StaticTransferdIn(AccountId, Did, Did, u64),
-        StaticTransferdOut(Did, AccountId, Did, u64),
-        Freezed(AccountId, Did, u64),
-        UnFreezed(AccountId, Did, u64),
+        Issued(AccountId, Did, Balance),
+        Transferd(AccountId, AccountId, Did, Balance, Option<Vec<u8>>),
+        StaticTransferdIn(AccountId, Did, Did, Balance),
+        StaticTransferdOut(Did, AccountId, Did, Balance),
+        Freezed(AccountId, Did, Balance),
+        UnFreezed(AccountId, Did, Balance),
+        Burned(AccountId, Did, Balance),
+        /// owner, spender, token, allowance
+        Approval(AccountId, AccountId, Did, Balance),
+        /// spender, from, to, token, amount
+        TransferdFrom(AccountId, AccountId, AccountId, Did, Balance),
+        Minted(AccountId, Did, Balance),
+        /// token, minter, burner, freezer
+        SetTokenRoles(Did, AccountId, AccountId, AccountId),
+        SetSupplyCap(Did, Option<Balance>),
+        AccountFrozen(AccountId, Did),
+        AccountThawed(AccountId, Did),
+        SetTokenMetadata(Did),
+        TokenVerified(Did, bool),
+        Destroyed(AccountId, Did),
     }
 );
 
@@ -62,6 +115,16 @@ decl_error! {
         SenderHaveNoToken,
         BalanceNotEnough,
         AmountOverflow,
+        SymbolTooLong,
+        AllowanceNotEnough,
+        NoPermission,
+        ExceedSupplyCap,
+        InvalidSupplyCap,
+        AccountFrozen,
+        SymbolTaken,
+        NameTooLong,
+        UriTooLong,
+        TokenInUse,
     }
 }
 
@@ -72,49 +135,218 @@ decl_module! {
         /// Initialize the token
         /// transfers the total_supply amout to the caller
         #[weight = 10_000]
-        fn issue(origin, total_supply: u64, symbol: Vec<u8>){
+        fn issue(origin, total_supply: T::Balance, symbol: Vec<u8>){
             let sender = ensure_signed(origin)?;
-            Self::_issue(sender, total_supply, symbol);
+            Self::_issue(sender, total_supply, symbol)?;
         }
 
         /// Transfer tokens from one account to another
         #[weight = 10_000]
-        fn transfer(origin, to: T::AccountId, token_id: Did, amount: u64, memo: Option<Vec<u8>>)-> DispatchResult {
+        fn transfer(origin, to: T::AccountId, token_id: Did, amount: T::Balance, memo: Option<Vec<u8>>)-> DispatchResult {
             let sender = ensure_signed(origin)?;
             Self::_transfer(sender, to, token_id, amount, memo)
         }
+
+        /// Allow `spender` to transfer up to `amount` of the caller's tokens
+        #[weight = 10_000]
+        fn approve(origin, spender: T::AccountId, token_id: Did, amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::_approve(sender, spender, token_id, amount)
+        }
+
+        #[weight = 10_000]
+        fn increase_allowance(origin, spender: T::AccountId, token_id: Did, amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            let allowance = Self::allowance((sender.clone(), spender.clone(), token_id.clone()))
+                .checked_add(&amount)
+                .ok_or(Error::<T>::AmountOverflow)?;
+            Self::_approve(sender, spender, token_id, allowance)
+        }
+
+        #[weight = 10_000]
+        fn decrease_allowance(origin, spender: T::AccountId, token_id: Did, amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            let allowance = Self::allowance((sender.clone(), spender.clone(), token_id.clone()))
+                .checked_sub(&amount)
+                .ok_or(Error::<T>::AllowanceNotEnough)?;
+            Self::_approve(sender, spender, token_id, allowance)
+        }
+
+        /// Transfer tokens of `from` out of the allowance it gave the caller
+        #[weight = 10_000]
+        fn transfer_from(origin, from: T::AccountId, to: T::AccountId, token_id: Did, amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::_transfer_from(sender, from, to, token_id, amount)
+        }
+
+        /// Only the owner can hand out roles; each defaults to the owner
+        #[weight = 10_000]
+        fn set_token_roles(origin, token_id: Did, minter: T::AccountId, burner: T::AccountId, freezer: T::AccountId) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::_set_token_roles(sender, token_id, minter, burner, freezer)
+        }
+
+        /// `None` lifts the cap; a cap can't be below the current supply
+        #[weight = 10_000]
+        fn set_supply_cap(origin, token_id: Did, cap: Option<T::Balance>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::_set_supply_cap(sender, token_id, cap)
+        }
+
+        #[weight = 10_000]
+        fn mint(origin, token_id: Did, to: T::AccountId, amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::_mint(sender, token_id, to, amount)
+        }
+
+        /// Burn the caller's own free tokens
+        #[weight = 10_000]
+        fn burn(origin, token_id: Did, amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::_burn(sender, token_id, amount)
+        }
+
+        /// Burn free tokens of any holder, for the burner role
+        #[weight = 10_000]
+        fn burn_from(origin, token_id: Did, from: T::AccountId, amount: T::Balance) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::_roles(&token_id)?.burner == sender, Error::<T>::NoPermission);
+            Self::_burn(from, token_id, amount)
+        }
+
+        /// A frozen account can't send the token until thawed
+        #[weight = 10_000]
+        fn freeze_account(origin, token_id: Did, who: T::AccountId) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::_roles(&token_id)?.freezer == sender, Error::<T>::NoPermission);
+            FrozenAccounts::<T>::insert((who.clone(), token_id.clone()), true);
+            Self::deposit_event(RawEvent::AccountFrozen(who, token_id));
+            Ok(())
+        }
+
+        /// Changing the metadata clears the verified flag
+        #[weight = 10_000]
+        fn set_token_metadata(origin, token_id: Did, name: Vec<u8>, decimals: u8, logo_uri: Option<Vec<u8>>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::_set_token_metadata(sender, token_id, name, decimals, logo_uri)
+        }
+
+        #[weight = 10_000]
+        fn set_token_verified(origin, token_id: Did, verified: bool) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(sender == T::DAO::get_dao_account(), Error::<T>::NoPermission);
+            let mut metadata = Self::token_metadata(&token_id).ok_or(Error::<T>::NoMatchingToken)?;
+            metadata.verified = verified;
+            TokenMetadatas::insert(token_id.clone(), metadata);
+            Self::deposit_event(RawEvent::TokenVerified(token_id, verified));
+            Ok(())
+        }
+
+        #[weight = 10_000]
+        fn thaw_account(origin, token_id: Did, who: T::AccountId) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::_roles(&token_id)?.freezer == sender, Error::<T>::NoPermission);
+            FrozenAccounts::<T>::remove((who.clone(), token_id.clone()));
+            Self::deposit_event(RawEvent::AccountThawed(who, token_id));
+            Ok(())
+        }
+
+        /// Only the owner can retire a token, once its whole supply is burned
+        #[weight = 10_000]
+        fn destroy(origin, token_id: Did) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::_destroy(sender, token_id)
+        }
     }
 }
 impl<T: Config> Module<T> {
-    fn _issue(from: T::AccountId, total_supply: u64, symbol: Vec<u8>) -> Did {
+    fn _issue(
+        from: T::AccountId,
+        total_supply: T::Balance,
+        symbol: Vec<u8>,
+    ) -> Result<Did, DispatchError> {
+        Self::_do_issue(from.clone(), from, total_supply, symbol)
+    }
+
+    /// `owner` holds every role while the cap keeps the supply where it was issued
+    fn _issue_capped(
+        owner: T::AccountId,
+        holder: T::AccountId,
+        total_supply: T::Balance,
+        symbol: Vec<u8>,
+    ) -> Result<Did, DispatchError> {
+        let token_id = Self::_do_issue(owner, holder, total_supply, symbol)?;
+        SupplyCaps::<T>::insert(token_id.clone(), total_supply);
+        Self::deposit_event(RawEvent::SetSupplyCap(token_id.clone(), Some(total_supply)));
+
+        Ok(token_id)
+    }
+
+    /// The supply goes to `holder`, who also pays the deposit
+    fn _do_issue(
+        owner: T::AccountId,
+        holder: T::AccountId,
+        total_supply: T::Balance,
+        symbol: Vec<u8>,
+    ) -> Result<Did, DispatchError> {
+        ensure!(
+            symbol.len() <= T::SymbolLimit::get() as usize,
+            Error::<T>::SymbolTooLong
+        );
+        let registered_symbol = symbol.to_ascii_uppercase();
+        ensure!(
+            !SymbolToToken::contains_key(&registered_symbol),
+            Error::<T>::SymbolTaken
+        );
+        let deposit = T::DepositPerByte::get()
+            .saturating_mul((symbol.len() as u32).into())
+            .saturating_add(T::TokenDepositBase::get());
+        T::Currency::reserve(&holder, deposit)?;
+
         let nonce = Nonce::get();
-        let new_token_id = T::Common::generate_did(from.clone(), nonce.clone());
+        let new_token_id = T::Common::generate_did(owner.clone(), nonce.clone());
 
         let token = Token {
             tid: new_token_id.clone(),
-            owner: from.clone(),
+            owner: owner.clone(),
             symbol: symbol.clone(),
             total_supply,
         };
 
         Nonce::mutate(|n| *n += 1);
         Tokens::<T>::insert(new_token_id.clone(), token);
-        Balances::<T>::insert((from.clone(), new_token_id.clone()), total_supply);
-        FreeBalances::<T>::insert((from.clone(), new_token_id.clone()), total_supply);
+        TokenMetadatas::insert(
+            new_token_id.clone(),
+            TokenMetadata {
+                symbol,
+                ..Default::default()
+            },
+        );
+        SymbolToToken::insert(registered_symbol, new_token_id.clone());
+        TokenDeposits::<T>::insert(
+            new_token_id.clone(),
+            StorageDeposit {
+                depositor: holder.clone(),
+                amount: deposit,
+            },
+        );
+        Balances::<T>::insert((holder.clone(), new_token_id.clone()), total_supply);
+        FreeBalances::<T>::insert((holder.clone(), new_token_id.clone()), total_supply);
 
-        let owned_token_index = OwnedTokensIndex::<T>::get(from.clone());
-        OwnedTokens::<T>::insert((from.clone(), owned_token_index), new_token_id.clone());
-        OwnedTokensIndex::<T>::insert(from.clone(), owned_token_index + 1);
+        let owned_token_index = OwnedTokensIndex::<T>::get(owner.clone());
+        OwnedTokens::<T>::insert((owner.clone(), owned_token_index), new_token_id.clone());
+        OwnedTokensIndex::<T>::insert(owner, owned_token_index + 1);
 
-        Self::deposit_event(RawEvent::Issued(from, new_token_id.clone(), total_supply));
+        Self::deposit_event(RawEvent::Issued(holder, new_token_id.clone(), total_supply));
 
-        new_token_id
+        Ok(new_token_id)
     }
+    #[transactional]
     fn _transfer(
         sender: T::AccountId,
         to: T::AccountId,
         token_id: Did,
-        amount: u64,
+        amount: T::Balance,
         memo: Option<Vec<u8>>,
     ) -> DispatchResult {
         let token = Self::token(&token_id);
@@ -123,36 +355,34 @@ impl<T: Config> Module<T> {
         if let Some(memos) = &memo {
             ensure!(memos.len() <= 512, Error::<T>::MemoLengthExceedLimitation);
         }
+        ensure!(
+            !Self::is_account_frozen((sender.clone(), token_id.clone())),
+            Error::<T>::AccountFrozen
+        );
 
         ensure!(
             <FreeBalances<T>>::contains_key((sender.clone(), &token_id)),
             Error::<T>::SenderHaveNoToken
         );
 
-        let from_amount = Self::balance_of((sender.clone(), token_id.clone()));
-        ensure!(from_amount >= amount, Error::<T>::BalanceNotEnough);
-        let new_from_amount = from_amount - amount;
-
-        let from_free_amount = Self::free_balance_of((sender.clone(), token_id.clone()));
-        ensure!(from_free_amount >= amount, Error::<T>::BalanceNotEnough);
-        let new_from_free_amount = from_free_amount - amount;
-
-        let to_amount = Self::balance_of((to.clone(), token_id.clone()));
-        let new_to_amount = to_amount + amount;
-        // ensure!(
-        // 	new_to_amount <= T::Balance::max_value(),
-        // 	Error::<T>::AmountOverflow
-        // );
-
-        let to_free_amount = Self::free_balance_of((to.clone(), token_id.clone()));
-        let new_to_free_amount = to_free_amount + amount;
-        // ensure!(
-        // 	new_to_free_amount <= T::Balance::max_value(),
-        // 	Error::<T>::AmountOverflow
-        // );
+        let new_from_amount = Self::balance_of((sender.clone(), token_id.clone()))
+            .checked_sub(&amount)
+            .ok_or(Error::<T>::BalanceNotEnough)?;
+        let new_from_free_amount = Self::free_balance_of((sender.clone(), token_id.clone()))
+            .checked_sub(&amount)
+            .ok_or(Error::<T>::BalanceNotEnough)?;
 
+        // a self transfer has to read the balances it just debited
         Balances::<T>::insert((sender.clone(), token_id.clone()), new_from_amount);
         FreeBalances::<T>::insert((sender.clone(), token_id.clone()), new_from_free_amount);
+
+        let new_to_amount = Self::balance_of((to.clone(), token_id.clone()))
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+        let new_to_free_amount = Self::free_balance_of((to.clone(), token_id.clone()))
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+
         Balances::<T>::insert((to.clone(), token_id.clone()), new_to_amount);
         FreeBalances::<T>::insert((to.clone(), token_id.clone()), new_to_free_amount);
         Self::deposit_event(RawEvent::Transferd(sender, to, token_id, amount, memo));
@@ -164,26 +394,30 @@ impl<T: Config> Module<T> {
         sender: T::AccountId,
         to: Did,
         token_id: Did,
-        amount: u64,
+        amount: T::Balance,
     ) -> DispatchResult {
         let token = Self::token(&token_id);
         ensure!(token.is_some(), Error::<T>::NoMatchingToken);
+        ensure!(
+            !Self::is_account_frozen((sender.clone(), token_id.clone())),
+            Error::<T>::AccountFrozen
+        );
 
         ensure!(
             <FreeBalances<T>>::contains_key((sender.clone(), &token_id)),
             Error::<T>::SenderHaveNoToken
         );
 
-        let from_amount = Self::balance_of((sender.clone(), token_id.clone()));
-        ensure!(from_amount >= amount, Error::<T>::BalanceNotEnough);
-        let new_from_amount = from_amount - amount;
+        let new_from_amount = Self::balance_of((sender.clone(), token_id.clone()))
+            .checked_sub(&amount)
+            .ok_or(Error::<T>::BalanceNotEnough)?;
+        let new_from_free_amount = Self::free_balance_of((sender.clone(), token_id.clone()))
+            .checked_sub(&amount)
+            .ok_or(Error::<T>::BalanceNotEnough)?;
 
-        let from_free_amount = Self::free_balance_of((sender.clone(), token_id.clone()));
-        ensure!(from_free_amount >= amount, Error::<T>::BalanceNotEnough);
-        let new_from_free_amount = from_free_amount - amount;
-
-        let to_amount = Self::static_balance_of((to.clone(), token_id.clone()));
-        let new_to_amount = to_amount + amount;
+        let new_to_amount = Self::static_balance_of((to.clone(), token_id.clone()))
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
 
         Balances::<T>::insert((sender.clone(), token_id.clone()), new_from_amount);
         FreeBalances::<T>::insert((sender.clone(), token_id.clone()), new_from_free_amount);
@@ -196,24 +430,21 @@ impl<T: Config> Module<T> {
         sender: Did,
         to: T::AccountId,
         token_id: Did,
-        amount: u64,
+        amount: T::Balance,
     ) -> DispatchResult {
         let token = Self::token(&token_id);
         ensure!(token.is_some(), Error::<T>::NoMatchingToken);
 
-        let from_amount = Self::static_balance_of((sender.clone(), token_id.clone()));
-        ensure!(from_amount >= amount, Error::<T>::BalanceNotEnough);
-        let new_from_amount = from_amount - amount;
-
-        let to_amount = Self::balance_of((to.clone(), token_id.clone()));
-        let new_to_amount = to_amount + amount;
+        let new_from_amount = Self::static_balance_of((sender.clone(), token_id.clone()))
+            .checked_sub(&amount)
+            .ok_or(Error::<T>::BalanceNotEnough)?;
 
-        let to_free_amount = Self::free_balance_of((to.clone(), token_id.clone()));
-        let new_to_free_amount = to_free_amount + amount;
-        // ensure!(
-        // 	new_to_free_amount <= T::Balance::max_value(),
-        // 	Error::<T>::AmountOverflow
-        // );
+        let new_to_amount = Self::balance_of((to.clone(), token_id.clone()))
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+        let new_to_free_amount = Self::free_balance_of((to.clone(), token_id.clone()))
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
 
         StaticBalances::insert((sender.clone(), token_id.clone()), new_from_amount);
         Balances::<T>::insert((to.clone(), token_id.clone()), new_to_amount);
@@ -223,36 +454,35 @@ impl<T: Config> Module<T> {
         Ok(())
     }
 
-    fn _freeze(sender: T::AccountId, token_id: Did, amount: u64) -> DispatchResult {
+    fn _freeze(sender: T::AccountId, token_id: Did, amount: T::Balance) -> DispatchResult {
         let token = Self::token(&token_id);
         ensure!(token.is_some(), Error::<T>::NoMatchingToken);
+        ensure!(
+            !Self::is_account_frozen((sender.clone(), token_id.clone())),
+            Error::<T>::AccountFrozen
+        );
 
         ensure!(
             FreeBalances::<T>::contains_key((sender.clone(), token_id.clone())),
             Error::<T>::SenderHaveNoToken
         );
 
-        let old_free_amount = Self::free_balance_of((sender.clone(), token_id.clone()));
-        ensure!(old_free_amount >= amount, Error::<T>::BalanceNotEnough);
+        let free_amount = Self::free_balance_of((sender.clone(), token_id.clone()))
+            .checked_sub(&amount)
+            .ok_or(Error::<T>::BalanceNotEnough)?;
+        let freezed_amount = Self::freezed_balance_of((sender.clone(), token_id.clone()))
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
 
-        let old_freezed_amount = Self::freezed_balance_of((sender.clone(), token_id.clone()));
-        // ensure!(
-        // 	old_freezed_amount + amount <= T::Balance::max_value(),
-        // 	Error::<T>::AmountOverflow
-        // );
-
-        FreeBalances::<T>::insert((sender.clone(), token_id.clone()), old_free_amount - amount);
-        FreezedBalances::<T>::insert(
-            (sender.clone(), token_id.clone()),
-            old_freezed_amount + amount,
-        );
+        FreeBalances::<T>::insert((sender.clone(), token_id.clone()), free_amount);
+        FreezedBalances::<T>::insert((sender.clone(), token_id.clone()), freezed_amount);
 
         Self::deposit_event(RawEvent::Freezed(sender, token_id, amount));
 
         Ok(())
     }
 
-    pub fn _unfreeze(sender: T::AccountId, token_id: Did, amount: u64) -> DispatchResult {
+    pub fn _unfreeze(sender: T::AccountId, token_id: Did, amount: T::Balance) -> DispatchResult {
         let token = Self::token(&token_id);
         ensure!(token.is_some(), Error::<T>::NoMatchingToken);
 
@@ -261,27 +491,290 @@ impl<T: Config> Module<T> {
             Error::<T>::SenderHaveNoToken
         );
 
-        let old_freezed_amount = Self::freezed_balance_of((sender.clone(), token_id.clone()));
-        ensure!(old_freezed_amount >= amount, Error::<T>::BalanceNotEnough);
+        let freezed_amount = Self::freezed_balance_of((sender.clone(), token_id.clone()))
+            .checked_sub(&amount)
+            .ok_or(Error::<T>::BalanceNotEnough)?;
+        let free_amount = Self::free_balance_of((sender.clone(), token_id.clone()))
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+
+        FreeBalances::<T>::insert((sender.clone(), token_id.clone()), free_amount);
+        FreezedBalances::<T>::insert((sender.clone(), token_id.clone()), freezed_amount);
+
+        Self::deposit_event(RawEvent::UnFreezed(sender, token_id, amount));
 
-        let old_free_amount = Self::free_balance_of((sender.clone(), token_id.clone()));
-        // ensure!(
-        // 	old_free_amount + amount <= T::Balance::max_value(),
-        // 	Error::<T>::AmountOverflow
-        // );
+        Ok(())
+    }
 
-        FreeBalances::<T>::insert((sender.clone(), token_id.clone()), old_free_amount + amount);
-        FreezedBalances::<T>::insert(
-            (sender.clone(), token_id.clone()),
-            old_freezed_amount - amount,
+    fn _burn(sender: T::AccountId, token_id: Did, amount: T::Balance) -> DispatchResult {
+        let mut token = Self::token(&token_id).ok_or(Error::<T>::NoMatchingToken)?;
+
+        Self::_ensure_free_balance(sender.clone(), token_id.clone(), amount)?;
+
+        let balance = Self::balance_of((sender.clone(), token_id.clone()))
+            .checked_sub(&amount)
+            .ok_or(Error::<T>::BalanceNotEnough)?;
+        let free_balance = Self::free_balance_of((sender.clone(), token_id.clone()))
+            .checked_sub(&amount)
+            .ok_or(Error::<T>::BalanceNotEnough)?;
+        token.total_supply = token
+            .total_supply
+            .checked_sub(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+
+        Balances::<T>::insert((sender.clone(), token_id.clone()), balance);
+        FreeBalances::<T>::insert((sender.clone(), token_id.clone()), free_balance);
+        Tokens::<T>::insert(token_id.clone(), token);
+
+        Self::deposit_event(RawEvent::Burned(sender, token_id, amount));
+
+        Ok(())
+    }
+
+    fn _destroy(sender: T::AccountId, token_id: Did) -> DispatchResult {
+        let token = Self::token(&token_id).ok_or(Error::<T>::NoMatchingToken)?;
+        ensure!(token.owner == sender, Error::<T>::NoPermission);
+        ensure!(token.total_supply.is_zero(), Error::<T>::TokenInUse);
+
+        Self::_retire_token(&token_id, &token.owner, &token.symbol);
+
+        Self::deposit_event(RawEvent::Destroyed(sender, token_id));
+
+        Ok(())
+    }
+
+    /// Drops the token, its symbol and whatever still points at it and returns the deposits
+    fn _retire_token(token_id: &Did, owner: &T::AccountId, symbol: &[u8]) {
+        let allowances: Vec<_> = Allowances::<T>::iter()
+            .map(|(key, _)| key)
+            .filter(|(_, _, id)| id == token_id)
+            .collect();
+        for key in allowances {
+            Allowances::<T>::remove(key);
+        }
+        let frozen: Vec<_> = FrozenAccounts::<T>::iter()
+            .map(|(key, _)| key)
+            .filter(|(_, id)| id == token_id)
+            .collect();
+        for key in frozen {
+            FrozenAccounts::<T>::remove(key);
+        }
+        Self::_remove_owned_token(owner, token_id);
+
+        Self::_release_deposit(TokenDeposits::<T>::take(token_id));
+        Self::_release_deposit(MetadataDeposits::<T>::take(token_id));
+        SymbolToToken::remove(symbol.to_ascii_uppercase());
+        Tokens::<T>::remove(token_id);
+        TokenMetadatas::remove(token_id);
+        TokenRoleInfos::<T>::remove(token_id);
+        SupplyCaps::<T>::remove(token_id);
+    }
+
+    /// Swaps the last owned token into the freed slot to keep the indexes dense
+    fn _remove_owned_token(owner: &T::AccountId, token_id: &Did) {
+        let count = Self::owned_token_index(owner);
+        let index = (0..count)
+            .find(|index| Self::owned_token((owner.clone(), *index)).as_ref() == Some(token_id));
+        if let Some(index) = index {
+            let last = count - 1;
+            if index != last {
+                if let Some(moved) = OwnedTokens::<T>::take((owner.clone(), last)) {
+                    OwnedTokens::<T>::insert((owner.clone(), index), moved);
+                }
+            } else {
+                OwnedTokens::<T>::remove((owner.clone(), index));
+            }
+            OwnedTokensIndex::<T>::insert(owner, last);
+        }
+    }
+
+    fn _release_deposit(deposit: Option<StorageDeposit<T::AccountId, BalanceOf<T>>>) {
+        if let Some(deposit) = deposit {
+            T::Currency::unreserve(&deposit.depositor, deposit.amount);
+        }
+    }
+
+    fn _approve(
+        owner: T::AccountId,
+        spender: T::AccountId,
+        token_id: Did,
+        amount: T::Balance,
+    ) -> DispatchResult {
+        ensure!(
+            Tokens::<T>::contains_key(&token_id),
+            Error::<T>::NoMatchingToken
         );
 
-        Self::deposit_event(RawEvent::UnFreezed(sender, token_id, amount));
+        if amount.is_zero() {
+            Allowances::<T>::remove((owner.clone(), spender.clone(), token_id.clone()));
+        } else {
+            Allowances::<T>::insert((owner.clone(), spender.clone(), token_id.clone()), amount);
+        }
+        Self::deposit_event(RawEvent::Approval(owner, spender, token_id, amount));
+
+        Ok(())
+    }
+
+    #[transactional]
+    fn _transfer_from(
+        spender: T::AccountId,
+        from: T::AccountId,
+        to: T::AccountId,
+        token_id: Did,
+        amount: T::Balance,
+    ) -> DispatchResult {
+        let allowance = Self::allowance((from.clone(), spender.clone(), token_id.clone()))
+            .checked_sub(&amount)
+            .ok_or(Error::<T>::AllowanceNotEnough)?;
+
+        Self::_approve(from.clone(), spender.clone(), token_id.clone(), allowance)?;
+        Self::_transfer(from.clone(), to.clone(), token_id.clone(), amount, None)?;
+        Self::deposit_event(RawEvent::TransferdFrom(spender, from, to, token_id, amount));
+
+        Ok(())
+    }
+
+    /// Roles not set by the owner fall back to the owner
+    fn _roles(token_id: &Did) -> Result<TokenRoles<T::AccountId>, DispatchError> {
+        let token = Self::token(token_id).ok_or(Error::<T>::NoMatchingToken)?;
+        Ok(Self::token_role_infos(token_id).unwrap_or(TokenRoles {
+            minter: token.owner.clone(),
+            burner: token.owner.clone(),
+            freezer: token.owner,
+        }))
+    }
+
+    fn _set_token_roles(
+        sender: T::AccountId,
+        token_id: Did,
+        minter: T::AccountId,
+        burner: T::AccountId,
+        freezer: T::AccountId,
+    ) -> DispatchResult {
+        let token = Self::token(&token_id).ok_or(Error::<T>::NoMatchingToken)?;
+        ensure!(token.owner == sender, Error::<T>::NoPermission);
+
+        TokenRoleInfos::<T>::insert(
+            token_id.clone(),
+            TokenRoles {
+                minter: minter.clone(),
+                burner: burner.clone(),
+                freezer: freezer.clone(),
+            },
+        );
+        Self::deposit_event(RawEvent::SetTokenRoles(token_id, minter, burner, freezer));
+
+        Ok(())
+    }
+
+    fn _set_supply_cap(
+        sender: T::AccountId,
+        token_id: Did,
+        cap: Option<T::Balance>,
+    ) -> DispatchResult {
+        let token = Self::token(&token_id).ok_or(Error::<T>::NoMatchingToken)?;
+        ensure!(token.owner == sender, Error::<T>::NoPermission);
+        if let Some(cap) = cap {
+            ensure!(cap >= token.total_supply, Error::<T>::InvalidSupplyCap);
+        }
+
+        match cap {
+            Some(cap) => SupplyCaps::<T>::insert(token_id.clone(), cap),
+            None => SupplyCaps::<T>::remove(&token_id),
+        }
+        Self::deposit_event(RawEvent::SetSupplyCap(token_id, cap));
+
+        Ok(())
+    }
+
+    fn _mint(
+        sender: T::AccountId,
+        token_id: Did,
+        to: T::AccountId,
+        amount: T::Balance,
+    ) -> DispatchResult {
+        let mut token = Self::token(&token_id).ok_or(Error::<T>::NoMatchingToken)?;
+        ensure!(
+            Self::_roles(&token_id)?.minter == sender,
+            Error::<T>::NoPermission
+        );
+
+        token.total_supply = token
+            .total_supply
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+        if let Some(cap) = Self::supply_cap(&token_id) {
+            ensure!(token.total_supply <= cap, Error::<T>::ExceedSupplyCap);
+        }
+        let balance = Self::balance_of((to.clone(), token_id.clone()))
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+        let free_balance = Self::free_balance_of((to.clone(), token_id.clone()))
+            .checked_add(&amount)
+            .ok_or(Error::<T>::AmountOverflow)?;
+
+        Balances::<T>::insert((to.clone(), token_id.clone()), balance);
+        FreeBalances::<T>::insert((to.clone(), token_id.clone()), free_balance);
+        Tokens::<T>::insert(token_id.clone(), token);
+
+        Self::deposit_event(RawEvent::Minted(to, token_id, amount));
+
+        Ok(())
+    }
+
+    /// The owner pays `DepositPerByte` for the name and logo URI
+    #[transactional]
+    fn _set_token_metadata(
+        sender: T::AccountId,
+        token_id: Did,
+        name: Vec<u8>,
+        decimals: u8,
+        logo_uri: Option<Vec<u8>>,
+    ) -> DispatchResult {
+        let token = Self::token(&token_id).ok_or(Error::<T>::NoMatchingToken)?;
+        ensure!(token.owner == sender, Error::<T>::NoPermission);
+        ensure!(
+            name.len() <= T::NameLimit::get() as usize,
+            Error::<T>::NameTooLong
+        );
+        let uri_len = logo_uri.as_ref().map_or(0, |uri| uri.len());
+        ensure!(
+            uri_len <= T::UriLimit::get() as usize,
+            Error::<T>::UriTooLong
+        );
+
+        Self::_release_deposit(MetadataDeposits::<T>::take(&token_id));
+        let deposit =
+            T::DepositPerByte::get().saturating_mul(((name.len() + uri_len) as u32).into());
+        T::Currency::reserve(&sender, deposit)?;
+
+        MetadataDeposits::<T>::insert(
+            token_id.clone(),
+            StorageDeposit {
+                depositor: sender,
+                amount: deposit,
+            },
+        );
+        TokenMetadatas::insert(
+            token_id.clone(),
+            TokenMetadata {
+                name,
+                symbol: token.symbol,
+                decimals,
+                logo_uri,
+                verified: false,
+            },
+        );
+        Self::deposit_event(RawEvent::SetTokenMetadata(token_id));
 
         Ok(())
     }
 
-    fn _ensure_free_balance(sender: T::AccountId, token_id: Did, amount: u64) -> DispatchResult {
+    fn _ensure_free_balance(
+        sender: T::AccountId,
+        token_id: Did,
+        amount: T::Balance,
+    ) -> DispatchResult {
         let token = Self::token(&token_id);
         ensure!(token.is_some(), Error::<T>::NoMatchingToken);
 
@@ -297,18 +790,41 @@ impl<T: Config> Module<T> {
     }
 }
 
-impl<T: Config> TokenManager<T::AccountId> for Module<T> {
+// RPC
+impl<T: Config> Module<T> {
+    /// Symbols match case-insensitively
+    pub fn token_by_symbol(symbol: Vec<u8>) -> Option<(Did, TokenMetadata)> {
+        let token_id = Self::symbol_to_token(symbol.to_ascii_uppercase())?;
+        let metadata = Self::token_metadata(&token_id)?;
+        Some((token_id, metadata))
+    }
+}
+
+impl<T: Config> TokenManager<T::AccountId, T::Balance> for Module<T> {
     // issue
-    fn issue(from: T::AccountId, total_supply: u64, symbol: Vec<u8>) -> Did {
+    fn issue(
+        from: T::AccountId,
+        total_supply: T::Balance,
+        symbol: Vec<u8>,
+    ) -> Result<Did, DispatchError> {
         Self::_issue(from, total_supply, symbol)
     }
 
+    fn issue_capped(
+        owner: T::AccountId,
+        holder: T::AccountId,
+        total_supply: T::Balance,
+        symbol: Vec<u8>,
+    ) -> Result<Did, DispatchError> {
+        Self::_issue_capped(owner, holder, total_supply, symbol)
+    }
+
     // transfer
     fn transfer(
         from: T::AccountId,
         to: T::AccountId,
         token_id: Did,
-        value: u64,
+        value: T::Balance,
         memo: Option<Vec<u8>>,
     ) -> DispatchResult {
         Self::_transfer(from, to, token_id, value, memo)
@@ -317,7 +833,7 @@ impl<T: Config> TokenManager<T::AccountId> for Module<T> {
         from: T::AccountId,
         to: Did,
         token_id: Did,
-        value: u64,
+        value: T::Balance,
     ) -> DispatchResult {
         Self::_static_transfer_in(from, to, token_id, value)
     }
@@ -325,27 +841,56 @@ impl<T: Config> TokenManager<T::AccountId> for Module<T> {
         from: Did,
         to: T::AccountId,
         token_id: Did,
-        value: u64,
+        value: T::Balance,
     ) -> DispatchResult {
         Self::_static_transfer_out(from, to, token_id, value)
     }
 
     // freeze
-    fn freeze(from: T::AccountId, token_id: Did, value: u64) -> DispatchResult {
+    fn freeze(from: T::AccountId, token_id: Did, value: T::Balance) -> DispatchResult {
         Self::_freeze(from, token_id, value)
     }
 
     // unfreeze
-    fn unfreeze(from: T::AccountId, token_id: Did, value: u64) -> DispatchResult {
+    fn unfreeze(from: T::AccountId, token_id: Did, value: T::Balance) -> DispatchResult {
         Self::_unfreeze(from, token_id, value)
     }
 
+    // burn
+    fn burn(from: T::AccountId, token_id: Did, value: T::Balance) -> DispatchResult {
+        Self::_burn(from, token_id, value)
+    }
+
+    // allowance
+    fn approve(
+        owner: T::AccountId,
+        spender: T::AccountId,
+        token_id: Did,
+        value: T::Balance,
+    ) -> DispatchResult {
+        Self::_approve(owner, spender, token_id, value)
+    }
+
+    fn transfer_from(
+        spender: T::AccountId,
+        from: T::AccountId,
+        to: T::AccountId,
+        token_id: Did,
+        value: T::Balance,
+    ) -> DispatchResult {
+        Self::_transfer_from(spender, from, to, token_id, value)
+    }
+
+    fn allowance(owner: T::AccountId, spender: T::AccountId, token_id: Did) -> T::Balance {
+        Self::allowance((owner, spender, token_id))
+    }
+
     // query
-    fn balance_of(from: T::AccountId, token_id: Did) -> u64 {
+    fn balance_of(from: T::AccountId, token_id: Did) -> T::Balance {
         Self::balance_of((from, token_id))
     }
 
-    fn static_balance_of(from: Did, token_id: Did) -> u64 {
+    fn static_balance_of(from: Did, token_id: Did) -> T::Balance {
         Self::static_balance_of((from, token_id))
     }
     fn owner_of(token_id: Did) -> Option<T::AccountId> {
@@ -354,7 +899,14 @@ impl<T: Config> TokenManager<T::AccountId> for Module<T> {
         }
         None
     }
-    fn ensure_free_balance(sender: T::AccountId, token_id: Did, amount: u64) -> DispatchResult {
+    fn total_supply(token_id: Did) -> T::Balance {
+        Self::token(token_id).map_or_else(Zero::zero, |token| token.total_supply)
+    }
+    fn ensure_free_balance(
+        sender: T::AccountId,
+        token_id: Did,
+        amount: T::Balance,
+    ) -> DispatchResult {
         Self::_ensure_free_balance(sender, token_id, amount)
     }
 }
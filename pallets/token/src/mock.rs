@@ -0,0 +1,130 @@
+use crate as pallet_token;
+use sp_core::H256;
+use frame_support::parameter_types;
+use codec::Encode;
+use sp_io::hashing::blake2_256;
+use sp_runtime::{
+	traits::{BlakeTwo256, IdentityLookup}, testing::Header,
+};
+use frame_system as system;
+use utilities::{BufferIndex, CommonManager, DAOManager, Did, ValueStruct};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+		TokenModule: pallet_token::{Module, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+	pub const ExistentialDeposit: u64 = 1;
+	pub const TokenDepositBase: u64 = 10;
+	pub const DepositPerByte: u64 = 1;
+	pub const SymbolLimit: u32 = 8;
+	pub const NameLimit: u32 = 16;
+	pub const UriLimit: u32 = 64;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+impl pallet_token::Config for Test {
+	type Event = Event;
+	type Common = MockCommon;
+	type Balance = u64;
+	type Currency = Balances;
+	type TokenDepositBase = TokenDepositBase;
+	type DepositPerByte = DepositPerByte;
+	type SymbolLimit = SymbolLimit;
+	type NameLimit = NameLimit;
+	type UriLimit = UriLimit;
+	type DAO = MockDAO;
+}
+
+pub const DAO_ACCOUNT: u64 = 100;
+
+/// Only `generate_did` is used by the token pallet
+pub struct MockCommon;
+impl CommonManager<u64> for MockCommon {
+	fn generate_did(from: u64, nonce: u64) -> Did {
+		Did { did: blake2_256(&(from, nonce).encode()) }
+	}
+	fn generate_hash(from: u64, nonce: u64) -> H256 {
+		H256::from(blake2_256(&(from, nonce).encode()))
+	}
+	fn add_to_queue(_id: u32, _integer: u32, _boolean: bool) {}
+	fn add_multiple(_id: u32, _integers: Vec<u32>, _boolean: bool) {}
+	fn pop_from_queue(_id: u32) {}
+	fn get_buffer_range(_id: u32) -> (BufferIndex, BufferIndex) {
+		(0, 0)
+	}
+	fn get_buffer_value(_id: u32, _index: BufferIndex) -> ValueStruct {
+		ValueStruct::default()
+	}
+}
+
+pub struct MockDAO;
+impl DAOManager<u64, u64> for MockDAO {
+	fn get_dao_account() -> u64 {
+		DAO_ACCOUNT
+	}
+	fn get_dao_tax() -> u64 {
+		0
+	}
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+	pallet_balances::GenesisConfig::<Test>{
+		balances: vec![(1, 1000), (2, 1000), (3, 1000)],
+	}.assimilate_storage(&mut t).unwrap();
+
+	let mut t: sp_io::TestExternalities = t.into();
+
+	t.execute_with(|| System::set_block_number(1) );
+	t
+}
@@ -1,108 +1,643 @@
-use crate::{Error, Module, Trait};
-use frame_support::{assert_noop, assert_ok, impl_outer_origin, parameter_types};
-use frame_system as system;
-use sp_core::H256;
-use sp_io::TestExternalities;
-use sp_runtime::{
-	testing::Header,
-	traits::{BlakeTwo256, IdentityLookup},
-	Perbill,
-};
-
-impl_outer_origin! {
-	pub enum Origin for TestRuntime {}
-}
-
-// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
-#[derive(Clone, PartialEq, Eq, Debug)]
-pub struct TestRuntime;
-parameter_types! {
-	pub const BlockHashCount: u64 = 250;
-	pub const MaximumBlockWeight: u32 = 1024;
-	pub const MaximumBlockLength: u32 = 2 * 1024;
-	pub const AvailableBlockRatio: Perbill = Perbill::one();
-}
-impl system::Trait for TestRuntime {
-	type Origin = Origin;
-	type Index = u64;
-	type Call = ();
-	type BlockNumber = u64;
-	type Hash = H256;
-	type Hashing = BlakeTwo256;
-	type AccountId = u64;
-	type Lookup = IdentityLookup<Self::AccountId>;
-	type Header = Header;
-	type Event = ();
-	type BlockHashCount = BlockHashCount;
-	type MaximumBlockWeight = MaximumBlockWeight;
-	type DbWeight = ();
-	type BlockExecutionWeight = ();
-	type ExtrinsicBaseWeight = ();
-	type MaximumBlockLength = MaximumBlockLength;
-	type AvailableBlockRatio = AvailableBlockRatio;
-	type Version = ();
-	type ModuleToIndex = ();
-	type AccountData = ();
-	type OnNewAccount = ();
-	type OnKilledAccount = ();
-}
-
-impl Trait for TestRuntime {
-	type Event = ();
-}
-
-pub type BasicToken = Module<TestRuntime>;
-
-pub struct ExtBuilder;
-
-impl ExtBuilder {
-	pub fn build() -> TestExternalities {
-		let storage = system::GenesisConfig::default()
-			.build_storage::<TestRuntime>()
-			.unwrap();
-		TestExternalities::from(storage)
-	}
+use crate::mock::*;
+use crate::Error;
+use frame_support::{assert_noop, assert_ok};
+use proptest::prelude::*;
+use sp_runtime::DispatchError;
+use utilities::{Did, TokenManager};
+
+const ACCOUNTS: [u64; 3] = [1, 2, 3];
+const VAULTS: [Did; 2] = [Did { did: [7; 32] }, Did { did: [8; 32] }];
+
+fn issue(owner: u64, total_supply: u64) -> Did {
+    assert_ok!(TokenModule::issue(
+        Origin::signed(owner),
+        total_supply,
+        b"DNF".to_vec()
+    ));
+    TokenModule::owned_token((owner, 0)).unwrap()
+}
+
+/// Every holder's balance splits into free and frozen, and all balances add up to the supply
+fn assert_invariants(token_id: &Did, vaults: &[Did]) {
+    let mut total = 0u128;
+    for who in ACCOUNTS.iter() {
+        let balance = TokenModule::balance_of((*who, token_id.clone()));
+        let free = TokenModule::free_balance_of((*who, token_id.clone()));
+        let freezed = TokenModule::freezed_balance_of((*who, token_id.clone()));
+        assert_eq!(balance as u128, free as u128 + freezed as u128);
+        total += balance as u128;
+    }
+    for vault in vaults.iter() {
+        total += TokenModule::static_balance_of((vault.clone(), token_id.clone())) as u128;
+    }
+    assert_eq!(total, TokenModule::total_supply(token_id.clone()) as u128);
+}
+
+fn snapshot(token_id: &Did, vaults: &[Did]) -> Vec<u64> {
+    let mut state = vec![TokenModule::total_supply(token_id.clone())];
+    for who in ACCOUNTS.iter() {
+        state.push(TokenModule::balance_of((*who, token_id.clone())));
+        state.push(TokenModule::free_balance_of((*who, token_id.clone())));
+        state.push(TokenModule::freezed_balance_of((*who, token_id.clone())));
+    }
+    for vault in vaults.iter() {
+        state.push(TokenModule::static_balance_of((
+            vault.clone(),
+            token_id.clone(),
+        )));
+    }
+    state
+}
+
+#[test]
+fn issue_should_work() {
+    new_test_ext().execute_with(|| {
+        let token_id = issue(1, 1_000);
+        let token = TokenModule::token(&token_id).unwrap();
+        assert_eq!(token.owner, 1);
+        assert_eq!(token.total_supply, 1_000);
+        assert_eq!(TokenModule::balance_of((1, token_id.clone())), 1_000);
+        assert_eq!(TokenModule::free_balance_of((1, token_id)), 1_000);
+    });
+}
+
+#[test]
+fn transfer_should_work() {
+    new_test_ext().execute_with(|| {
+        let token_id = issue(1, 1_000);
+        assert_ok!(TokenModule::transfer(
+            Origin::signed(1),
+            2,
+            token_id.clone(),
+            300,
+            None
+        ));
+        assert_eq!(TokenModule::balance_of((1, token_id.clone())), 700);
+        assert_eq!(TokenModule::balance_of((2, token_id.clone())), 300);
+        assert_eq!(TokenModule::free_balance_of((2, token_id.clone())), 300);
+
+        assert_noop!(
+            TokenModule::transfer(Origin::signed(2), 3, token_id.clone(), 301, None),
+            Error::<Test>::BalanceNotEnough
+        );
+
+        assert_ok!(TokenModule::transfer(
+            Origin::signed(2),
+            2,
+            token_id.clone(),
+            300,
+            None
+        ));
+        assert_eq!(TokenModule::balance_of((2, token_id.clone())), 300);
+        assert_invariants(&token_id, &[]);
+    });
+}
+
+#[test]
+fn transfer_from_should_spend_allowance() {
+    new_test_ext().execute_with(|| {
+        let token_id = issue(1, 1_000);
+        assert_noop!(
+            TokenModule::transfer_from(Origin::signed(2), 1, 3, token_id.clone(), 1),
+            Error::<Test>::AllowanceNotEnough
+        );
+
+        assert_ok!(TokenModule::approve(
+            Origin::signed(1),
+            2,
+            token_id.clone(),
+            300
+        ));
+        assert_eq!(TokenModule::allowance((1, 2, token_id.clone())), 300);
+        assert_ok!(TokenModule::increase_allowance(
+            Origin::signed(1),
+            2,
+            token_id.clone(),
+            100
+        ));
+        assert_ok!(TokenModule::decrease_allowance(
+            Origin::signed(1),
+            2,
+            token_id.clone(),
+            50
+        ));
+        assert_eq!(TokenModule::allowance((1, 2, token_id.clone())), 350);
+        assert_noop!(
+            TokenModule::decrease_allowance(Origin::signed(1), 2, token_id.clone(), 351),
+            Error::<Test>::AllowanceNotEnough
+        );
+
+        assert_ok!(TokenModule::transfer_from(
+            Origin::signed(2),
+            1,
+            3,
+            token_id.clone(),
+            200
+        ));
+        assert_eq!(TokenModule::allowance((1, 2, token_id.clone())), 150);
+        assert_eq!(TokenModule::balance_of((1, token_id.clone())), 800);
+        assert_eq!(TokenModule::balance_of((3, token_id.clone())), 200);
+        assert_noop!(
+            TokenModule::transfer_from(Origin::signed(2), 1, 3, token_id.clone(), 151),
+            Error::<Test>::AllowanceNotEnough
+        );
+
+        // the allowance is used up exactly
+        assert_ok!(<TokenModule as TokenManager<u64, u64>>::transfer_from(
+            2,
+            1,
+            2,
+            token_id.clone(),
+            150
+        ));
+        assert_eq!(TokenModule::allowance((1, 2, token_id.clone())), 0);
+        assert_eq!(TokenModule::balance_of((2, token_id.clone())), 150);
+        assert_noop!(
+            TokenModule::transfer_from(Origin::signed(2), 1, 2, token_id.clone(), 1),
+            Error::<Test>::AllowanceNotEnough
+        );
+        assert_invariants(&token_id, &[]);
+    });
+}
+
+#[test]
+fn transfer_from_should_not_exceed_balance() {
+    new_test_ext().execute_with(|| {
+        let token_id = issue(1, 100);
+        assert_ok!(TokenModule::approve(
+            Origin::signed(1),
+            2,
+            token_id.clone(),
+            500
+        ));
+        assert_noop!(
+            TokenModule::transfer_from(Origin::signed(2), 1, 3, token_id.clone(), 101),
+            Error::<Test>::BalanceNotEnough
+        );
+        assert_eq!(TokenModule::allowance((1, 2, token_id)), 500);
+    });
+}
+
+#[test]
+fn freeze_should_move_free_balance() {
+    new_test_ext().execute_with(|| {
+        let token_id = issue(1, 1_000);
+        assert_ok!(<TokenModule as TokenManager<u64, u64>>::freeze(
+            1,
+            token_id.clone(),
+            400
+        ));
+        assert_eq!(TokenModule::free_balance_of((1, token_id.clone())), 600);
+        assert_eq!(TokenModule::freezed_balance_of((1, token_id.clone())), 400);
+
+        assert_noop!(
+            TokenModule::transfer(Origin::signed(1), 2, token_id.clone(), 601, None),
+            Error::<Test>::BalanceNotEnough
+        );
+        assert_noop!(
+            <TokenModule as TokenManager<u64, u64>>::unfreeze(1, token_id.clone(), 401),
+            Error::<Test>::BalanceNotEnough
+        );
+        assert_invariants(&token_id, &[]);
+    });
+}
+
+#[test]
+fn roles_should_guard_mint_burn_and_freeze() {
+    new_test_ext().execute_with(|| {
+        let token_id = issue(1, 1_000);
+        assert_noop!(
+            TokenModule::set_token_roles(Origin::signed(2), token_id.clone(), 2, 2, 2),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(TokenModule::set_token_roles(
+            Origin::signed(1),
+            token_id.clone(),
+            2,
+            3,
+            2
+        ));
+
+        assert_noop!(
+            TokenModule::mint(Origin::signed(1), token_id.clone(), 1, 10),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(TokenModule::mint(
+            Origin::signed(2),
+            token_id.clone(),
+            3,
+            10
+        ));
+        assert_eq!(TokenModule::balance_of((3, token_id.clone())), 10);
+
+        assert_noop!(
+            TokenModule::burn_from(Origin::signed(1), token_id.clone(), 1, 100),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(TokenModule::burn_from(
+            Origin::signed(3),
+            token_id.clone(),
+            1,
+            100
+        ));
+        // holders may always burn their own tokens
+        assert_ok!(TokenModule::burn(Origin::signed(3), token_id.clone(), 10));
+        assert_eq!(TokenModule::token(&token_id).unwrap().total_supply, 900);
+
+        assert_noop!(
+            TokenModule::freeze_account(Origin::signed(1), token_id.clone(), 1),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(TokenModule::freeze_account(
+            Origin::signed(2),
+            token_id.clone(),
+            1
+        ));
+        assert_noop!(
+            TokenModule::transfer(Origin::signed(1), 3, token_id.clone(), 1, None),
+            Error::<Test>::AccountFrozen
+        );
+        assert_noop!(
+            <TokenModule as TokenManager<u64, u64>>::freeze(1, token_id.clone(), 1),
+            Error::<Test>::AccountFrozen
+        );
+        assert_ok!(TokenModule::thaw_account(
+            Origin::signed(2),
+            token_id.clone(),
+            1
+        ));
+        assert_ok!(TokenModule::transfer(
+            Origin::signed(1),
+            3,
+            token_id.clone(),
+            1,
+            None
+        ));
+        assert_invariants(&token_id, &[]);
+    });
 }
 
 #[test]
-fn init_works() {
-	ExtBuilder::build().execute_with(|| {
-		assert_ok!(BasicToken::init(Origin::signed(1)));
-		assert_eq!(BasicToken::get_balance(1), 21000000);
-	})
+fn supply_cap_should_bound_mint() {
+    new_test_ext().execute_with(|| {
+        let token_id = issue(1, 1_000);
+        assert_noop!(
+            TokenModule::set_supply_cap(Origin::signed(1), token_id.clone(), Some(999)),
+            Error::<Test>::InvalidSupplyCap
+        );
+        assert_noop!(
+            TokenModule::set_supply_cap(Origin::signed(2), token_id.clone(), Some(1_010)),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(TokenModule::set_supply_cap(
+            Origin::signed(1),
+            token_id.clone(),
+            Some(1_010)
+        ));
+
+        assert_noop!(
+            TokenModule::mint(Origin::signed(1), token_id.clone(), 2, 11),
+            Error::<Test>::ExceedSupplyCap
+        );
+        assert_ok!(TokenModule::mint(
+            Origin::signed(1),
+            token_id.clone(),
+            2,
+            10
+        ));
+
+        assert_ok!(TokenModule::set_supply_cap(
+            Origin::signed(1),
+            token_id.clone(),
+            None
+        ));
+        assert_eq!(TokenModule::supply_cap(&token_id), None);
+        assert_ok!(TokenModule::mint(
+            Origin::signed(1),
+            token_id.clone(),
+            2,
+            10
+        ));
+        assert_eq!(TokenModule::token(&token_id).unwrap().total_supply, 1_020);
+        assert_invariants(&token_id, &[]);
+    });
 }
 
 #[test]
-fn cant_double_init() {
-	ExtBuilder::build().execute_with(|| {
-		assert_ok!(BasicToken::init(Origin::signed(1)));
-		assert_noop!(
-			BasicToken::init(Origin::signed(1)),
-			Error::<TestRuntime>::AlreadyInitialized
-		);
-	})
+fn issue_capped_should_leave_roles_with_owner() {
+    new_test_ext().execute_with(|| {
+        let token_id =
+            <TokenModule as TokenManager<u64, u64>>::issue_capped(1, 2, 500, b"CAP".to_vec())
+                .unwrap();
+        let token = TokenModule::token(&token_id).unwrap();
+        assert_eq!(token.owner, 1);
+        assert_eq!(token.total_supply, 500);
+        assert_eq!(TokenModule::balance_of((2, token_id.clone())), 500);
+        assert_eq!(TokenModule::supply_cap(&token_id), Some(500));
+        assert_eq!(Balances::reserved_balance(2), 13);
+
+        assert_noop!(
+            TokenModule::mint(Origin::signed(2), token_id.clone(), 2, 1),
+            Error::<Test>::NoPermission
+        );
+        assert_noop!(
+            TokenModule::mint(Origin::signed(1), token_id.clone(), 1, 1),
+            Error::<Test>::ExceedSupplyCap
+        );
+        assert_noop!(
+            TokenModule::freeze_account(Origin::signed(2), token_id.clone(), 2),
+            Error::<Test>::NoPermission
+        );
+    });
 }
 
 #[test]
-fn transfer_works() {
-	ExtBuilder::build().execute_with(|| {
-		assert_ok!(BasicToken::init(Origin::signed(1)));
+fn symbols_should_be_unique_until_retired() {
+    new_test_ext().execute_with(|| {
+        let token_id = issue(1, 1_000);
+        assert_eq!(Balances::reserved_balance(1), 13);
+        assert_noop!(
+            TokenModule::issue(Origin::signed(2), 10, b"dnf".to_vec()),
+            Error::<Test>::SymbolTaken
+        );
+        assert_noop!(
+            TokenModule::issue(Origin::signed(2), 10, b"TOOLONGSYM".to_vec()),
+            Error::<Test>::SymbolTooLong
+        );
+        let (found, metadata) = TokenModule::token_by_symbol(b"dNf".to_vec()).unwrap();
+        assert_eq!(found, token_id);
+        assert_eq!(metadata.symbol, b"DNF".to_vec());
+
+        // burning part of the supply keeps the symbol
+        assert_ok!(TokenModule::burn(Origin::signed(1), token_id.clone(), 400));
+        assert_noop!(
+            TokenModule::issue(Origin::signed(2), 10, b"DNF".to_vec()),
+            Error::<Test>::SymbolTaken
+        );
+
+        assert_noop!(
+            TokenModule::destroy(Origin::signed(1), token_id.clone()),
+            Error::<Test>::TokenInUse
+        );
 
-		// Transfer 100 tokens from user 1 to user 2
-		assert_ok!(BasicToken::transfer(Origin::signed(1), 2, 100));
+        // burning the last unit leaves the token to its owner
+        assert_ok!(TokenModule::burn(Origin::signed(1), token_id.clone(), 600));
+        assert_eq!(TokenModule::token(&token_id).unwrap().total_supply, 0);
+        assert_noop!(
+            TokenModule::issue(Origin::signed(2), 10, b"DNF".to_vec()),
+            Error::<Test>::SymbolTaken
+        );
+        assert_noop!(
+            TokenModule::destroy(Origin::signed(2), token_id.clone()),
+            Error::<Test>::NoPermission
+        );
 
-		assert_eq!(BasicToken::get_balance(1), 20999900);
-		assert_eq!(BasicToken::get_balance(2), 100);
-	})
+        assert_ok!(TokenModule::destroy(Origin::signed(1), token_id.clone()));
+        assert_eq!(TokenModule::token(&token_id), None);
+        assert_eq!(TokenModule::token_metadata(&token_id), None);
+        assert_eq!(TokenModule::token_by_symbol(b"DNF".to_vec()), None);
+        assert_eq!(Balances::reserved_balance(1), 0);
+        assert_noop!(
+            TokenModule::mint(Origin::signed(1), token_id, 1, 10),
+            Error::<Test>::NoMatchingToken
+        );
+
+        assert_ok!(TokenModule::issue(Origin::signed(2), 10, b"dnf".to_vec()));
+        let reissued = TokenModule::owned_token((2, 0)).unwrap();
+        assert_eq!(
+            TokenModule::token_by_symbol(b"DNF".to_vec()).unwrap().0,
+            reissued
+        );
+    });
 }
 
 #[test]
-fn cant_spend_more_than_you_have() {
-	ExtBuilder::build().execute_with(|| {
-		assert_ok!(BasicToken::init(Origin::signed(1)));
-		assert_noop!(
-			BasicToken::transfer(Origin::signed(1), 2, 21000001),
-			Error::<TestRuntime>::InsufficientFunds
-		);
-	})
+fn destroy_should_clear_token_references() {
+    new_test_ext().execute_with(|| {
+        let token_id = issue(1, 1_000);
+        assert_ok!(TokenModule::issue(Origin::signed(1), 10, b"KEEP".to_vec()));
+        let kept = TokenModule::owned_token((1, 1)).unwrap();
+        assert_ok!(TokenModule::approve(
+            Origin::signed(1),
+            2,
+            token_id.clone(),
+            50
+        ));
+        assert_ok!(TokenModule::approve(Origin::signed(1), 2, kept.clone(), 5));
+        assert_ok!(TokenModule::freeze_account(
+            Origin::signed(1),
+            token_id.clone(),
+            3
+        ));
+
+        assert_ok!(TokenModule::burn(
+            Origin::signed(1),
+            token_id.clone(),
+            1_000
+        ));
+        assert_ok!(TokenModule::destroy(Origin::signed(1), token_id.clone()));
+
+        assert_eq!(TokenModule::allowance((1, 2, token_id.clone())), 0);
+        assert!(!TokenModule::is_account_frozen((3, token_id)));
+        assert_eq!(TokenModule::owned_token_index(1), 1);
+        assert_eq!(TokenModule::owned_token((1, 0)), Some(kept.clone()));
+        assert_eq!(TokenModule::owned_token((1, 1)), None);
+        // other tokens keep their state
+        assert_eq!(TokenModule::allowance((1, 2, kept)), 5);
+    });
+}
+
+#[test]
+fn set_token_verified_should_need_dao() {
+    new_test_ext().execute_with(|| {
+        let token_id = issue(1, 1_000);
+        assert_ok!(TokenModule::set_token_metadata(
+            Origin::signed(1),
+            token_id.clone(),
+            b"Dnft".to_vec(),
+            12,
+            None
+        ));
+        assert_eq!(Balances::reserved_balance(1), 17);
+
+        assert_noop!(
+            TokenModule::set_token_verified(Origin::signed(1), token_id.clone(), true),
+            Error::<Test>::NoPermission
+        );
+        assert_noop!(
+            TokenModule::set_token_verified(
+                Origin::signed(DAO_ACCOUNT),
+                Did { did: [9; 32] },
+                true
+            ),
+            Error::<Test>::NoMatchingToken
+        );
+        assert_ok!(TokenModule::set_token_verified(
+            Origin::signed(DAO_ACCOUNT),
+            token_id.clone(),
+            true
+        ));
+        let (_, metadata) = TokenModule::token_by_symbol(b"DNF".to_vec()).unwrap();
+        assert!(metadata.verified);
+        assert_eq!(metadata.name, b"Dnft".to_vec());
+        assert_eq!(metadata.decimals, 12);
+
+        // new metadata needs a new verification
+        assert_ok!(TokenModule::set_token_metadata(
+            Origin::signed(1),
+            token_id.clone(),
+            b"Dnft2".to_vec(),
+            12,
+            None
+        ));
+        assert!(!TokenModule::token_metadata(&token_id).unwrap().verified);
+        assert_eq!(Balances::reserved_balance(1), 18);
+    });
+}
+
+#[test]
+fn overflow_should_fail() {
+    new_test_ext().execute_with(|| {
+        let token_id = issue(1, u64::max_value() - 10);
+        assert_noop!(
+            TokenModule::mint(Origin::signed(1), token_id.clone(), 2, 11),
+            Error::<Test>::AmountOverflow
+        );
+        assert_ok!(TokenModule::mint(
+            Origin::signed(1),
+            token_id.clone(),
+            2,
+            10
+        ));
+
+        assert_ok!(TokenModule::approve(
+            Origin::signed(1),
+            3,
+            token_id.clone(),
+            u64::max_value()
+        ));
+        assert_noop!(
+            TokenModule::increase_allowance(Origin::signed(1), 3, token_id.clone(), 1),
+            Error::<Test>::AmountOverflow
+        );
+        assert_invariants(&token_id, &[]);
+    });
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    Transfer(u64, u64, u64),
+    Approve(u64, u64, u64),
+    TransferFrom(u64, u64, u64, u64),
+    Freeze(u64, u64),
+    Unfreeze(u64, u64),
+    Mint(u64, u64),
+    Burn(u64, u64),
+    StaticIn(u64, usize, u64),
+    StaticOut(usize, u64, u64),
+}
+
+fn account() -> impl Strategy<Value = u64> {
+    prop::sample::select(ACCOUNTS.to_vec())
+}
+
+fn vault() -> impl Strategy<Value = usize> {
+    0..VAULTS.len()
+}
+
+/// Mostly small amounts, sometimes ones that can only overflow
+fn amount() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        8 => 0..2_000u64,
+        1 => Just(0u64),
+        1 => (0..1_000u64).prop_map(|d| u64::max_value() - d),
+    ]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (account(), account(), amount()).prop_map(|(from, to, a)| Op::Transfer(from, to, a)),
+        (account(), account(), amount())
+            .prop_map(|(owner, spender, a)| Op::Approve(owner, spender, a)),
+        (account(), account(), account(), amount())
+            .prop_map(|(spender, from, to, a)| Op::TransferFrom(spender, from, to, a)),
+        (account(), amount()).prop_map(|(who, a)| Op::Freeze(who, a)),
+        (account(), amount()).prop_map(|(who, a)| Op::Unfreeze(who, a)),
+        (account(), amount()).prop_map(|(who, a)| Op::Mint(who, a)),
+        (account(), amount()).prop_map(|(who, a)| Op::Burn(who, a)),
+        (account(), vault(), amount()).prop_map(|(who, v, a)| Op::StaticIn(who, v, a)),
+        (vault(), account(), amount()).prop_map(|(v, who, a)| Op::StaticOut(v, who, a)),
+    ]
+}
+
+/// Runs `op` and returns how much it moved the total supply by
+fn apply(token_id: &Did, op: Op) -> Result<i128, DispatchError> {
+    let token_id = token_id.clone();
+    match op {
+        Op::Transfer(from, to, a) => {
+            TokenModule::transfer(Origin::signed(from), to, token_id, a, None)?
+        }
+        Op::Approve(owner, spender, a) => {
+            TokenModule::approve(Origin::signed(owner), spender, token_id, a)?
+        }
+        Op::TransferFrom(spender, from, to, a) => {
+            let allowance = TokenModule::allowance((from, spender, token_id.clone()));
+            TokenModule::transfer_from(Origin::signed(spender), from, to, token_id.clone(), a)?;
+            assert_eq!(
+                TokenModule::allowance((from, spender, token_id)),
+                allowance - a
+            );
+        }
+        Op::Freeze(who, a) => <TokenModule as TokenManager<u64, u64>>::freeze(who, token_id, a)?,
+        Op::Unfreeze(who, a) => {
+            <TokenModule as TokenManager<u64, u64>>::unfreeze(who, token_id, a)?
+        }
+        Op::Mint(who, a) => {
+            TokenModule::mint(Origin::signed(1), token_id, who, a)?;
+            return Ok(a as i128);
+        }
+        Op::Burn(who, a) => {
+            TokenModule::burn(Origin::signed(who), token_id, a)?;
+            return Ok(-(a as i128));
+        }
+        Op::StaticIn(who, v, a) => <TokenModule as TokenManager<u64, u64>>::static_transfer_in(
+            who,
+            VAULTS[v].clone(),
+            token_id,
+            a,
+        )?,
+        Op::StaticOut(v, who, a) => <TokenModule as TokenManager<u64, u64>>::static_transfer_out(
+            VAULTS[v].clone(),
+            who,
+            token_id,
+            a,
+        )?,
+    }
+    Ok(0)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// Failed calls change nothing; the supply only moves by what was minted or burned
+    #[test]
+    fn random_operations_should_keep_balances_consistent(
+        ops in prop::collection::vec(op(), 1..200)
+    ) {
+        new_test_ext().execute_with(|| {
+            let token_id = issue(1, 10_000);
+
+            for op in ops {
+                let before = snapshot(&token_id, &VAULTS);
+                match apply(&token_id, op) {
+                    Ok(delta) => assert_eq!(
+                        TokenModule::total_supply(token_id.clone()) as i128,
+                        before[0] as i128 + delta
+                    ),
+                    Err(_) => assert_eq!(before, snapshot(&token_id, &VAULTS)),
+                }
+                assert_invariants(&token_id, &VAULTS);
+            }
+        });
+    }
 }
@@ -2,10 +2,14 @@
 #![allow(clippy::string_lit_as_bytes)]
 
 use frame_support::{
-    decl_error, decl_event, decl_module, decl_storage, dispatch, ensure, StorageMap, StorageValue,
+    decl_error, decl_event, decl_module, decl_storage, dispatch, ensure, Parameter, StorageMap,
+    StorageValue,
 };
 use frame_system::ensure_signed;
-use sp_runtime::DispatchResult;
+use sp_runtime::{
+    traits::{AtLeast32BitUnsigned, Member},
+    DispatchResult,
+};
 use sp_std::{
     cmp::{Eq, PartialEq},
     prelude::*,
@@ -15,7 +19,9 @@ use utilities::{CommonManager, Did, TokenManager, TradeMethod, TradePair, TradeP
 
 pub trait Config: frame_system::Config {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
-    type Token: TokenManager<Self::AccountId>;
+    /// Matches the `Token` balance type
+    type Balance: Parameter + Member + AtLeast32BitUnsigned + Default + Copy;
+    type Token: TokenManager<Self::AccountId, Self::Balance>;
     type Common: CommonManager<Self::AccountId>;
 }
 
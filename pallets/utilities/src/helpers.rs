@@ -0,0 +1,320 @@
+//! Logic shared by the NFT and swap pallets. Storage stays in each pallet; these only take
+//! the values they need and hand back what the pallet should record or emit.
+
+use codec::{Decode, Encode};
+use frame_support::{
+    ensure,
+    traits::{BalanceStatus, ExistenceRequirement, ReservableCurrency},
+};
+use sp_io::hashing::blake2_256;
+use sp_runtime::{
+    traits::{SaturatedConversion, Saturating, UniqueSaturatedFrom, UniqueSaturatedInto, Zero},
+    DispatchError, DispatchResult, RuntimeDebug,
+};
+use sp_std::prelude::*;
+
+use crate::{
+    Attribute, AttributeValue, MintDrop, MultiNonFungibleManager, MysteryBox, NFTId, NFTType,
+    NFTUser, RentOffer, RoyaltyInfo, StorageDeposit,
+};
+
+/// Where a sale takes the buyer's funds from
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum PaymentSource {
+    /// Free balance, keeping the buyer alive
+    Free,
+    /// Funds the buyer reserved up front
+    Reserved,
+}
+
+/// Splits `price` between the royalty beneficiary and `seller`.
+/// Returns the royalty paid so the calling pallet can emit its own event.
+pub fn pay_with_royalty<AccountId, C>(
+    source: PaymentSource,
+    buyer: &AccountId,
+    seller: &AccountId,
+    price: C::Balance,
+    royalty: Option<RoyaltyInfo<AccountId>>,
+) -> Result<Option<(AccountId, C::Balance)>, DispatchError>
+where
+    AccountId: PartialEq,
+    C: ReservableCurrency<AccountId>,
+{
+    let pay = |to: &AccountId, amount: C::Balance| -> DispatchResult {
+        match source {
+            PaymentSource::Free => C::transfer(buyer, to, amount, ExistenceRequirement::KeepAlive),
+            PaymentSource::Reserved => {
+                let missing = C::repatriate_reserved(buyer, to, amount, BalanceStatus::Free)?;
+                ensure!(
+                    missing.is_zero(),
+                    DispatchError::Other("InsufficientReserve")
+                );
+                Ok(())
+            }
+        }
+    };
+
+    let mut paid = None;
+    let mut seller_amount = price;
+    if let Some(royalty) = royalty {
+        let royalty_amount = royalty.rate * price;
+        if royalty.beneficiary != *seller && !royalty_amount.is_zero() {
+            pay(&royalty.beneficiary, royalty_amount)?;
+            seller_amount = price.saturating_sub(royalty_amount);
+            paid = Some((royalty.beneficiary, royalty_amount));
+        }
+    }
+    pay(seller, seller_amount)?;
+
+    Ok(paid)
+}
+
+/// `base` plus `per_byte` for every stored byte, reserved from `who`
+pub fn reserve_deposit<AccountId, C>(
+    who: &AccountId,
+    base: C::Balance,
+    per_byte: C::Balance,
+    bytes: usize,
+) -> Result<StorageDeposit<AccountId, C::Balance>, DispatchError>
+where
+    AccountId: Clone,
+    C: ReservableCurrency<AccountId>,
+{
+    let amount = per_byte
+        .saturating_mul((bytes as u32).into())
+        .saturating_add(base);
+    C::reserve(who, amount)?;
+
+    Ok(StorageDeposit {
+        depositor: who.clone(),
+        amount,
+    })
+}
+
+pub fn release_deposit<AccountId, C>(deposit: Option<StorageDeposit<AccountId, C::Balance>>)
+where
+    C: ReservableCurrency<AccountId>,
+{
+    if let Some(deposit) = deposit {
+        C::unreserve(&deposit.depositor, deposit.amount);
+    }
+}
+
+/// Releases the deposit of the attribute being overwritten and reserves one for `key_len`
+/// plus the encoded value
+pub fn reserve_attribute<AccountId, C>(
+    who: AccountId,
+    value: AttributeValue,
+    key_len: usize,
+    base: C::Balance,
+    per_byte: C::Balance,
+    old: Option<Attribute<AccountId, C::Balance>>,
+) -> Result<Attribute<AccountId, C::Balance>, DispatchError>
+where
+    C: ReservableCurrency<AccountId>,
+{
+    let bytes: C::Balance = ((key_len + value.encoded_size()) as u32).into();
+    let deposit = per_byte.saturating_mul(bytes).saturating_add(base);
+    if let Some(old) = old {
+        C::unreserve(&old.depositor, old.deposit);
+    }
+    C::reserve(&who, deposit)?;
+
+    Ok(Attribute {
+        value,
+        depositor: who,
+        deposit,
+    })
+}
+
+/// Why a public mint was turned away
+#[derive(Copy, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum DropError {
+    NotOpen,
+    NotInAllowlist,
+    LimitReached,
+}
+
+/// Checks the window, the allowlist proof and the per-account cap for `amount` more mints.
+/// Returns the account's new mint count.
+pub fn check_drop<AccountId, Balance, BlockNumber>(
+    drop: &MintDrop<Balance, BlockNumber>,
+    now: &BlockNumber,
+    who: &AccountId,
+    proof: &[[u8; 32]],
+    minted: u32,
+    amount: u32,
+) -> Result<u32, DropError>
+where
+    AccountId: Encode,
+    BlockNumber: PartialOrd,
+{
+    ensure!(drop.is_open(now), DropError::NotOpen);
+    if let Some(root) = drop.allowlist_root {
+        ensure!(
+            verify_allowlist(root, who, proof),
+            DropError::NotInAllowlist
+        );
+    }
+    minted
+        .checked_add(amount)
+        .filter(|minted| *minted <= drop.per_account_limit)
+        .ok_or(DropError::LimitReached)
+}
+
+/// Folds `proof` over the `blake2_256(who)` leaf, hashing each pair in sorted order
+pub fn verify_allowlist<AccountId: Encode>(
+    root: [u8; 32],
+    who: &AccountId,
+    proof: &[[u8; 32]],
+) -> bool {
+    let leaf = blake2_256(&who.encode());
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            blake2_256(&[node, *sibling].concat())
+        } else {
+            blake2_256(&[*sibling, node].concat())
+        }
+    });
+    computed == root
+}
+
+/// Why a mystery box can not be revealed
+#[derive(Copy, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum RevealError {
+    Revealed,
+    TooEarly,
+    HashMismatch,
+    LengthMismatch,
+    NotEnoughMetadata,
+}
+
+/// Checks `metadata` against the committed hash and length and that it covers the `minted` NFTs
+pub fn check_reveal<BlockNumber: PartialOrd>(
+    mystery_box: &MysteryBox<BlockNumber>,
+    now: &BlockNumber,
+    metadata: &[Vec<u8>],
+    minted: u64,
+) -> Result<(), RevealError> {
+    ensure!(!mystery_box.revealed, RevealError::Revealed);
+    ensure!(*now >= mystery_box.reveal_block, RevealError::TooEarly);
+    ensure!(
+        blake2_256(&metadata.encode()) == mystery_box.metadata_hash,
+        RevealError::HashMismatch
+    );
+    ensure!(
+        metadata.len() as u64 == mystery_box.metadata_len,
+        RevealError::LengthMismatch
+    );
+    ensure!(
+        metadata.len() as u64 >= minted,
+        RevealError::NotEnoughMetadata
+    );
+    Ok(())
+}
+
+/// Moves a `seed`-picked entry into each of the first `count` slots; `count` must not exceed
+/// `metadata.len()`
+pub fn shuffle_metadata<Seed: Encode>(seed: &Seed, metadata: &mut [Vec<u8>], count: u64) {
+    for i in 0..count {
+        let random = blake2_256(&(seed, i).encode());
+        let offset =
+            u64::decode(&mut &random[..]).unwrap_or_default() % (metadata.len() as u64 - i);
+        metadata.swap(i as usize, (i + offset) as usize);
+    }
+}
+
+/// Why an NFT can not be attached under a parent
+#[derive(Copy, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum NestingError {
+    NoPermission,
+    Cycle,
+    TooDeep,
+}
+
+/// Checks that `who` owns `parent` and that attaching `child` keeps the tree acyclic and at
+/// most `max_depth` deep
+pub fn check_attach<AccountId, Balance, M>(
+    who: &AccountId,
+    child: &(NFTType, NFTId),
+    parent: &(NFTType, NFTId),
+    max_depth: u32,
+) -> Result<(), NestingError>
+where
+    AccountId: PartialEq,
+    M: MultiNonFungibleManager<AccountId, Balance>,
+{
+    ensure!(
+        M::owner_of(parent.0, parent.1.clone()).as_ref() == Some(who),
+        NestingError::NoPermission
+    );
+    let lineage = M::lineage_of(parent.0, parent.1.clone());
+    ensure!(!lineage.contains(child), NestingError::Cycle);
+    let depth = (lineage.len() as u32).saturating_add(M::height_of(child.0, child.1.clone()));
+    ensure!(depth <= max_depth, NestingError::TooDeep);
+    Ok(())
+}
+
+/// Each child's own transfer carries its subtree along
+pub fn move_children<AccountId, Balance, M>(
+    children: Vec<(NFTType, NFTId)>,
+    from: &AccountId,
+    to: &AccountId,
+) -> DispatchResult
+where
+    AccountId: Clone,
+    M: MultiNonFungibleManager<AccountId, Balance>,
+{
+    for (child_type, child_id) in children {
+        M::unlock_nft(child_type, child_id.clone())?;
+        M::transfer_single_nft(child_type, from.clone(), to.clone(), child_id.clone())?;
+        M::lock_nft(child_type, child_id)?;
+    }
+
+    Ok(())
+}
+
+/// Why a rental can not start
+#[derive(Copy, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum RentalError {
+    InvalidDuration,
+    TooManyExpiries,
+}
+
+/// The user of an NFT while its rental has not expired
+pub fn active_user<AccountId, BlockNumber: PartialOrd>(
+    user: Option<NFTUser<AccountId, BlockNumber>>,
+    now: &BlockNumber,
+) -> Option<AccountId> {
+    user.filter(|info| info.expiry > *now).map(|info| info.user)
+}
+
+/// Queues `nft_id` in the expiries of one block, at most `max` per block
+pub fn schedule_expiry(
+    nft_ids: &mut Vec<NFTId>,
+    nft_id: &NFTId,
+    max: u32,
+) -> Result<(), RentalError> {
+    if !nft_ids.contains(nft_id) {
+        ensure!((nft_ids.len() as u32) < max, RentalError::TooManyExpiries);
+        nft_ids.push(nft_id.clone());
+    }
+    Ok(())
+}
+
+/// Price of renting for `duration` blocks under `offer`
+pub fn rent_cost<Balance, BlockNumber>(
+    offer: &RentOffer<Balance, BlockNumber>,
+    duration: BlockNumber,
+) -> Result<Balance, RentalError>
+where
+    Balance: Copy + Saturating + UniqueSaturatedFrom<u128>,
+    BlockNumber: PartialOrd + Zero + UniqueSaturatedInto<u128>,
+{
+    ensure!(
+        !duration.is_zero() && duration <= offer.max_duration,
+        RentalError::InvalidDuration
+    );
+    let blocks: Balance = duration.saturated_into::<u128>().saturated_into();
+    Ok(offer.price_per_block.saturating_mul(blocks))
+}
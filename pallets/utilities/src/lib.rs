@@ -5,13 +5,16 @@
 use frame_support::dispatch;
 use sp_std::{
     cmp::{Eq, PartialEq},
+    marker::PhantomData,
     ops::Not,
     prelude::*,
 };
 
 use codec::{Decode, Encode};
 use sp_core::H256;
-use sp_runtime::{DispatchResult, RuntimeDebug};
+use sp_runtime::{DispatchResult, Perbill, RuntimeDebug};
+
+pub mod helpers;
 
 #[derive(Encode, Decode, Default, PartialOrd, Ord, PartialEq, Eq, Clone, RuntimeDebug)]
 pub struct Did {
@@ -53,18 +56,96 @@ pub struct AuctionId {
     pub did: [u8; 32],
 }
 
+#[derive(Encode, Decode, Default, PartialOrd, Ord, PartialEq, Eq, Clone, RuntimeDebug)]
+pub struct BundleId {
+    pub did: [u8; 32],
+}
+
 #[derive(Encode, Decode, Default, PartialOrd, Ord, PartialEq, Eq, Clone, RuntimeDebug)]
 pub struct ProposalId {
     pub did: [u8; 32],
 }
 
+/// Storage layout version of a pallet, checked by `on_runtime_upgrade`
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+    V1 = 0,
+    V2,
+    V3,
+}
+
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1
+    }
+}
+
 /// NFT Class
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum TransferPolicy {
+    Transferable = 0,
+    /// Never changes hands once minted
+    Soulbound,
+    /// Only moves to or from the class issuer
+    IssuerOnly,
+}
+
 #[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
 pub struct ClassInfo<AccountId> {
     pub name: Vec<u8>,
     pub info: Vec<u8>,
     pub total_supply: u64,
     pub issuer: AccountId,
+    pub transfer_policy: TransferPolicy,
+}
+
+/// `ClassInfo` layout before `transfer_policy`, decoded by the `Releases::V2` migrations
+#[derive(Decode)]
+pub struct ClassInfoV1<AccountId> {
+    pub name: Vec<u8>,
+    pub info: Vec<u8>,
+    pub total_supply: u64,
+    pub issuer: AccountId,
+}
+
+impl<AccountId> From<ClassInfoV1<AccountId>> for ClassInfo<AccountId> {
+    fn from(old: ClassInfoV1<AccountId>) -> Self {
+        ClassInfo {
+            name: old.name,
+            info: old.info,
+            total_supply: old.total_supply,
+            issuer: old.issuer,
+            transfer_policy: TransferPolicy::Transferable,
+        }
+    }
+}
+
+impl<AccountId: PartialEq> ClassInfo<AccountId> {
+    pub fn can_transfer(&self, from: &AccountId, to: &AccountId) -> bool {
+        match self.transfer_policy {
+            TransferPolicy::Transferable => true,
+            TransferPolicy::Soulbound => false,
+            TransferPolicy::IssuerOnly => *from == self.issuer || *to == self.issuer,
+        }
+    }
+
+    /// Whether `owner` may list a token for sale to an arbitrary buyer
+    pub fn can_offer(&self, owner: &AccountId) -> bool {
+        match self.transfer_policy {
+            TransferPolicy::Transferable => true,
+            TransferPolicy::Soulbound => false,
+            TransferPolicy::IssuerOnly => *owner == self.issuer,
+        }
+    }
+}
+
+/// Class managers besides the owner in `ClassInfo::issuer`
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
+pub struct ClassRoles<AccountId> {
+    /// Updates class metadata, attributes and minters
+    pub admin: AccountId,
+    /// Freezes class metadata
+    pub freezer: AccountId,
 }
 
 /// NFT
@@ -74,6 +155,8 @@ pub enum NFTStatus {
     Burned,
     Offered,
     InCollection,
+    Locked,
+    Fractionalized,
 }
 
 #[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
@@ -88,6 +171,96 @@ pub struct NFTInfo<AccountId, Balance> {
     pub status: NFTStatus,
 }
 
+/// Royalty paid to `beneficiary` on every secondary sale
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
+pub struct RoyaltyInfo<AccountId> {
+    pub beneficiary: AccountId,
+    pub rate: Perbill,
+}
+
+/// Temporary user of an NFT, valid until `expiry`
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
+pub struct NFTUser<AccountId, BlockNumber> {
+    pub user: AccountId,
+    pub expiry: BlockNumber,
+}
+
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
+pub struct RentOffer<Balance, BlockNumber> {
+    pub price_per_block: Balance,
+    pub max_duration: BlockNumber,
+}
+
+/// Mint voucher signed off-chain by the class issuer, redeemed on-chain by the buyer
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
+pub struct MintVoucher<Balance, BlockNumber> {
+    pub class_id: ClassId,
+    pub metadata: Vec<u8>,
+    pub price: Balance,
+    pub nonce: u64,
+    pub expiry: BlockNumber,
+}
+
+/// Public mint window of a class, paid to the class issuer
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
+pub struct MintDrop<Balance, BlockNumber> {
+    pub start: BlockNumber,
+    pub end: BlockNumber,
+    pub price: Balance,
+    pub per_account_limit: u32,
+    /// Merkle root of `blake2_256(account)` leaves, hashed in sorted pairs
+    pub allowlist_root: Option<[u8; 32]>,
+}
+
+impl<Balance, BlockNumber: PartialOrd> MintDrop<Balance, BlockNumber> {
+    pub fn is_open(&self, now: &BlockNumber) -> bool {
+        self.start <= *now && *now < self.end
+    }
+}
+
+/// Blind-box mode of a class: metadata is committed by hash and assigned at `reveal_block`
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
+pub struct MysteryBox<BlockNumber> {
+    /// `blake2_256` of the SCALE-encoded `Vec<Vec<u8>>` metadata set
+    pub metadata_hash: [u8; 32],
+    /// Entries in the metadata set, no more NFTs can be minted than this
+    pub metadata_len: u64,
+    pub reveal_block: BlockNumber,
+    /// Shuffle seed, fixed by the first reveal attempt at or after `reveal_block`
+    pub seed: Option<[u8; 32]>,
+    pub revealed: bool,
+}
+
+/// Funds reserved from `depositor` while a class, NFT, token or collection is stored
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
+pub struct StorageDeposit<AccountId, Balance> {
+    pub depositor: AccountId,
+    pub amount: Balance,
+}
+
+/// Class or NFT an attribute belongs to
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
+pub enum AttributeTarget {
+    Class(ClassId),
+    NFT(NFTId),
+}
+
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
+pub enum AttributeValue {
+    Bytes(Vec<u8>),
+    Text(Vec<u8>),
+    Integer(i128),
+    Bool(bool),
+}
+
+/// Attribute value with the deposit reserved from `depositor` for its bytes
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
+pub struct Attribute<AccountId, Balance> {
+    pub value: AttributeValue,
+    pub depositor: AccountId,
+    pub deposit: Balance,
+}
+
 #[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
 pub struct NFTSource<ClassId> {
     pub class_id: ClassId,
@@ -117,10 +290,28 @@ pub trait NFT721Manager<AccountId, Balance> {
 
     fn get_nft(nft_id: NFTId) -> Option<NFTInfo<AccountId, Balance>>;
 
+    fn royalty_of(nft_id: NFTId) -> Option<RoyaltyInfo<AccountId>>;
+
+    fn user_of(nft_id: NFTId) -> Option<AccountId>;
+
     // Todo safeTransfer
     fn transfer_single_nft(from: AccountId, to: AccountId, nft_id: NFTId) -> DispatchResult;
 
     fn destroy_single_nft(who: AccountId, nft_id: NFTId) -> DispatchResult;
+
+    // Approval
+    fn approve_single_nft(who: AccountId, to: AccountId, nft_id: NFTId) -> DispatchResult;
+
+    fn set_approval_for_all(who: AccountId, to: AccountId, approved: bool) -> DispatchResult;
+
+    fn is_approved_or_owner(spender: AccountId, nft_id: NFTId) -> bool;
+
+    fn transfer_from(
+        who: AccountId,
+        from: AccountId,
+        to: AccountId,
+        nft_id: NFTId,
+    ) -> DispatchResult;
 }
 
 pub trait NFT1155Manager<AccountId, Balance> {
@@ -145,6 +336,10 @@ pub trait NFT1155Manager<AccountId, Balance> {
 
     fn get_nft(nft_id: NFTId) -> Option<NFTInfo<AccountId, Balance>>;
 
+    fn royalty_of(nft_id: NFTId) -> Option<RoyaltyInfo<AccountId>>;
+
+    fn user_of(nft_id: NFTId) -> Option<AccountId>;
+
     fn get_nft_by_index(class_id: ClassId, index: u64) -> Option<NFTId>;
 
     // fn owned_nfts(account: AccountId) -> Vec<NFTSource<ClassId>>;
@@ -179,17 +374,18 @@ pub enum CollectionStatus {
     Normal = 0,
     Decoupled,
     Burned,
+    Offered,
 }
 
 #[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
-pub struct Collection<AccountId, Balance, CollectionStatus, ClassId> {
+pub struct Collection<AccountId, Balance, CollectionStatus> {
     pub name: Vec<u8>,
     pub symbol: Vec<u8>,
     pub info: Vec<u8>,
     pub owner: AccountId,
     pub issuer: AccountId,
     pub price: Balance,
-    pub source: Vec<NFTSource<ClassId>>,
+    pub members: Vec<(NFTType, NFTId)>,
     pub status: CollectionStatus,
 }
 
@@ -215,6 +411,10 @@ pub trait NFT2006Manager<AccountId, Balance> {
 
     fn get_nft(nft_id: NFTId) -> Option<NFTInfo<AccountId, Balance>>;
 
+    fn royalty_of(nft_id: NFTId) -> Option<RoyaltyInfo<AccountId>>;
+
+    fn user_of(nft_id: NFTId) -> Option<AccountId>;
+
     fn get_nft_by_index(class_id: ClassId, index: u64) -> Option<NFTId>;
 
     // fn owned_nfts(account: AccountId) -> Vec<NFTSource<ClassId>>;
@@ -249,12 +449,12 @@ pub trait NFT2006Manager<AccountId, Balance> {
         owner: AccountId,
         info: Vec<u8>,
         price: Balance,
-        source: Vec<NFTSource<ClassId>>,
+        members: Vec<(NFTType, NFTId)>,
     ) -> DispatchResult;
 
     fn get_collection(
         collection_id: CollectionId,
-    ) -> Option<Collection<AccountId, Balance, CollectionStatus, ClassId>>;
+    ) -> Option<Collection<AccountId, Balance, CollectionStatus>>;
 
     fn owned_collections(account: AccountId) -> Vec<CollectionId>;
 
@@ -267,7 +467,11 @@ pub trait NFT2006Manager<AccountId, Balance> {
         collection_id: CollectionId,
     ) -> DispatchResult;
 
-    fn destroy_collection(who: AccountId, collection_id: CollectionId) -> DispatchResult;
+    fn destroy_collection(
+        who: AccountId,
+        collection_id: CollectionId,
+        burn_nfts: bool,
+    ) -> DispatchResult;
 
     fn approve_collection(
         who: AccountId,
@@ -283,6 +487,222 @@ pub enum NFTType {
     NFT1155,
     NFT2006,
 }
+
+/// What every NFT standard supports, implemented by each NFT pallet
+pub trait NonFungibleManager<AccountId, Balance> {
+    // Inspect
+    fn get_class(class_id: ClassId) -> Option<ClassInfo<AccountId>>;
+
+    fn get_nft(nft_id: NFTId) -> Option<NFTInfo<AccountId, Balance>>;
+
+    fn owner_of(nft_id: NFTId) -> Option<AccountId>;
+
+    fn royalty_of(nft_id: NFTId) -> Option<RoyaltyInfo<AccountId>>;
+
+    fn user_of(nft_id: NFTId) -> Option<AccountId>;
+
+    // Mint
+    fn mint_nft(
+        class_id: ClassId,
+        info: Vec<u8>,
+        metadata: Vec<u8>,
+        price: Balance,
+        miner: AccountId,
+    ) -> Option<NFTId>;
+
+    // Transfer
+    fn transfer_single_nft(from: AccountId, to: AccountId, nft_id: NFTId) -> DispatchResult;
+
+    /// A locked NFT can't be transferred, listed or burned until unlocked
+    fn lock_nft(nft_id: NFTId) -> DispatchResult;
+
+    fn unlock_nft(nft_id: NFTId) -> DispatchResult;
+
+    // Burn
+    fn burn_nft(who: AccountId, nft_id: NFTId) -> DispatchResult;
+
+    // Approval
+    fn approve_single_nft(who: AccountId, to: AccountId, nft_id: NFTId) -> DispatchResult;
+
+    // Composition
+    fn parent_of(nft_id: NFTId) -> Option<(NFTType, NFTId)>;
+
+    fn children_of(nft_id: NFTId) -> Vec<(NFTType, NFTId)>;
+
+    /// Parent-side bookkeeping only; the child's pallet checks and locks the child
+    fn add_child(nft_id: NFTId, child: (NFTType, NFTId)) -> DispatchResult;
+
+    fn remove_child(nft_id: NFTId, child: (NFTType, NFTId)) -> DispatchResult;
+}
+
+/// `NonFungibleManager` across all standards, the standard picked by `NFTType`
+pub trait MultiNonFungibleManager<AccountId, Balance> {
+    fn get_class(nft_type: NFTType, class_id: ClassId) -> Option<ClassInfo<AccountId>>;
+
+    fn get_nft(nft_type: NFTType, nft_id: NFTId) -> Option<NFTInfo<AccountId, Balance>>;
+
+    fn owner_of(nft_type: NFTType, nft_id: NFTId) -> Option<AccountId>;
+
+    fn royalty_of(nft_type: NFTType, nft_id: NFTId) -> Option<RoyaltyInfo<AccountId>>;
+
+    fn user_of(nft_type: NFTType, nft_id: NFTId) -> Option<AccountId>;
+
+    fn mint_nft(
+        nft_type: NFTType,
+        class_id: ClassId,
+        info: Vec<u8>,
+        metadata: Vec<u8>,
+        price: Balance,
+        miner: AccountId,
+    ) -> Option<NFTId>;
+
+    fn transfer_single_nft(
+        nft_type: NFTType,
+        from: AccountId,
+        to: AccountId,
+        nft_id: NFTId,
+    ) -> DispatchResult;
+
+    fn lock_nft(nft_type: NFTType, nft_id: NFTId) -> DispatchResult;
+
+    fn unlock_nft(nft_type: NFTType, nft_id: NFTId) -> DispatchResult;
+
+    fn burn_nft(nft_type: NFTType, who: AccountId, nft_id: NFTId) -> DispatchResult;
+
+    fn approve_single_nft(
+        nft_type: NFTType,
+        who: AccountId,
+        to: AccountId,
+        nft_id: NFTId,
+    ) -> DispatchResult;
+
+    fn parent_of(nft_type: NFTType, nft_id: NFTId) -> Option<(NFTType, NFTId)>;
+
+    fn children_of(nft_type: NFTType, nft_id: NFTId) -> Vec<(NFTType, NFTId)>;
+
+    fn add_child(nft_type: NFTType, nft_id: NFTId, child: (NFTType, NFTId)) -> DispatchResult;
+
+    fn remove_child(nft_type: NFTType, nft_id: NFTId, child: (NFTType, NFTId)) -> DispatchResult;
+
+    /// The NFT followed by its ancestors, ending with the root
+    fn lineage_of(nft_type: NFTType, nft_id: NFTId) -> Vec<(NFTType, NFTId)> {
+        let mut lineage = vec![(nft_type, nft_id)];
+        while let Some(parent) = lineage
+            .last()
+            .and_then(|(nft_type, nft_id)| Self::parent_of(*nft_type, nft_id.clone()))
+        {
+            lineage.push(parent);
+        }
+        lineage
+    }
+
+    /// Levels of descendants below the NFT, 0 for a leaf
+    fn height_of(nft_type: NFTType, nft_id: NFTId) -> u32 {
+        Self::children_of(nft_type, nft_id)
+            .into_iter()
+            .map(|(child_type, child_id)| Self::height_of(child_type, child_id) + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Routes `MultiNonFungibleManager` calls to the pallet handling each `NFTType`
+pub struct NonFungibleAdapter<NFT721, NFT1155, NFT2006>(PhantomData<(NFT721, NFT1155, NFT2006)>);
+
+macro_rules! route_by_type {
+    ($nft_type:expr, $method:ident($($arg:expr),*)) => {
+        match $nft_type {
+            NFTType::NFT721 => NFT721::$method($($arg),*),
+            NFTType::NFT1155 => NFT1155::$method($($arg),*),
+            NFTType::NFT2006 => NFT2006::$method($($arg),*),
+        }
+    };
+}
+
+impl<AccountId, Balance, NFT721, NFT1155, NFT2006> MultiNonFungibleManager<AccountId, Balance>
+    for NonFungibleAdapter<NFT721, NFT1155, NFT2006>
+where
+    NFT721: NonFungibleManager<AccountId, Balance>,
+    NFT1155: NonFungibleManager<AccountId, Balance>,
+    NFT2006: NonFungibleManager<AccountId, Balance>,
+{
+    fn get_class(nft_type: NFTType, class_id: ClassId) -> Option<ClassInfo<AccountId>> {
+        route_by_type!(nft_type, get_class(class_id))
+    }
+
+    fn get_nft(nft_type: NFTType, nft_id: NFTId) -> Option<NFTInfo<AccountId, Balance>> {
+        route_by_type!(nft_type, get_nft(nft_id))
+    }
+
+    fn owner_of(nft_type: NFTType, nft_id: NFTId) -> Option<AccountId> {
+        route_by_type!(nft_type, owner_of(nft_id))
+    }
+
+    fn royalty_of(nft_type: NFTType, nft_id: NFTId) -> Option<RoyaltyInfo<AccountId>> {
+        route_by_type!(nft_type, royalty_of(nft_id))
+    }
+
+    fn user_of(nft_type: NFTType, nft_id: NFTId) -> Option<AccountId> {
+        route_by_type!(nft_type, user_of(nft_id))
+    }
+
+    fn mint_nft(
+        nft_type: NFTType,
+        class_id: ClassId,
+        info: Vec<u8>,
+        metadata: Vec<u8>,
+        price: Balance,
+        miner: AccountId,
+    ) -> Option<NFTId> {
+        route_by_type!(nft_type, mint_nft(class_id, info, metadata, price, miner))
+    }
+
+    fn transfer_single_nft(
+        nft_type: NFTType,
+        from: AccountId,
+        to: AccountId,
+        nft_id: NFTId,
+    ) -> DispatchResult {
+        route_by_type!(nft_type, transfer_single_nft(from, to, nft_id))
+    }
+
+    fn lock_nft(nft_type: NFTType, nft_id: NFTId) -> DispatchResult {
+        route_by_type!(nft_type, lock_nft(nft_id))
+    }
+
+    fn unlock_nft(nft_type: NFTType, nft_id: NFTId) -> DispatchResult {
+        route_by_type!(nft_type, unlock_nft(nft_id))
+    }
+
+    fn burn_nft(nft_type: NFTType, who: AccountId, nft_id: NFTId) -> DispatchResult {
+        route_by_type!(nft_type, burn_nft(who, nft_id))
+    }
+
+    fn approve_single_nft(
+        nft_type: NFTType,
+        who: AccountId,
+        to: AccountId,
+        nft_id: NFTId,
+    ) -> DispatchResult {
+        route_by_type!(nft_type, approve_single_nft(who, to, nft_id))
+    }
+
+    fn parent_of(nft_type: NFTType, nft_id: NFTId) -> Option<(NFTType, NFTId)> {
+        route_by_type!(nft_type, parent_of(nft_id))
+    }
+
+    fn children_of(nft_type: NFTType, nft_id: NFTId) -> Vec<(NFTType, NFTId)> {
+        route_by_type!(nft_type, children_of(nft_id))
+    }
+
+    fn add_child(nft_type: NFTType, nft_id: NFTId, child: (NFTType, NFTId)) -> DispatchResult {
+        route_by_type!(nft_type, add_child(nft_id, child))
+    }
+
+    fn remove_child(nft_type: NFTType, nft_id: NFTId, child: (NFTType, NFTId)) -> DispatchResult {
+        route_by_type!(nft_type, remove_child(nft_id, child))
+    }
+}
 /// DAO
 #[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, RuntimeDebug)]
 pub enum ProposalTheme {
@@ -414,41 +834,121 @@ pub trait CommonManager<AccountId> {
 
 /// token
 #[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug)]
-pub struct Token<AccountId> {
+pub struct Token<AccountId, Balance> {
     pub tid: Did,
     pub owner: AccountId,
     pub symbol: Vec<u8>,
-    pub total_supply: u64,
+    pub total_supply: Balance,
 }
 
-pub trait TokenManager<AccountId> {
+/// What wallets show for a token; only the DAO sets `verified`
+#[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug, Default)]
+pub struct TokenMetadata {
+    pub name: Vec<u8>,
+    pub symbol: Vec<u8>,
+    pub decimals: u8,
+    pub logo_uri: Option<Vec<u8>>,
+    pub verified: bool,
+}
+
+/// Token managers besides the owner in `Token::owner`
+#[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug)]
+pub struct TokenRoles<AccountId> {
+    pub minter: AccountId,
+    pub burner: AccountId,
+    /// Freezes and thaws holder accounts
+    pub freezer: AccountId,
+}
+
+/// Fractional vault
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum VaultStatus {
+    Active = 0,
+    Auction,
+}
+
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone)]
+pub struct Vault<AccountId, Balance, BlockNumber, TokenBalance> {
+    pub token_id: Did,
+    pub curator: AccountId,
+    pub total_supply: TokenBalance,
+    pub reserve_price: Balance,
+    pub status: VaultStatus,
+    pub bidder: Option<AccountId>,
+    pub bid: Balance,
+    pub auction_end: BlockNumber,
+}
+
+pub trait TokenManager<AccountId, Balance> {
     // issue
-    fn issue(from: AccountId, total_supply: u64, symbol: Vec<u8>) -> Did;
+    fn issue(
+        from: AccountId,
+        total_supply: Balance,
+        symbol: Vec<u8>,
+    ) -> Result<Did, dispatch::DispatchError>;
+    /// `holder` gets the supply; `owner` keeps every role and the supply can't grow
+    fn issue_capped(
+        owner: AccountId,
+        holder: AccountId,
+        total_supply: Balance,
+        symbol: Vec<u8>,
+    ) -> Result<Did, dispatch::DispatchError>;
 
     // transfer
     fn transfer(
         from: AccountId,
         to: AccountId,
         token_id: Did,
-        value: u64,
+        value: Balance,
         memo: Option<Vec<u8>>,
     ) -> DispatchResult;
 
     // transfer
-    fn static_transfer_in(from: AccountId, to: Did, token_id: Did, value: u64) -> DispatchResult;
-    fn static_transfer_out(from: Did, to: AccountId, token_id: Did, value: u64) -> DispatchResult;
+    fn static_transfer_in(
+        from: AccountId,
+        to: Did,
+        token_id: Did,
+        value: Balance,
+    ) -> DispatchResult;
+    fn static_transfer_out(
+        from: Did,
+        to: AccountId,
+        token_id: Did,
+        value: Balance,
+    ) -> DispatchResult;
 
     // freeze
-    fn freeze(from: AccountId, token_id: Did, value: u64) -> DispatchResult;
+    fn freeze(from: AccountId, token_id: Did, value: Balance) -> DispatchResult;
 
     // unfreeze
-    fn unfreeze(from: AccountId, token_id: Did, value: u64) -> DispatchResult;
+    fn unfreeze(from: AccountId, token_id: Did, value: Balance) -> DispatchResult;
+
+    // burn
+    fn burn(from: AccountId, token_id: Did, value: Balance) -> DispatchResult;
+
+    // allowance
+    fn approve(
+        owner: AccountId,
+        spender: AccountId,
+        token_id: Did,
+        value: Balance,
+    ) -> DispatchResult;
+    /// Spends `value` of the allowance `from` gave to `spender`
+    fn transfer_from(
+        spender: AccountId,
+        from: AccountId,
+        to: AccountId,
+        token_id: Did,
+        value: Balance,
+    ) -> DispatchResult;
+    fn allowance(owner: AccountId, spender: AccountId, token_id: Did) -> Balance;
 
     // query
-    fn balance_of(from: AccountId, token_id: Did) -> u64;
-    fn static_balance_of(from: Did, token_id: Did) -> u64;
+    fn balance_of(from: AccountId, token_id: Did) -> Balance;
+    fn static_balance_of(from: Did, token_id: Did) -> Balance;
     fn owner_of(token_id: Did) -> Option<AccountId>;
-    fn ensure_free_balance(sender: AccountId, token_id: Did, amount: u64) -> DispatchResult;
+    fn total_supply(token_id: Did) -> Balance;
+    fn ensure_free_balance(sender: AccountId, token_id: Did, amount: Balance) -> DispatchResult;
 }
 /// order
 #[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, RuntimeDebug)]
@@ -513,29 +1013,29 @@ pub enum OrderStatus {
 }
 
 #[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug)]
-pub struct LiquidityPool {
+pub struct LiquidityPool<Balance> {
     pub tpid: Did,
     pub token0: Did,
     pub token1: Did,
-    pub token0_amount: u64,
-    pub token1_amount: u64,
-    pub k_last: u64, // k_last = token0_amount * token1_amount
-    pub swap_price_last: u64,
-    pub swap_price_highest: u64,
-    pub swap_price_lowest: u64,
-    pub token0_trade_volume_total: u64,
-    pub token1_trade_volume_total: u64,
+    pub token0_amount: Balance,
+    pub token1_amount: Balance,
+    pub k_last: Balance, // k_last = token0_amount * token1_amount
+    pub swap_price_last: Balance,
+    pub swap_price_highest: Balance,
+    pub swap_price_lowest: Balance,
+    pub token0_trade_volume_total: Balance,
+    pub token1_trade_volume_total: Balance,
 }
 
 #[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug)]
-pub struct AmmOrder<AccountId> {
+pub struct AmmOrder<AccountId, Balance> {
     pub lpid: Did,
     pub owner: AccountId,
     pub token_have: Did,
-    pub token_have_amount: u64,
+    pub token_have_amount: Balance,
     pub token_want: Did,
-    pub token_want_amount: u64,
-    pub token_swap_price: u64,
+    pub token_want_amount: Balance,
+    pub token_swap_price: Balance,
 }
 
 #[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug)]
@@ -547,26 +1047,26 @@ pub struct OrderQueueInfo {
 }
 
 #[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug)]
-pub struct LimitOrder<AccountId, Moment> {
+pub struct LimitOrder<AccountId, Moment, Balance> {
     pub tpid: Did,
     pub owner: AccountId,
-    pub price: u64,
-    pub amount: u64,
+    pub price: Balance,
+    pub amount: Balance,
     pub created_time: Moment,
-    pub remained_amount: u64,
+    pub remained_amount: Balance,
     pub otype: OrderType,
     pub status: OrderStatus,
 }
 
 #[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug)]
-pub struct Trade<AccountId> {
+pub struct Trade<AccountId, Balance> {
     pub tpid: Did,
     pub buyer: AccountId,
     pub seller: AccountId,
-    pub price: u64,
+    pub price: Balance,
     pub otype: OrderType,
-    pub base_amount: u64,
-    pub quote_amount: u64,
+    pub base_amount: Balance,
+    pub quote_amount: Balance,
 }
 
 #[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, RuntimeDebug)]
@@ -597,6 +1097,24 @@ pub struct Auction<AccountId, Balance, Moment> {
     pub status: AuctionStatus,
 }
 
+/// Buyer-initiated offer on any NFT, backed by reserved funds
+#[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug)]
+pub struct NFTOffer<AccountId, Balance, BlockNumber> {
+    pub bidder: AccountId,
+    pub nft_type: NFTType,
+    pub nft_id: NFTId,
+    pub amount: Balance,
+    pub expiry: BlockNumber,
+}
+
+/// NFTs of any standard sold together for one price, locked while listed
+#[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug)]
+pub struct Bundle<AccountId, Balance> {
+    pub seller: AccountId,
+    pub items: Vec<(NFTType, NFTId)>,
+    pub price: Balance,
+}
+
 #[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug)]
 pub struct BidInfo<AccountId, Balance, Moment> {
     pub bidder: AccountId,
@@ -17,7 +17,7 @@ use sp_runtime::traits::{
 use sp_runtime::{
     create_runtime_str, generic, impl_opaque_keys,
     transaction_validity::{TransactionSource, TransactionValidity},
-    ApplyExtrinsicResult, MultiSignature,
+    ApplyExtrinsicResult, ModuleId, MultiSignature,
 };
 use sp_std::prelude::*;
 #[cfg(feature = "std")]
@@ -292,22 +292,98 @@ impl pallet_dao::Config for Runtime {
     type Event = Event;
     type Currency = Balances;
 }
+parameter_types! {
+    pub const MaxNestingDepth: u32 = 5;
+    pub const AttributeDepositBase: Balance = 10_000;
+    pub const AttributeDepositPerByte: Balance = 100;
+    pub const AttributeKeyLimit: u32 = 64;
+    pub const AttributeValueLimit: u32 = 256;
+    pub const ClassDepositBase: Balance = 100_000;
+    pub const NFTDepositBase: Balance = 10_000;
+    pub const CollectionDepositBase: Balance = 10_000;
+    pub const TokenDepositBase: Balance = 100_000;
+    pub const DepositPerByte: Balance = 100;
+    pub const NameLimit: u32 = 64;
+    pub const InfoLimit: u32 = 1024;
+    pub const MaxClassMinters: u32 = 32;
+    pub const MaxBundleItems: u32 = 16;
+    pub const MaxExpiriesPerBlock: u32 = 64;
+    pub const SymbolLimit: u32 = 16;
+    pub const UriLimit: u32 = 256;
+}
+
 impl pallet_nft721::Config for Runtime {
     type Event = Event;
     type Currency = Balances;
+    type Signature = Signature;
+    type PublicKey = <Signature as Verify>::Signer;
+    type NFT = utilities::NonFungibleAdapter<NFT721Module, NFT1155Module, NFT2006Module>;
+    type MaxNestingDepth = MaxNestingDepth;
+    type AttributeDepositBase = AttributeDepositBase;
+    type AttributeDepositPerByte = AttributeDepositPerByte;
+    type AttributeKeyLimit = AttributeKeyLimit;
+    type AttributeValueLimit = AttributeValueLimit;
+    type ClassDepositBase = ClassDepositBase;
+    type NFTDepositBase = NFTDepositBase;
+    type DepositPerByte = DepositPerByte;
+    type NameLimit = NameLimit;
+    type InfoLimit = InfoLimit;
+    type MaxClassMinters = MaxClassMinters;
+    type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+    type Randomness = RandomnessCollectiveFlip;
 }
 impl pallet_nft1155::Config for Runtime {
     type Event = Event;
     type Currency = Balances;
+    type Signature = Signature;
+    type PublicKey = <Signature as Verify>::Signer;
+    type NFT = utilities::NonFungibleAdapter<NFT721Module, NFT1155Module, NFT2006Module>;
+    type MaxNestingDepth = MaxNestingDepth;
+    type AttributeDepositBase = AttributeDepositBase;
+    type AttributeDepositPerByte = AttributeDepositPerByte;
+    type AttributeKeyLimit = AttributeKeyLimit;
+    type AttributeValueLimit = AttributeValueLimit;
+    type ClassDepositBase = ClassDepositBase;
+    type NFTDepositBase = NFTDepositBase;
+    type DepositPerByte = DepositPerByte;
+    type NameLimit = NameLimit;
+    type InfoLimit = InfoLimit;
+    type MaxClassMinters = MaxClassMinters;
+    type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+    type Randomness = RandomnessCollectiveFlip;
 }
+parameter_types! {
+    pub const NFT2006ModuleId: ModuleId = ModuleId(*b"dnft/frg");
+    pub const BuyoutPeriod: BlockNumber = 3 * DAYS;
+}
+
 impl pallet_nft2006::Config for Runtime {
     type Event = Event;
     type Currency = Balances;
+    type Balance = u64;
     type Token = pallet_token::Module<Runtime>;
+    type NFT = utilities::NonFungibleAdapter<NFT721Module, NFT1155Module, NFT2006Module>;
+    type ModuleId = NFT2006ModuleId;
+    type BuyoutPeriod = BuyoutPeriod;
+    type MaxNestingDepth = MaxNestingDepth;
+    type AttributeDepositBase = AttributeDepositBase;
+    type AttributeDepositPerByte = AttributeDepositPerByte;
+    type AttributeKeyLimit = AttributeKeyLimit;
+    type AttributeValueLimit = AttributeValueLimit;
+    type ClassDepositBase = ClassDepositBase;
+    type NFTDepositBase = NFTDepositBase;
+    type CollectionDepositBase = CollectionDepositBase;
+    type DepositPerByte = DepositPerByte;
+    type NameLimit = NameLimit;
+    type InfoLimit = InfoLimit;
+    type MaxClassMinters = MaxClassMinters;
+    type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+    type Randomness = RandomnessCollectiveFlip;
 }
 impl pallet_swap_amm::Config for Runtime {
     type Event = Event;
     type Common = pallet_common::Module<Runtime>;
+    type Balance = u64;
     type Token = pallet_token::Module<Runtime>;
     type TradePair = pallet_trade_pair::Module<Runtime>;
 }
@@ -315,32 +391,40 @@ impl pallet_swap_auction::Config for Runtime {
     type Event = Event;
     type Time = Timestamp;
     type Currency = Balances;
-    type NFT721 = NFT721Module;
-    type NFT1155 = NFT1155Module;
-    type NFT2006 = NFT2006Module;
+    type NFT = utilities::NonFungibleAdapter<NFT721Module, NFT1155Module, NFT2006Module>;
+    type MaxBundleItems = MaxBundleItems;
+    type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
 }
 impl pallet_swap_orderbook::Config for Runtime {
     type Event = Event;
     type Time = Timestamp;
     type Common = pallet_common::Module<Runtime>;
+    type Balance = u64;
     type Token = pallet_token::Module<Runtime>;
     type TradePair = pallet_trade_pair::Module<Runtime>;
 }
 impl pallet_tax::Config for Runtime {
     type Event = Event;
     type Currency = Balances;
-    type NFT721 = NFT721Module;
-    type NFT1155 = NFT1155Module;
-    type NFT2006 = NFT2006Module;
+    type NFT = utilities::NonFungibleAdapter<NFT721Module, NFT1155Module, NFT2006Module>;
     type DAO = DAOModule;
 }
 impl pallet_token::Config for Runtime {
     type Event = Event;
     type Common = pallet_common::Module<Runtime>;
+    type Balance = u64;
+    type Currency = Balances;
+    type TokenDepositBase = TokenDepositBase;
+    type DepositPerByte = DepositPerByte;
+    type SymbolLimit = SymbolLimit;
+    type NameLimit = NameLimit;
+    type UriLimit = UriLimit;
+    type DAO = DAOModule;
 }
 impl pallet_trade_pair::Config for Runtime {
     type Event = Event;
     type Common = pallet_common::Module<Runtime>;
+    type Balance = u64;
     type Token = pallet_token::Module<Runtime>;
 }
 
@@ -363,9 +447,9 @@ construct_runtime!(
         AIModule: pallet_ai::{Module, Call, Storage, Event<T>},
         CommonModule: pallet_common::{Module, Call, Storage, Event<T>},
         DAOModule: pallet_dao::{Module, Call, Storage, Event<T>},
-        NFT721Module: pallet_nft721::{Module, Call, Storage, Event<T>},
-        NFT1155Module: pallet_nft1155::{Module, Call, Storage, Event<T>},
-        NFT2006Module: pallet_nft2006::{Module, Call, Storage, Event<T>},
+        NFT721Module: pallet_nft721::{Module, Call, Storage, Config, Event<T>},
+        NFT1155Module: pallet_nft1155::{Module, Call, Storage, Config, Event<T>},
+        NFT2006Module: pallet_nft2006::{Module, Call, Storage, Config, Event<T>},
         SwapAMMModule: pallet_swap_amm::{Module, Call, Storage, Event<T>},
         SwapAuctionModule: pallet_swap_auction::{Module, Call, Storage, Event<T>},
         SwapOrderBookModule: pallet_swap_orderbook::{Module, Call, Storage, Event<T>},
@@ -517,6 +601,12 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_token_runtime_api::TokenApi<Block> for Runtime {
+        fn token_by_symbol(symbol: Vec<u8>) -> Option<(utilities::Did, utilities::TokenMetadata)> {
+            TokenModule::token_by_symbol(symbol)
+        }
+    }
+
     impl frame_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
         fn account_nonce(account: AccountId) -> Index {
             System::account_nonce(account)